@@ -0,0 +1,39 @@
+use criterion::{ black_box, criterion_group, criterion_main, Criterion };
+use xylosip::{ Request, RequestRef };
+
+/// Builds an INVITE carrying a body of `len` bytes
+fn invite_with_body(len: usize) -> Vec<u8> {
+    let mut bytes = format!("INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+Content-Type: multipart/mixed;boundary=unique-boundary-1\r\n\
+Content-Length: {}\r\n\
+\r\n", len).into_bytes();
+    bytes.resize(bytes.len() + len, b'x');
+
+    bytes
+}
+
+fn parse_body(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_body");
+
+    for len in [1024, 64 * 1024].iter() {
+        let bytes = invite_with_body(*len);
+
+        group.bench_function(format!("Request/{}", len), |b| {
+            b.iter(|| Request::parse(black_box(&bytes)).unwrap())
+        });
+        group.bench_function(format!("RequestRef/{}", len), |b| {
+            b.iter(|| RequestRef::parse(black_box(&bytes)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_body);
+criterion_main!(benches);
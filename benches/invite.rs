@@ -0,0 +1,22 @@
+use criterion::{ black_box, criterion_group, criterion_main, Criterion };
+use xylosip::{ HeaderIter, Message, Request };
+
+fn parse_invite(c: &mut Criterion) {
+    let bytes = include_bytes!("../assets/invite.sip");
+    let mut group = c.benchmark_group("parse_invite");
+
+    group.bench_function("Request", |b| {
+        b.iter(|| Request::parse(black_box(bytes)).unwrap())
+    });
+    group.bench_function("Message", |b| {
+        b.iter(|| Message::parse(black_box(bytes)).unwrap())
+    });
+    group.bench_function("HeaderIter", |b| {
+        b.iter(|| HeaderIter::new(black_box(bytes)).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_invite);
+criterion_main!(benches);
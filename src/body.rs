@@ -0,0 +1,117 @@
+use crate::prelude::*;
+use crate::{
+    header::{ Header, Media, MediaType },
+    parser::{ rfc3261, Error, ErrorKind },
+};
+
+/// A single part of a multipart body
+#[derive(PartialEq, Debug, Clone)]
+pub struct BodyPart {
+    /// the headers of the part. Headers that aren't SIP headers (e.g. Content-ID) are kept as
+    /// `Header::Extension`
+    pub headers: Vec<Header>,
+
+    /// the content of the part. This is completely unparsed and unvalidated.
+    pub body: Vec<u8>,
+}
+
+impl BodyPart {
+    /// Returns the Content-Type of the part, if any
+    pub fn content_type(&self) -> Option<&Media> {
+        self.headers.iter().find_map(|header| match header {
+            Header::ContentType(media) => Some(media),
+            _ => None,
+        })
+    }
+
+    /// Returns the Content-ID of the part, without its enclosing angle brackets
+    pub fn content_id(&self) -> Option<&str> {
+        self.headers.iter().find_map(|header| match header {
+            Header::Extension(name, value) if name.eq_ignore_ascii_case("Content-ID") => {
+                let value = value.trim();
+                Some(value.strip_prefix('<')
+                    .and_then(|value| value.strip_suffix('>'))
+                    .unwrap_or(value))
+            },
+            _ => None,
+        })
+    }
+
+    /// Whether the part is the one referenced by a `cid:` URI (RFC2392)
+    ///
+    /// The scheme is optional, so a bare Content-ID matches as well.
+    pub fn matches_cid(&self, cid: &str) -> bool {
+        let cid = match cid.get(..4) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("cid:") => &cid[4..],
+            _ => cid,
+        };
+
+        self.content_id() == Some(cid)
+    }
+}
+
+/// Splits a multipart body (e.g. `multipart/mixed` or `multipart/related`) into its parts
+///
+/// The parts are delimited by the boundary parameter of `content_type`. The preamble and the
+/// epilogue surrounding the parts are discarded. Fails if `content_type` isn't a multipart media
+/// type, if it has no boundary, or if the body is malformed.
+pub fn parse_multipart<'a>(content_type: &Media, body: &'a [u8]) -> Result<Vec<BodyPart>, Error<'a, &'a [u8]>> {
+    if content_type.r#type != MediaType::Multipart {
+        return Err(Error::new(ErrorKind::UnexpectedContentType));
+    }
+
+    let boundary = match content_type.param("boundary") {
+        Some(boundary) => boundary,
+        None => return Err(Error::new(ErrorKind::InvalidMultipartBody)),
+    };
+
+    match rfc3261::multipart_body(body, boundary.as_bytes()) {
+        Ok((_, parts)) => Ok(parts),
+        Err(nom::Err::Failure(err)) | Err(nom::Err::Error(err)) => Err(err),
+        Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::{ MediaParam, MediaSubType };
+
+    fn media(r#type: MediaType, subtype: &str, boundary: &str) -> Media {
+        Media {
+            r#type,
+            subtype: MediaSubType::IETFExtension(subtype.to_string()),
+            params: vec![MediaParam { name: "boundary".to_string(), value: boundary.to_string() }],
+        }
+    }
+
+    #[test]
+    fn parse_multipart_reads_every_part() {
+        let body = b"--unique-boundary-1\r\n\
+Content-Type: application/sdp\r\n\
+\r\n\
+v=0\r\n\
+--unique-boundary-1\r\n\
+Content-Type: application/ISUP;version=itu-t92+\r\n\
+\r\n\
+\x01\x00\x49\r\n\
+--unique-boundary-1--";
+        let parts = parse_multipart(&media(MediaType::Multipart, "related", "unique-boundary-1"), body).unwrap();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].body, b"v=0");
+        assert_eq!(parts[1].content_type().and_then(|media| media.param("version")), Some("itu-t92+"));
+        assert_eq!(parts[1].body, b"\x01\x00\x49");
+    }
+
+    #[test]
+    fn parse_multipart_rejects_other_media_types_and_malformed_bodies() {
+        let body = b"--boundary1\r\n\r\nv=0\r\n--boundary1--\r\n";
+
+        assert_eq!(
+            parse_multipart(&media(MediaType::Application, "sdp", "boundary1"), body).map_err(|err| err.kind),
+            Err(ErrorKind::UnexpectedContentType),
+        );
+        assert!(parse_multipart(&media(MediaType::Multipart, "mixed", "other"), body).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::prelude::*;
+use core::fmt;
+
+use crate::header::Header;
+
+/// Errors that can occur while serializing SIP data back to its wire format
+#[derive(PartialEq, Debug, Copy, Clone, thiserror::Error)]
+pub enum EncodeError {
+    #[error("header value contains a line break")]
+    HeaderInjection,
+}
+
+/// Validates a header value before it is written to the wire
+///
+/// Header values are always emitted on a single line, there is no folding. A value containing CR
+/// or LF would terminate the header early, and whatever follows would be interpreted as another
+/// header (or as the body), so such values are rejected.
+pub fn header_value(value: &str) -> Result<&str, EncodeError> {
+    if value.bytes().any(|c| c == b'\r' || c == b'\n') {
+        Err(EncodeError::HeaderInjection)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Encodes a display name as a quoted-string
+///
+/// Double quotes and backslashes are escaped. Line breaks can't be represented inside a
+/// quoted-string and are rejected.
+pub fn display_name(name: &str) -> Result<String, EncodeError> {
+    let name = header_value(name)?;
+
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('"');
+    for c in name.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    Ok(quoted)
+}
+
+/// A header written under a given name
+struct HeaderLine<'a>(&'a str, &'a Header);
+
+impl fmt::Display for HeaderLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.1.fmt_named(f, self.0)
+    }
+}
+
+/// Formats `value` on a single line, rejecting it if it spans several lines
+///
+/// Display names that can't be encoded make the formatting fail (see `display_name`), this is
+/// reported as an injection as well.
+fn line(value: &dyn fmt::Display) -> Result<String, EncodeError> {
+    let mut line = String::new();
+    fmt::Write::write_fmt(&mut line, format_args!("{}", value))
+        .map_err(|_| EncodeError::HeaderInjection)?;
+    header_value(&line)?;
+
+    Ok(line)
+}
+
+/// Serializes a message: its start line, its headers and its body
+///
+/// Any Content-Length header is rewritten to match the actual length of the body, and one is
+/// added after the other headers if none was present.
+///
+/// Each header comes with the name it is written under, see `NamedHeader::written_name`.
+///
+/// The start line and every header are checked with `header_value`, so that a value containing a
+/// line break can't inject headers into the message.
+pub(crate) fn message<'a>(
+    start_line: &dyn fmt::Display,
+    headers: impl Iterator<Item = (&'a str, &'a Header)>,
+    body: Option<&[u8]>,
+) -> Result<Vec<u8>, EncodeError> {
+    let body = body.unwrap_or_default();
+    let content_length = Header::ContentLength(body.len() as i32);
+
+    let mut bytes = Vec::new();
+    let mut push_line = |value: &dyn fmt::Display| -> Result<(), EncodeError> {
+        bytes.extend_from_slice(line(value)?.as_bytes());
+        bytes.extend_from_slice(b"\r\n");
+
+        Ok(())
+    };
+
+    push_line(start_line)?;
+    let mut has_content_length = false;
+    for (name, header) in headers {
+        let header = match header {
+            Header::ContentLength(_) => {
+                has_content_length = true;
+                &content_length
+            },
+            header => header,
+        };
+        push_line(&HeaderLine(name, header))?;
+    }
+
+    if !has_content_length {
+        push_line(&content_length)?;
+    }
+
+    bytes.extend_from_slice(b"\r\n");
+    bytes.extend_from_slice(body);
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::From;
+    use crate::sip::Uri;
+
+    #[test]
+    fn header_value_accepts_single_line() {
+        assert_eq!(header_value("SIP/2.0/UDP pc33.atlanta.com"), Ok("SIP/2.0/UDP pc33.atlanta.com"));
+    }
+
+    #[test]
+    fn header_value_rejects_line_breaks() {
+        assert_eq!(header_value("a\r\nVia: x"), Err(EncodeError::HeaderInjection));
+        assert_eq!(header_value("a\nb"), Err(EncodeError::HeaderInjection));
+        assert_eq!(header_value("a\rb"), Err(EncodeError::HeaderInjection));
+    }
+
+    #[test]
+    fn display_name_is_quoted_and_escaped() {
+        assert_eq!(display_name("Alice").unwrap(), "\"Alice\"");
+        assert_eq!(display_name("Doe, \"JD\" John").unwrap(), "\"Doe, \\\"JD\\\" John\"");
+        assert_eq!(display_name("back\\slash").unwrap(), "\"back\\\\slash\"");
+    }
+
+    #[test]
+    fn display_name_with_crlf_is_rejected() {
+        let from = From {
+            addr: Uri::Absolute("sip:alice@atlanta.example.com".to_string()),
+            name: Some("Alice\r\nContact: <sip:mallory@evil.example.com>".to_string()),
+            params: vec![],
+        };
+
+        assert_eq!(display_name(from.name.as_ref().unwrap()), Err(EncodeError::HeaderInjection));
+    }
+
+    #[test]
+    fn request_with_crlf_in_a_header_value_is_not_encoded() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = crate::request::Request::parse(bytes).unwrap();
+        assert!(req.to_bytes().is_ok());
+
+        let mut injected = req.clone();
+        injected.headers.push(
+            Header::Extension("Subject".to_string(), "lunch\r\nContact: <sip:mallory@evil.example.com>".to_string()).into()
+        );
+        assert_eq!(injected.to_bytes(), Err(EncodeError::HeaderInjection));
+
+        let mut injected = req;
+        for header in injected.headers.iter_mut() {
+            if let Header::From(from) = &mut header.header {
+                from.name = Some("Alice\r\nContact: <sip:mallory@evil.example.com>".to_string());
+            }
+        }
+        assert_eq!(injected.to_bytes(), Err(EncodeError::HeaderInjection));
+    }
+}
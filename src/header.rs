@@ -1,10 +1,66 @@
+use crate::prelude::*;
 use super::sip::*;
+use crate::parser::{ rfc3261, Error };
+
+use core::fmt;
+
+/// Writes `items`, separated by `separator`
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T], separator: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        item.fmt(f)?;
+    }
+
+    Ok(())
+}
+
+/// Writes each parameter, preceded by a semicolon
+fn write_params<T: fmt::Display>(f: &mut fmt::Formatter<'_>, params: &[T]) -> fmt::Result {
+    for param in params {
+        write!(f, ";{}", param)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a parameter value, quoting it unless it can be written as a token or an IPv6 reference
+///
+/// Quoted-strings are stored without their surrounding quotes but with their escapes, so they
+/// only need to be wrapped in quotes again.
+fn write_param_value(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    let is_token = !value.is_empty() && value.bytes()
+        .all(|c| c.is_ascii_alphanumeric() || b"-.!%*_+`'~".contains(&c));
+    let is_ipv6 = value.starts_with('[') && value.ends_with(']');
+
+    if is_token || is_ipv6 {
+        f.write_str(value)
+    } else {
+        write!(f, "\"{}\"", value)
+    }
+}
+
+/// Writes an address as a name-addr, i.e. with angle brackets, so that its parameters can't be
+/// mistaken for the header's own parameters
+///
+/// The display name is written as a quoted-string (see `encode::display_name`). Formatting fails
+/// if it contains a line break.
+fn write_name_addr(f: &mut fmt::Formatter<'_>, name: &Option<String>, addr: &Uri) -> fmt::Result {
+    if let Some(name) = name {
+        let name = crate::encode::display_name(name).map_err(|_| fmt::Error)?;
+        write!(f, "{} ", name)?;
+    }
+
+    write!(f, "<{}>", addr)
+}
 
 /// Representation of an HTTP Language Range
 ///
 /// **Note**: This may be renamed to `LanguageTag` in the future to be clearer and more in line
 /// with RFC2616.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LanguageRange {
     /// This variant indicates that the language in question was equal to `*`. This means that the
     /// user accepts any language equally.
@@ -31,12 +87,22 @@ pub enum LanguageRange {
     Other(String),
 }
 
+impl fmt::Display for LanguageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Other(range) => f.write_str(range),
+        }
+    }
+}
+
 /// Language description, used in the Accept-Language header
 ///
 /// The serialized version of this could be for example `en-US;q=0.8`, or simply `en`.
 ///
 /// **Note**: This might be refactored into an ordered list by preference.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Language {
     /// The language tag for this specific language definition
     pub range: LanguageRange,
@@ -47,6 +113,46 @@ pub struct Language {
     pub params: Vec<AcceptParam>
 }
 
+impl Language {
+    /// Returns the preference given to this language, defaulting to 1.0 when no Q param is set
+    pub fn q(&self) -> f32 {
+        self.params.iter()
+            .find_map(|param| match param {
+                AcceptParam::Q(q) => Some(*q),
+                _ => None,
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Returns the number of characters of `tag` matched by this language's range, if any
+    ///
+    /// A range matches a tag if it is equal to it, or if it is a prefix of the tag followed by a
+    /// `-`. The `*` range matches any tag, but with the lowest possible specificity.
+    fn matches(&self, tag: &str) -> Option<usize> {
+        match &self.range {
+            LanguageRange::Any => Some(0),
+            LanguageRange::Other(range) => {
+                let prefix_of_tag = tag.len() > range.len()
+                    && tag.as_bytes()[range.len()] == b'-'
+                    && tag[..range.len()].eq_ignore_ascii_case(range);
+
+                if range.eq_ignore_ascii_case(tag) || prefix_of_tag {
+                    Some(range.len())
+                } else {
+                    None
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.range.fmt(f)?;
+        write_params(f, &self.params)
+    }
+}
+
 /// Representation of a content-coding.
 ///
 /// A content-coding is used to indicate how the body of a message has been transformed. For
@@ -60,6 +166,7 @@ pub struct Language {
 ///
 /// [1]: https://tools.ietf.org/html/rfc3261#section-20.12
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ContentCoding {
     /// This value indicates, when used in the Content-Encoding header, that the client accepts any
     /// kind of permissible encoding.
@@ -69,12 +176,22 @@ pub enum ContentCoding {
     Other(String),
 }
 
+impl fmt::Display for ContentCoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Other(coding) => f.write_str(coding),
+        }
+    }
+}
+
 /// Content-coding description, used in the Accept-Encoding header
 ///
 /// The serialized version of this could be for example `gzip;q=0.1`.
 ///
 /// **Note**: This might be refactored into an ordered list by preference.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Encoding {
     /// The descriptor of an encoding format
     pub coding: ContentCoding,
@@ -85,7 +202,27 @@ pub struct Encoding {
     pub params: Vec<AcceptParam>
 }
 
+impl Encoding {
+    /// Returns the preference given to this encoding, defaulting to 1.0 when no Q param is set
+    pub fn q(&self) -> f32 {
+        self.params.iter()
+            .find_map(|param| match param {
+                AcceptParam::Q(q) => Some(*q),
+                _ => None,
+            })
+            .unwrap_or(1.0)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.coding.fmt(f)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MediaSubType {
     Any,
     IETFExtension(String),
@@ -93,7 +230,19 @@ pub enum MediaSubType {
     XExtension(String),
 }
 
+impl fmt::Display for MediaSubType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::IETFExtension(subtype)
+                | Self::IANAExtension(subtype)
+                | Self::XExtension(subtype) => f.write_str(subtype),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MediaType {
     Any,
     Text,
@@ -107,38 +256,135 @@ pub enum MediaType {
     XExtension(String),
 }
 
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Text => f.write_str("text"),
+            Self::Image => f.write_str("image"),
+            Self::Audio => f.write_str("audio"),
+            Self::Video => f.write_str("video"),
+            Self::Application => f.write_str("application"),
+            Self::Message => f.write_str("message"),
+            Self::Multipart => f.write_str("multipart"),
+            Self::IETFExtension(r#type) | Self::XExtension(r#type) => f.write_str(r#type),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MediaParam {
     pub name: String,
     pub value: String,
 }
 
+impl fmt::Display for MediaParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}=", self.name)?;
+        write_param_value(f, &self.value)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Media {
     pub r#type: MediaType,
     pub subtype: MediaSubType,
     pub params: Vec<MediaParam>,
 }
 
+impl Media {
+    /// Returns the value of the first parameter named `name`, compared case-insensitively
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|param| param.name.eq_ignore_ascii_case(name))
+            .map(|param| param.value.as_str())
+    }
+}
+
+impl fmt::Display for Media {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.r#type, self.subtype)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AcceptParam {
-    Q(String),
+    /// the preference, between 0 and 1
+    Q(f32),
     Extension(GenericParam),
 }
 
+impl fmt::Display for AcceptParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Q(q) => write!(f, "q={}", q),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Accept {
     pub media: Media,
     pub params: Vec<AcceptParam>
 }
 
+impl Accept {
+    /// Returns the preference given to this media range, defaulting to 1.0 when no Q param is set
+    pub fn q(&self) -> f32 {
+        self.params.iter()
+            .find_map(|param| match param {
+                AcceptParam::Q(q) => Some(*q),
+                _ => None,
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Returns the value of the parameter named `name`, compared case-insensitively
+    ///
+    /// Both the media-range parameters (e.g. `level`) and the accept-extension parameters are
+    /// looked up, in that order. Use `q` for the preference. Parameters without a value are
+    /// reported as an empty string.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.media.param(name).or_else(|| {
+            self.params.iter().find_map(|param| match param {
+                AcceptParam::Extension(param) if param.name.eq_ignore_ascii_case(name) => {
+                    Some(param.value.as_deref().unwrap_or(""))
+                },
+                _ => None,
+            })
+        })
+    }
+}
+
+impl fmt::Display for Accept {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.media.fmt(f)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AlertInfo {
     pub uri: String,
     pub params: Vec<GenericParam>,
 }
 
+impl fmt::Display for AlertInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.uri)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum URIParam {
     Transport(Transport),
     User(User),
@@ -146,16 +392,75 @@ pub enum URIParam {
     TTL(i32),
     MAddr(String),
     LR,
+    /// GRUU (RFC5627). The value is only present in GRUUs minted by a registrar.
+    Gr(Option<String>),
     Other(String, Option<String>),
 }
 
+impl fmt::Display for URIParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(transport) => write!(f, "transport={}", transport),
+            Self::User(user) => write!(f, "user={}", user),
+            Self::Method(method) => write!(f, "method={}", method),
+            Self::TTL(ttl) => write!(f, "ttl={}", ttl),
+            Self::MAddr(maddr) => write!(f, "maddr={}", maddr),
+            Self::LR => f.write_str("lr"),
+            Self::Gr(Some(value)) => {
+                f.write_str("gr=")?;
+                write_escaped(f, value, rfc3261::is_param_char)
+            },
+            Self::Gr(None) => f.write_str("gr"),
+            Self::Other(name, value) => {
+                write_escaped(f, name, rfc3261::is_param_char)?;
+                match value {
+                    Some(value) => {
+                        f.write_str("=")?;
+                        write_escaped(f, value, rfc3261::is_param_char)
+                    },
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+impl URIParam {
+    /// Returns the name of the parameter, as written for extension parameters
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Transport(_) => "transport",
+            Self::User(_) => "user",
+            Self::Method(_) => "method",
+            Self::TTL(_) => "ttl",
+            Self::MAddr(_) => "maddr",
+            Self::LR => "lr",
+            Self::Gr(_) => "gr",
+            Self::Other(name, _) => name,
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct URIHeader {
     pub name: String,
     pub value: String,
 }
 
+impl URIHeader {
+    /// Decodes the SIP or SIPS URI carried by the header, if any
+    ///
+    /// The value of URI headers may itself contain a URI (for example a Refer-To
+    /// header embedded in a Contact URI). URIs nested too deeply are rejected with
+    /// `ErrorKind::NestingTooDeep`.
+    pub fn embedded_uri(&self) -> Result<Option<SipUri>, Error<'static, &'static [u8]>> {
+        rfc3261::embedded_uri(&self.value)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ViaParam {
     Ttl(i32),
     MAddr(String),
@@ -164,48 +469,246 @@ pub enum ViaParam {
     Extension(GenericParam),
 }
 
+impl fmt::Display for ViaParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ttl(ttl) => write!(f, "ttl={}", ttl),
+            Self::MAddr(maddr) => write!(f, "maddr={}", maddr),
+            Self::Received(received) => write!(f, "received={}", received),
+            Self::Branch(branch) => write!(f, "branch={}", branch),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Via {
+    /// the sent-protocol as written, e.g. `SIP/2.0/UDP`
     pub protocol: String,
-    pub sent_by: String,
+    /// the transport of the sent-protocol
+    pub transport: Transport,
+    pub sent_by: Host,
+    pub port: Option<u16>,
     pub params: Vec<ViaParam>,
 }
 
+impl Via {
+    /// Returns the transport the request was sent over, as given by the sent-protocol
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// Returns the value of the branch parameter, if any
+    pub fn branch(&self) -> Option<&str> {
+        self.params.iter().find_map(|param| match param {
+            ViaParam::Branch(branch) => Some(branch.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the host responses to the request should be sent to (RFC3261 section 18.2.2)
+    ///
+    /// This is the address of the `received` param, added by the server when the request didn't
+    /// come from the sent-by host, and the sent-by host otherwise.
+    pub fn response_host(&self) -> Host {
+        let received = self.params.iter().find_map(|param| match param {
+            ViaParam::Received(received) => Some(received),
+            _ => None,
+        });
+
+        match received {
+            Some(received) => match received.trim_start_matches('[').trim_end_matches(']').parse() {
+                Ok(crate::net::IpAddr::V4(addr)) => Host::IpV4(addr),
+                Ok(crate::net::IpAddr::V6(addr)) => Host::IpV6(addr),
+                Err(_) => Host::Domain(received.clone()),
+            },
+            None => self.sent_by.clone(),
+        }
+    }
+
+    /// Returns the port responses to the request should be sent to (RFC3261 section 18.2.2)
+    ///
+    /// The value of the `rport` param (RFC3581), filled by the server with the source port of the
+    /// request, takes precedence over the sent-by port. Without either, the default port of the
+    /// transport is used: 5061 for TLS, 5060 otherwise.
+    pub fn response_port(&self) -> u16 {
+        let rport = self.params.iter().find_map(|param| match param {
+            ViaParam::Extension(GenericParam { name, value: Some(value) })
+                if name.eq_ignore_ascii_case("rport") => value.parse().ok(),
+            _ => None,
+        });
+
+        rport.or(self.port).unwrap_or_else(|| self.transport().default_port())
+    }
+}
+
+impl fmt::Display for Via {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.protocol, self.sent_by)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InfoParamPurpose {
     Icon,
     Info,
     Card,
+    /// additional data about an emergency call (RFC7852), e.g. `EmergencyCallData.ProviderInfo`.
+    /// The value holds the type of data, without the `EmergencyCallData.` prefix.
+    EmergencyCallData(String),
+    /// call completion service (RFC6910)
+    CallCompletion,
     Other(String),
 }
 
+impl fmt::Display for InfoParamPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Icon => f.write_str("icon"),
+            Self::Info => f.write_str("info"),
+            Self::Card => f.write_str("card"),
+            Self::EmergencyCallData(data) => write!(f, "EmergencyCallData.{}", data),
+            Self::CallCompletion => f.write_str("call-completion"),
+            Self::Other(purpose) => f.write_str(purpose),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InfoParam {
     Purpose(InfoParamPurpose),
     Extension(GenericParam)
 }
 
+impl fmt::Display for InfoParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Purpose(purpose) => write!(f, "purpose={}", purpose),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Info {
-    pub uri: String,
+    pub uri: Uri,
     pub params: Vec<InfoParam>,
 }
 
+impl Info {
+    /// Returns the purpose of the information, if one was given
+    pub fn purpose(&self) -> Option<&InfoParamPurpose> {
+        self.params.iter().find_map(|param| match param {
+            InfoParam::Purpose(purpose) => Some(purpose),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.uri)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// Answer mode requested by the caller (RFC5373), used by the Answer-Mode and Priv-Answer-Mode
+/// headers
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnswerModeValue {
+    Manual,
+    Auto,
+    Extension(String),
+}
+
+impl fmt::Display for AnswerModeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Manual => f.write_str("Manual"),
+            Self::Auto => f.write_str("Auto"),
+            Self::Extension(mode) => f.write_str(mode),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnswerModeParam {
+    /// the request must be rejected if the requested answer mode can't be honoured
+    Require,
+    Extension(GenericParam),
+}
+
+impl fmt::Display for AnswerModeParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Require => f.write_str("require"),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnswerMode {
+    pub mode: AnswerModeValue,
+    pub params: Vec<AnswerModeParam>,
+}
+
+impl fmt::Display for AnswerMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.mode.fmt(f)?;
+        write_params(f, &self.params)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AlgorithmKind {
     MD5,
     MD5Sess,
     Extension(String)
 }
 
+impl fmt::Display for AlgorithmKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MD5 => f.write_str("MD5"),
+            Self::MD5Sess => f.write_str("MD5-sess"),
+            Self::Extension(algorithm) => f.write_str(algorithm),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QOPValue {
     Auth,
     AuthInt,
     Extension(String)
 }
 
+impl fmt::Display for QOPValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auth => f.write_str("auth"),
+            Self::AuthInt => f.write_str("auth-int"),
+            Self::Extension(qop) => f.write_str(qop),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DigestParam {
     Realm(String),
     Domain(Vec<String>),
@@ -217,13 +720,62 @@ pub enum DigestParam {
     Extension(String, String),
 }
 
+impl fmt::Display for DigestParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Realm(realm) => write!(f, "realm=\"{}\"", realm),
+            Self::Domain(uris) => write!(f, "domain=\"{}\"", uris.join(" ")),
+            Self::Nonce(nonce) => write!(f, "nonce=\"{}\"", nonce),
+            Self::Opaque(opaque) => write!(f, "opaque=\"{}\"", opaque),
+            Self::Stale(stale) => write!(f, "stale={}", if *stale { "true" } else { "false" }),
+            Self::Algorithm(algorithm) => write!(f, "algorithm={}", algorithm),
+            Self::QOPOptions(options) => {
+                f.write_str("qop=\"")?;
+                write_list(f, options, ",")?;
+                f.write_str("\"")
+            },
+            Self::Extension(name, value) => {
+                write!(f, "{}=", name)?;
+                write_param_value(f, value)
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Challenge {
     Digest(Vec<DigestParam>),
     Other(String, Vec<(String, String)>)
 }
 
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Digest(params) => {
+                f.write_str("Digest ")?;
+                write_list(f, params, ", ")
+            },
+            Self::Other(scheme, params) => write_auth_params(f, scheme, params),
+        }
+    }
+}
+
+fn write_auth_params(f: &mut fmt::Formatter<'_>, scheme: &str, params: &[(String, String)]) -> fmt::Result {
+    write!(f, "{} ", scheme)?;
+    for (i, (name, value)) in params.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}=", name)?;
+        write_param_value(f, value)?;
+    }
+
+    Ok(())
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DigestResponseParam {
     Username(String),
     Realm(String),
@@ -238,13 +790,48 @@ pub enum DigestResponseParam {
     Extension(String, String),
 }
 
+impl fmt::Display for DigestResponseParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Username(username) => write!(f, "username=\"{}\"", username),
+            Self::Realm(realm) => write!(f, "realm=\"{}\"", realm),
+            Self::Nonce(nonce) => write!(f, "nonce=\"{}\"", nonce),
+            Self::URI(uri) => write!(f, "uri=\"{}\"", uri),
+            Self::Response(response) => write!(f, "response=\"{}\"", response),
+            Self::Algorithm(algorithm) => write!(f, "algorithm={}", algorithm),
+            Self::CNonce(cnonce) => write!(f, "cnonce=\"{}\"", cnonce),
+            Self::Opaque(opaque) => write!(f, "opaque=\"{}\"", opaque),
+            Self::QOP(qop) => write!(f, "qop={}", qop),
+            Self::NonceCount(nc) => write!(f, "nc={}", nc),
+            Self::Extension(name, value) => {
+                write!(f, "{}=", name)?;
+                write_param_value(f, value)
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Credentials {
     DigestResponse(Vec<DigestResponseParam>),
     OtherResponse(String, Vec<(String, String)>)
 }
 
+impl fmt::Display for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DigestResponse(params) => {
+                f.write_str("Digest ")?;
+                write_list(f, params, ", ")
+            },
+            Self::OtherResponse(scheme, params) => write_auth_params(f, scheme, params),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AuthenticationInfo {
     NextNonce(String),
     QOP(QOPValue),
@@ -253,7 +840,21 @@ pub enum AuthenticationInfo {
     NonceCount(String)
 }
 
+impl fmt::Display for AuthenticationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NextNonce(nonce) => write!(f, "nextnonce=\"{}\"", nonce),
+            Self::QOP(qop) => write!(f, "qop={}", qop),
+            // the response digest is stored with its quotes
+            Self::ResponseAuth(auth) => write!(f, "rspauth={}", auth),
+            Self::CNonce(cnonce) => write!(f, "cnonce=\"{}\"", cnonce),
+            Self::NonceCount(nc) => write!(f, "nc={}", nc),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Priority {
     Emergency,
     Urgent,
@@ -262,99 +863,505 @@ pub enum Priority {
     Extension(String),
 }
 
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Emergency => f.write_str("emergency"),
+            Self::Urgent => f.write_str("urgent"),
+            Self::Normal => f.write_str("normal"),
+            Self::NonUrgent => f.write_str("non-urgent"),
+            Self::Extension(priority) => f.write_str(priority),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ToParam {
     Tag(String),
     Extension(GenericParam),
 }
 
+impl fmt::Display for ToParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag={}", tag),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct To {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<ToParam>,
 }
 
+impl To {
+    /// Returns the parameters of the URI (as opposed to the header's own parameters)
+    pub fn uri_params(&self) -> &[URIParam] {
+        self.addr.params()
+    }
+
+    /// Returns the value of the tag parameter, if any
+    pub fn tag(&self) -> Option<&str> {
+        self.params.iter().find_map(|param| match param {
+            ToParam::Tag(tag) => Some(tag.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Sets the tag parameter, replacing any existing one
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.params.retain(|param| !matches!(param, ToParam::Tag(_)));
+        self.params.push(ToParam::Tag(tag.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for To {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericParam {
     pub name: String,
     pub value: Option<String>,
 }
 
+impl fmt::Display for GenericParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(value) = &self.value {
+            f.write_str("=")?;
+            write_param_value(f, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the value of the first parameter of `params` named `name`, compared case-insensitively
+///
+/// Parameters without a value are reported as an empty string, so that their presence can be
+/// told apart from their absence.
+pub fn param<'a>(params: &'a [GenericParam], name: &str) -> Option<&'a str> {
+    params.iter()
+        .find(|param| param.name.eq_ignore_ascii_case(name))
+        .map(|param| param.value.as_deref().unwrap_or(""))
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Route {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<GenericParam>,
 }
 
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// Identity of the originator of a message, asserted by a trusted element (RFC3325)
+///
+/// Along with the `from-change` option tag (RFC4916), it is also carried in UPDATE and re-INVITE
+/// requests to present the identity of the connected party.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PAssertedIdentity {
+    pub addr: Uri,
+    pub name: Option<String>,
+}
+
+impl fmt::Display for PAssertedIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)
+    }
+}
+
+/// Identity carried by the P-Associated-URI and P-Called-Party-ID headers (RFC3455)
+///
+/// In P-Associated-URI, it is one of the URIs the registrar associated with the registered
+/// public identity. In P-Called-Party-ID, it is the URI the request was originally addressed to.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PIdentity {
+    pub addr: Uri,
+    pub name: Option<String>,
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for PIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// Dialog to join, as carried by the Join header (RFC3911)
+///
+/// The recipient of an INVITE carrying it is asked to add the new dialog to the session (e.g. a
+/// conference) the identified dialog belongs to.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Join {
+    /// the Call-ID of the dialog
+    pub call_id: String,
+
+    /// the tag of the To header of the dialog (`to-tag` param)
+    pub to_tag: String,
+
+    /// the tag of the From header of the dialog (`from-tag` param)
+    pub from_tag: String,
+
+    /// the other parameters of the header
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for Join {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};to-tag={};from-tag={}", self.call_id, self.to_tag, self.from_tag)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// Dialog targeted by a request, as carried by the Target-Dialog header (RFC4538)
+///
+/// It lets the recipient of an out-of-dialog request (e.g. a REFER) authorize it by proving
+/// knowledge of an existing dialog.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetDialog {
+    /// the Call-ID of the dialog
+    pub call_id: String,
+
+    /// the tag of the recipient of the request in the dialog (`local-tag` param)
+    pub local_tag: String,
+
+    /// the tag of the sender of the request in the dialog (`remote-tag` param)
+    pub remote_tag: String,
+
+    /// the other parameters of the header
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for TargetDialog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};local-tag={};remote-tag={}", self.call_id, self.local_tag, self.remote_tag)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// Caller preferences expressed by an Accept-Contact or Reject-Contact header (RFC3841)
+///
+/// The feature-tag parameters (e.g. `audio`, `methods="INVITE"`) describe the UAs the caller
+/// would like the request to reach, or not to reach.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContactPref {
+    /// whether UAs not matching the preference must be discarded (`require` param)
+    pub require: bool,
+
+    /// whether only UAs explicitly advertising the features match (`explicit` param)
+    pub explicit: bool,
+
+    /// the feature-tag parameters, and any other parameter
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for ContactPref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("*")?;
+        write_params(f, &self.params)?;
+        if self.require {
+            f.write_str(";require")?;
+        }
+        if self.explicit {
+            f.write_str(";explicit")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Addresses of the charging functions of an IMS network (RFC3455)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PChargingFunctionAddresses {
+    /// the Charging Collection Function addresses (`ccf` params), in order of preference
+    pub ccf: Vec<String>,
+
+    /// the Event Charging Function addresses (`ecf` params), in order of preference
+    pub ecf: Vec<String>,
+
+    /// the other parameters of the header
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for PChargingFunctionAddresses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addresses = self.ccf.iter().map(|ccf| ("ccf", ccf))
+            .chain(self.ecf.iter().map(|ecf| ("ecf", ecf)));
+
+        for (i, (name, address)) in addresses.enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(f, "{}=", name)?;
+            write_param_value(f, address)?;
+        }
+
+        write_params(f, &self.params)
+    }
+}
+
+/// Value of an Identity-Info header (RFC4474)
+///
+/// It tells where to find the certificate of the authentication service that signed the request,
+/// and how the signature of the Identity header was computed.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentityInfo {
+    /// the URI of the certificate of the signer
+    pub uri: String,
+
+    /// the signature algorithm (`alg` param), e.g. `rsa-sha1`
+    pub alg: Option<String>,
+
+    /// the other parameters of the header
+    pub params: Vec<GenericParam>,
+}
+
+impl fmt::Display for IdentityInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.uri)?;
+        if let Some(alg) = &self.alg {
+            write!(f, ";alg={}", alg)?;
+        }
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplyTo {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<GenericParam>,
 }
 
+impl fmt::Display for ReplyTo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RecordRoute {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<GenericParam>,
 }
 
+impl fmt::Display for RecordRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FromParam {
     Tag(String),
     Extension(GenericParam),
 }
 
+impl fmt::Display for FromParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag={}", tag),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct From {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<FromParam>,
 }
 
+impl From {
+    /// Returns the parameters of the URI (as opposed to the header's own parameters)
+    pub fn uri_params(&self) -> &[URIParam] {
+        self.addr.params()
+    }
+
+    /// Returns the value of the tag parameter, if any
+    pub fn tag(&self) -> Option<&str> {
+        self.params.iter().find_map(|param| match param {
+            FromParam::Tag(tag) => Some(tag.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Sets the tag parameter, replacing any existing one
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.params.retain(|param| !matches!(param, FromParam::Tag(_)));
+        self.params.push(FromParam::Tag(tag.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for From {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ContactParam {
-    Q(String),
-    Expires(i32),
+    /// the preference, between 0 and 1
+    Q(f32),
+    /// the number of seconds the binding is valid for
+    Expires(u32),
+    /// the push notification service to use (RFC8599), e.g. `apns` or `fcm`
+    PushProvider(String),
+    /// the identifier of the device for the push notification service (RFC8599)
+    PushPrid(String),
+    /// additional information required by the push notification service (RFC8599)
+    PushParam(String),
     Extension(GenericParam),
 }
 
+impl fmt::Display for ContactParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Q(q) => write!(f, "q={}", q),
+            Self::Expires(expires) => write!(f, "expires={}", expires),
+            Self::PushProvider(provider) => write!(f, "pn-provider={}", provider),
+            Self::PushPrid(prid) => write!(f, "pn-prid={}", prid),
+            Self::PushParam(param) => write!(f, "pn-param={}", param),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Contact {
-    pub addr: String,
+    pub addr: Uri,
     pub name: Option<String>,
     pub params: Vec<ContactParam>,
 }
 
+impl Contact {
+    /// Returns the parameters of the URI (as opposed to the header's own parameters)
+    pub fn uri_params(&self) -> &[URIParam] {
+        self.addr.params()
+    }
+
+    /// Whether the contact URI is a GRUU, i.e. carries a `gr` URI parameter
+    pub fn is_gruu(&self) -> bool {
+        self.uri_params().iter().any(|param| matches!(param, URIParam::Gr(_)))
+    }
+
+    /// Returns the value of the `gr` URI parameter, if the contact URI is a GRUU with a value
+    pub fn gruu(&self) -> Option<&str> {
+        self.uri_params().iter().find_map(|param| match param {
+            URIParam::Gr(value) => value.as_deref(),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Contact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name_addr(f, &self.name, &self.addr)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ContactValue {
     Any,
     Specific(Vec<Contact>),
 }
 
+impl fmt::Display for ContactValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Specific(contacts) => write_list(f, contacts, ", "),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ErrorInfo {
     pub uri: String,
     pub params: Vec<GenericParam>,
 }
 
+impl fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.uri)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WarningAgent {
-    HostPort(String, Option<i32>),
+    HostPort(Host, Option<u16>),
     Pseudonym(String),
 }
 
+impl fmt::Display for WarningAgent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HostPort(host, Some(port)) => write!(f, "{}:{}", host, port),
+            Self::HostPort(host, None) => host.fmt(f),
+            Self::Pseudonym(pseudonym) => f.write_str(pseudonym),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Warning {
     pub code: String,
     pub agent: WarningAgent,
     pub text: String,
 }
 
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} \"{}\"", self.code, self.agent, self.text)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DispositionType {
     Render,
     Session,
@@ -363,7 +1370,20 @@ pub enum DispositionType {
     Extension(String),
 }
 
+impl fmt::Display for DispositionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Render => f.write_str("render"),
+            Self::Session => f.write_str("session"),
+            Self::Icon => f.write_str("icon"),
+            Self::Alert => f.write_str("alert"),
+            Self::Extension(disposition) => f.write_str(disposition),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DispositionParam {
     HandlingOptional,
     HandlingRequired,
@@ -371,32 +1391,126 @@ pub enum DispositionParam {
     Extension(GenericParam),
 }
 
+impl fmt::Display for DispositionParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HandlingOptional => f.write_str("handling=optional"),
+            Self::HandlingRequired => f.write_str("handling=required"),
+            Self::OtherHandling(handling) => write!(f, "handling={}", handling),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContentDisposition {
     pub disposition: DispositionType,
     pub params: Vec<DispositionParam>
 }
 
+impl fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.disposition.fmt(f)?;
+        write_params(f, &self.params)
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RetryParam {
     AvailabilityDuration(i32),
     Extension(GenericParam),
 }
 
+impl fmt::Display for RetryParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AvailabilityDuration(duration) => write!(f, "duration={}", duration),
+            Self::Extension(param) => param.fmt(f),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RetryAfter {
     pub duration: i32,
     pub comment: Option<String>,
     pub params: Vec<RetryParam>,
 }
 
+impl fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.duration)?;
+        if let Some(comment) = &self.comment {
+            write!(f, " ({})", comment)?;
+        }
+        write_params(f, &self.params)
+    }
+}
+
+/// Value of a Timestamp header
+///
+/// A UAC sets the timestamp when sending a request, and the UAS echoes it in its response,
+/// together with the time it took to process the request. Both values are kept as written, so
+/// that they are echoed without losing precision, and can be read as numbers with `as_f64` and
+/// `round_trip_hint`.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    /// the time at which the request was sent, in an unspecified unit (usually seconds)
+    pub value: String,
+
+    /// the time the UAS spent processing the request before responding, if given
+    pub delay: Option<String>,
+}
+
+impl Timestamp {
+    /// Returns the time at which the request was sent as a number
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value.parse().ok()
+    }
+
+    /// Returns the processing delay reported by the UAS, if any
+    ///
+    /// A UAC estimates the round-trip time of a transaction by subtracting both the timestamp
+    /// and this delay from the time at which the response was received.
+    pub fn round_trip_hint(&self) -> Option<f64> {
+        self.delay.as_ref()?.parse().ok()
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)?;
+        match &self.delay {
+            Some(delay) => write!(f, " {}", delay),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A header that could not be parsed
+///
+/// This is only produced when parsing leniently, for example by `Request::parse_best_effort`.
+#[derive(PartialEq, Debug, Clone, thiserror::Error)]
+#[error("malformed header: {}", String::from_utf8_lossy(raw))]
+pub struct HeaderError {
+    /// the raw header line, without its terminating CRLF
+    pub raw: Vec<u8>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Header {
     Accept(Vec<Accept>),
+    AcceptContact(Vec<ContactPref>),
     AcceptEncoding(Vec<Encoding>),
     AcceptLanguage(Vec<Language>),
     AlertInfo(Vec<AlertInfo>),
     Allow(Vec<Method>),
+    AnswerMode(AnswerMode),
     AuthenticationInfo(Vec<AuthenticationInfo>),
     Authorization(Credentials),
     CallID(String),
@@ -407,22 +1521,34 @@ pub enum Header {
     ContentLanguage(Vec<String>),
     ContentLength(i32),
     ContentType(Media),
-    CSeq(i32, Method),
+    CSeq(CSeq),
     Date(String),
     ErrorInfo(Vec<ErrorInfo>),
-    Expires(i32),
+    Expires(u32),
     From(From),
     Via(Vec<Via>),
+    /// the signature of the request (RFC4474), without its quotes
+    Identity(String),
+    IdentityInfo(IdentityInfo),
     InReplyTo(Vec<String>),
+    Join(Join),
     MaxForwards(i32),
     MIMEVersion(String),
-    MinExpires(i32),
+    MinExpires(u32),
     Organization(Option<String>),
+    PAssertedIdentity(Vec<PAssertedIdentity>),
+    PAssociatedURI(Vec<PIdentity>),
+    PCalledPartyID(PIdentity),
+    PChargingFunctionAddresses(PChargingFunctionAddresses),
     Priority(Priority),
+    PrivAnswerMode(AnswerMode),
     ProxyAuthenticate(Challenge),
     ProxyAuthorization(Credentials),
     ProxyRequire(Vec<String>),
     RecordRoute(Vec<RecordRoute>),
+    /// whether the REFER request creates an implicit subscription (RFC4488), and the parameters
+    ReferSub(bool, Vec<GenericParam>),
+    RejectContact(Vec<ContactPref>),
     ReplyTo(ReplyTo),
     Require(Vec<String>),
     RetryAfter(RetryAfter),
@@ -430,7 +1556,8 @@ pub enum Header {
     Server(String),
     Subject(Option<String>),
     Supported(Vec<String>),
-    Timestamp(String, Option<String>),
+    TargetDialog(TargetDialog),
+    Timestamp(Timestamp),
     To(To),
     Unsupported(Vec<String>),
     UserAgent(String),
@@ -438,3 +1565,536 @@ pub enum Header {
     WWWAuthenticate(Challenge),
     Extension(String, String),
 }
+
+impl Header {
+    /// Returns the canonical (long form) name of the header
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Accept(_) => "Accept",
+            Self::AcceptContact(_) => "Accept-Contact",
+            Self::AcceptEncoding(_) => "Accept-Encoding",
+            Self::AcceptLanguage(_) => "Accept-Language",
+            Self::AlertInfo(_) => "Alert-Info",
+            Self::Allow(_) => "Allow",
+            Self::AnswerMode(_) => "Answer-Mode",
+            Self::AuthenticationInfo(_) => "Authentication-Info",
+            Self::Authorization(_) => "Authorization",
+            Self::CallID(_) => "Call-ID",
+            Self::CallInfo(_) => "Call-Info",
+            Self::Contact(_) => "Contact",
+            Self::ContentDisposition(_) => "Content-Disposition",
+            Self::ContentEncoding(_) => "Content-Encoding",
+            Self::ContentLanguage(_) => "Content-Language",
+            Self::ContentLength(_) => "Content-Length",
+            Self::ContentType(_) => "Content-Type",
+            Self::CSeq(_) => "CSeq",
+            Self::Date(_) => "Date",
+            Self::ErrorInfo(_) => "Error-Info",
+            Self::Expires(_) => "Expires",
+            Self::From(_) => "From",
+            Self::Via(_) => "Via",
+            Self::Identity(_) => "Identity",
+            Self::IdentityInfo(_) => "Identity-Info",
+            Self::InReplyTo(_) => "In-Reply-To",
+            Self::Join(_) => "Join",
+            Self::MaxForwards(_) => "Max-Forwards",
+            Self::MIMEVersion(_) => "MIME-Version",
+            Self::MinExpires(_) => "Min-Expires",
+            Self::Organization(_) => "Organization",
+            Self::PAssertedIdentity(_) => "P-Asserted-Identity",
+            Self::PAssociatedURI(_) => "P-Associated-URI",
+            Self::PCalledPartyID(_) => "P-Called-Party-ID",
+            Self::PChargingFunctionAddresses(_) => "P-Charging-Function-Addresses",
+            Self::Priority(_) => "Priority",
+            Self::PrivAnswerMode(_) => "Priv-Answer-Mode",
+            Self::ProxyAuthenticate(_) => "Proxy-Authenticate",
+            Self::ProxyAuthorization(_) => "Proxy-Authorization",
+            Self::ProxyRequire(_) => "Proxy-Require",
+            Self::RecordRoute(_) => "Record-Route",
+            Self::ReferSub(_, _) => "Refer-Sub",
+            Self::RejectContact(_) => "Reject-Contact",
+            Self::ReplyTo(_) => "Reply-To",
+            Self::Require(_) => "Require",
+            Self::RetryAfter(_) => "Retry-After",
+            Self::Route(_) => "Route",
+            Self::Server(_) => "Server",
+            Self::Subject(_) => "Subject",
+            Self::Supported(_) => "Supported",
+            Self::TargetDialog(_) => "Target-Dialog",
+            Self::Timestamp(_) => "Timestamp",
+            Self::To(_) => "To",
+            Self::Unsupported(_) => "Unsupported",
+            Self::UserAgent(_) => "User-Agent",
+            Self::Warning(_) => "Warning",
+            Self::WWWAuthenticate(_) => "WWW-Authenticate",
+            Self::Extension(name, _) => name,
+        }
+    }
+
+    /// Returns the compact form of the header name, for the headers which have one
+    pub fn compact_name(&self) -> Option<&'static str> {
+        match self {
+            Self::AcceptContact(_) => Some("a"),
+            Self::CallID(_) => Some("i"),
+            Self::Contact(_) => Some("m"),
+            Self::ContentEncoding(_) => Some("e"),
+            Self::ContentLength(_) => Some("l"),
+            Self::ContentType(_) => Some("c"),
+            Self::From(_) => Some("f"),
+            Self::RejectContact(_) => Some("j"),
+            Self::Subject(_) => Some("s"),
+            Self::Supported(_) => Some("k"),
+            Self::To(_) => Some("t"),
+            Self::Via(_) => Some("v"),
+            _ => None,
+        }
+    }
+
+    /// Whether the header is called `name`, in its long or compact form, ignoring case
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name().eq_ignore_ascii_case(name)
+            || matches!(self.compact_name(), Some(compact) if compact.eq_ignore_ascii_case(name))
+    }
+
+    /// Selects the language from `available` preferred by an Accept-Language header
+    ///
+    /// Each available language tag is weighted by the Q param of the most specific language range
+    /// matching it, so that `en` matches `en-US`, and `*` only applies to tags not matched by
+    /// another range. Tags weighted at 0 (for example through `*;q=0`) are never returned. When
+    /// several tags share the highest weight, the first one in `available` wins. An empty
+    /// Accept-Language header accepts every language.
+    ///
+    /// Returns `None` if no language is acceptable, or if this is not an Accept-Language header.
+    pub fn best_language_match<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let languages = match self {
+            Self::AcceptLanguage(languages) => languages,
+            _ => return None,
+        };
+
+        if languages.is_empty() {
+            return available.first().copied();
+        }
+
+        let mut best = None;
+        let mut best_q = 0.0;
+        for tag in available {
+            let q = languages.iter()
+                .filter_map(|language| language.matches(tag).map(|len| (len, language.q())))
+                .max_by_key(|(len, _)| *len)
+                .map(|(_, q)| q)
+                .unwrap_or(0.0);
+
+            if q > best_q {
+                best = Some(*tag);
+                best_q = q;
+            }
+        }
+
+        best
+    }
+
+    /// Whether only the identity encoding (i.e. no encoding at all) is acceptable
+    ///
+    /// This is the case when the Accept-Encoding header is empty, or when every encoding it
+    /// accepts (with a non-zero weight) is `identity`. A server answering a request carrying such a
+    /// header must not encode the body of its response.
+    ///
+    /// Returns `false` if this is not an Accept-Encoding header.
+    pub fn identity_only(&self) -> bool {
+        let encodings = match self {
+            Self::AcceptEncoding(encodings) => encodings,
+            _ => return false,
+        };
+
+        encodings.iter()
+            .filter(|encoding| encoding.q() > 0.0)
+            .all(|encoding| match &encoding.coding {
+                ContentCoding::Other(coding) => coding.eq_ignore_ascii_case("identity"),
+                ContentCoding::Any => false,
+            })
+    }
+
+    /// Selects the encoding from `supported` preferred by the entries of an Accept-Encoding header
+    ///
+    /// Each supported encoding is weighted by the Q param of the entry naming it, or else by the
+    /// Q param of the `*` entry. A missing Q param counts as 1.0, and encodings weighted at 0 are
+    /// not acceptable. `identity` stays acceptable when no entry applies to it, but is then only
+    /// selected if nothing else is. When several encodings share the highest weight, the first
+    /// one in `supported` wins. An empty Accept-Encoding header only accepts `identity`.
+    ///
+    /// Returns `None` if none of the supported encodings is acceptable.
+    pub fn negotiate_encoding(accept: &[Encoding], supported: &[&str]) -> Option<String> {
+        let mut best = None;
+        let mut best_q = 0.0;
+
+        for coding in supported {
+            let named = accept.iter().find(|encoding| {
+                matches!(&encoding.coding, ContentCoding::Other(name) if name.eq_ignore_ascii_case(coding))
+            });
+            let any = accept.iter().find(|encoding| encoding.coding == ContentCoding::Any);
+
+            let q = match named.or(any) {
+                Some(encoding) => encoding.q(),
+                None if coding.eq_ignore_ascii_case("identity") => f32::MIN_POSITIVE,
+                None => 0.0,
+            };
+
+            if q > best_q {
+                best = Some(coding.to_string());
+                best_q = q;
+            }
+        }
+
+        best
+    }
+}
+
+/// A header of a request, along with the name it was received under
+///
+/// The received name (e.g. `v` or `VIA` for a Via header) lets a request be forwarded with the
+/// casing and compact forms it arrived with. It is only a matter of presentation, so it is ignored
+/// when comparing headers. The header itself is reachable through `Deref`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NamedHeader {
+    /// the header
+    pub header: Header,
+
+    /// the name the header was received under, if it was parsed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub name: Option<String>,
+}
+
+impl NamedHeader {
+    /// Wraps a header along with the name it was received under
+    pub fn new(header: Header, name: Option<String>) -> Self {
+        Self {
+            header,
+            name,
+        }
+    }
+
+    /// Returns the name the header is written under
+    ///
+    /// This is the received name, as long as it still designates the header (the header may have
+    /// been replaced since), and the canonical name otherwise.
+    pub fn written_name(&self) -> &str {
+        match &self.name {
+            Some(name) if self.header.is_named(name) => name,
+            _ => self.header.name(),
+        }
+    }
+}
+
+impl core::convert::From<Header> for NamedHeader {
+    fn from(header: Header) -> Self {
+        Self::new(header, None)
+    }
+}
+
+impl core::ops::Deref for NamedHeader {
+    type Target = Header;
+
+    fn deref(&self) -> &Header {
+        &self.header
+    }
+}
+
+impl core::ops::DerefMut for NamedHeader {
+    fn deref_mut(&mut self) -> &mut Header {
+        &mut self.header
+    }
+}
+
+impl PartialEq for NamedHeader {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+    }
+}
+
+impl PartialEq<Header> for NamedHeader {
+    fn eq(&self, other: &Header) -> bool {
+        &self.header == other
+    }
+}
+
+/// Writes the header as `Name: value` under the name given by `written_name`
+impl fmt::Display for NamedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.header.fmt_named(f, self.written_name())
+    }
+}
+
+/// Writes the header as `Name: value`, without the terminating CRLF
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_named(f, self.name())
+    }
+}
+
+impl Header {
+    /// Formats the header like `Display` does, but under the given name
+    pub(crate) fn fmt_named(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        use fmt::Display;
+
+        write!(f, "{}: ", name)?;
+
+        match self {
+            Self::Accept(accepts) => write_list(f, accepts, ", "),
+            Self::AcceptContact(prefs) | Self::RejectContact(prefs) => write_list(f, prefs, ", "),
+            Self::AcceptEncoding(encodings) => write_list(f, encodings, ", "),
+            Self::AcceptLanguage(languages) => write_list(f, languages, ", "),
+            Self::AlertInfo(infos) => write_list(f, infos, ", "),
+            Self::Allow(methods) => write_list(f, methods, ", "),
+            Self::AnswerMode(mode) | Self::PrivAnswerMode(mode) => mode.fmt(f),
+            Self::AuthenticationInfo(infos) => write_list(f, infos, ", "),
+            Self::Authorization(credentials)
+                | Self::ProxyAuthorization(credentials) => credentials.fmt(f),
+            Self::CallID(id) => f.write_str(id),
+            Self::CallInfo(infos) => write_list(f, infos, ", "),
+            Self::Contact(contact) => contact.fmt(f),
+            Self::ContentDisposition(disposition) => disposition.fmt(f),
+            Self::ContentEncoding(values)
+                | Self::ContentLanguage(values)
+                | Self::InReplyTo(values)
+                | Self::ProxyRequire(values)
+                | Self::Require(values)
+                | Self::Supported(values)
+                | Self::Unsupported(values) => write_list(f, values, ", "),
+            Self::ContentLength(value)
+                | Self::MaxForwards(value) => value.fmt(f),
+            Self::Expires(value)
+                | Self::MinExpires(value) => value.fmt(f),
+            Self::ContentType(media) => media.fmt(f),
+            Self::CSeq(cseq) => cseq.fmt(f),
+            Self::Date(value)
+                | Self::MIMEVersion(value)
+                | Self::Server(value)
+                | Self::UserAgent(value) => f.write_str(value),
+            Self::ErrorInfo(infos) => write_list(f, infos, ", "),
+            Self::From(from) => from.fmt(f),
+            Self::Via(vias) => write_list(f, vias, ", "),
+            Self::Identity(signature) => write!(f, "\"{}\"", signature),
+            Self::IdentityInfo(info) => info.fmt(f),
+            Self::Join(join) => join.fmt(f),
+            Self::Organization(value) | Self::Subject(value) => match value {
+                Some(value) => f.write_str(value),
+                None => Ok(()),
+            },
+            Self::PAssertedIdentity(identities) => write_list(f, identities, ", "),
+            Self::PAssociatedURI(identities) => write_list(f, identities, ", "),
+            Self::PCalledPartyID(identity) => identity.fmt(f),
+            Self::PChargingFunctionAddresses(addresses) => addresses.fmt(f),
+            Self::Priority(priority) => priority.fmt(f),
+            Self::ProxyAuthenticate(challenge)
+                | Self::WWWAuthenticate(challenge) => challenge.fmt(f),
+            Self::RecordRoute(routes) => write_list(f, routes, ", "),
+            Self::ReferSub(value, params) => {
+                value.fmt(f)?;
+                write_params(f, params)
+            },
+            Self::ReplyTo(reply_to) => reply_to.fmt(f),
+            Self::RetryAfter(retry_after) => retry_after.fmt(f),
+            Self::Route(routes) => write_list(f, routes, ", "),
+            Self::TargetDialog(target) => target.fmt(f),
+            Self::Timestamp(timestamp) => timestamp.fmt(f),
+            Self::To(to) => to.fmt(f),
+            Self::Warning(warnings) => write_list(f, warnings, ", "),
+            Self::Extension(_, value) => f.write_str(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn via(protocol: &str, port: Option<u16>, params: Vec<ViaParam>) -> Via {
+        Via {
+            protocol: protocol.to_string(),
+            transport: match protocol.rsplit('/').next() {
+                Some("TLS") => Transport::TLS,
+                _ => Transport::UDP,
+            },
+            sent_by: Host::Domain("client.atlanta.example.com".to_string()),
+            port,
+            params,
+        }
+    }
+
+    #[test]
+    fn via_response_host_prefers_received() {
+        let sent_by = via("SIP/2.0/UDP", None, vec![]);
+        assert_eq!(sent_by.response_host(), Host::Domain("client.atlanta.example.com".to_string()));
+
+        let received = via("SIP/2.0/UDP", None, vec![ViaParam::Received("192.0.2.1".to_string())]);
+        assert_eq!(received.response_host(), Host::IpV4("192.0.2.1".parse().unwrap()));
+
+        let received = via("SIP/2.0/UDP", None, vec![ViaParam::Received("2001:db8::9:1".to_string())]);
+        assert_eq!(received.response_host(), Host::IpV6("2001:db8::9:1".parse().unwrap()));
+    }
+
+    #[test]
+    fn via_response_port_prefers_rport() {
+        let rport = |value: Option<&str>| ViaParam::Extension(GenericParam {
+            name: "rport".to_string(),
+            value: value.map(|value| value.to_string()),
+        });
+
+        assert_eq!(via("SIP/2.0/UDP", None, vec![]).response_port(), 5060);
+        assert_eq!(via("SIP/2.0/TLS", None, vec![]).response_port(), 5061);
+        assert_eq!(via("SIP/2.0/UDP", Some(5070), vec![]).response_port(), 5070);
+        assert_eq!(via("SIP/2.0/UDP", Some(5070), vec![rport(None)]).response_port(), 5070);
+        assert_eq!(via("SIP/2.0/UDP", Some(5070), vec![rport(Some("9988"))]).response_port(), 9988);
+    }
+
+    fn language(range: &str, q: Option<f32>) -> Language {
+        Language {
+            range: match range {
+                "*" => LanguageRange::Any,
+                other => LanguageRange::Other(other.to_string()),
+            },
+            params: q.into_iter().map(AcceptParam::Q).collect(),
+        }
+    }
+
+    #[test]
+    fn with_tag_replaces_existing_tags() {
+        let to = To {
+            addr: Uri::Absolute("tel:+14155551212".to_string()),
+            name: None,
+            params: vec![
+                ToParam::Tag("old".to_string()),
+                ToParam::Extension(GenericParam { name: "foo".to_string(), value: None }),
+            ],
+        };
+        let to = to.with_tag("a6c85cf");
+        assert_eq!(to.tag(), Some("a6c85cf"));
+        assert_eq!(to.params.len(), 2);
+        assert_eq!(to.with_tag("1928301774").params.iter().filter(|param| matches!(param, ToParam::Tag(_))).count(), 1);
+
+        let from = From {
+            addr: Uri::Absolute("tel:+14155551212".to_string()),
+            name: None,
+            params: vec![],
+        };
+        assert_eq!(from.with_tag("1928301774").to_string(), "<tel:+14155551212>;tag=1928301774");
+    }
+
+    #[test]
+    fn param_ignores_case_of_names() {
+        let params = vec![
+            GenericParam { name: "Foo".to_string(), value: Some("Bar".to_string()) },
+            GenericParam { name: "lr".to_string(), value: None },
+            GenericParam { name: "foo".to_string(), value: Some("baz".to_string()) },
+        ];
+
+        assert_eq!(param(&params, "FOO"), Some("Bar"));
+        assert_eq!(param(&params, "LR"), Some(""));
+        assert_eq!(param(&params, "maddr"), None);
+    }
+
+    fn encoding(coding: &str, q: Option<f32>) -> Encoding {
+        Encoding {
+            coding: match coding {
+                "*" => ContentCoding::Any,
+                other => ContentCoding::Other(other.to_string()),
+            },
+            params: q.into_iter().map(AcceptParam::Q).collect(),
+        }
+    }
+
+    #[test]
+    fn negotiate_encoding_follows_q_values() {
+        let accept = [
+            encoding("gzip", Some(0.5)),
+            encoding("deflate", None),
+            encoding("br", Some(0.0)),
+        ];
+
+        assert_eq!(Header::negotiate_encoding(&accept, &["gzip", "deflate"]), Some("deflate".to_string()));
+        assert_eq!(Header::negotiate_encoding(&accept, &["GZIP", "br"]), Some("GZIP".to_string()));
+        assert_eq!(Header::negotiate_encoding(&accept, &["br", "compress"]), None);
+        assert_eq!(Header::negotiate_encoding(&accept, &["identity", "gzip"]), Some("gzip".to_string()));
+        assert_eq!(Header::negotiate_encoding(&accept, &["br", "identity"]), Some("identity".to_string()));
+    }
+
+    #[test]
+    fn negotiate_encoding_applies_wildcard_to_unlisted_encodings() {
+        let accept = [encoding("*", Some(0.0)), encoding("gzip", Some(0.2))];
+
+        assert_eq!(Header::negotiate_encoding(&accept, &["deflate", "gzip"]), Some("gzip".to_string()));
+        assert_eq!(Header::negotiate_encoding(&accept, &["deflate", "identity"]), None);
+        assert_eq!(Header::negotiate_encoding(&[encoding("*", None)], &["br", "gzip"]), Some("br".to_string()));
+    }
+
+    #[test]
+    fn negotiate_encoding_with_empty_header_only_accepts_identity() {
+        assert_eq!(Header::negotiate_encoding(&[], &["gzip", "identity"]), Some("identity".to_string()));
+        assert_eq!(Header::negotiate_encoding(&[], &["gzip"]), None);
+    }
+
+    #[test]
+    fn best_language_match_honours_wildcard_exclusion() {
+        let header = Header::AcceptLanguage(vec![
+            language("*", Some(0.0)),
+            language("en", Some(1.0)),
+        ]);
+
+        assert_eq!(header.best_language_match(&["fr", "en"]), Some("en"));
+        assert_eq!(header.best_language_match(&["fr", "de"]), None);
+    }
+
+    #[test]
+    fn best_language_match_matches_prefixes() {
+        let header = Header::AcceptLanguage(vec![language("en", None)]);
+
+        assert_eq!(header.best_language_match(&["fr-FR", "en-US"]), Some("en-US"));
+        assert_eq!(header.best_language_match(&["english"]), None);
+    }
+
+    #[test]
+    fn best_language_match_prefers_most_specific_range() {
+        let header = Header::AcceptLanguage(vec![
+            language("en", Some(0.8)),
+            language("en-GB", Some(0.0)),
+            language("da", Some(0.9)),
+        ]);
+
+        assert_eq!(header.best_language_match(&["en-GB", "en-US"]), Some("en-US"));
+        assert_eq!(header.best_language_match(&["en-US", "da"]), Some("da"));
+    }
+
+    #[test]
+    fn contact_exposes_uri_params() {
+        let contact = Contact {
+            addr: Uri::Sip("sip:alice@192.0.2.4;transport=tcp;maddr=192.0.2.5".parse().unwrap()),
+            name: None,
+            params: vec![ContactParam::Expires(3600)],
+        };
+
+        assert_eq!(contact.uri_params(), &[
+            URIParam::Transport(Transport::TCP),
+            URIParam::MAddr("192.0.2.5".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn absolute_uris_have_no_uri_params() {
+        let to = To {
+            addr: Uri::Absolute("tel:+12125551212".to_string()),
+            name: None,
+            params: vec![],
+        };
+
+        assert!(to.uri_params().is_empty());
+    }
+
+    #[test]
+    fn best_language_match_requires_accept_language() {
+        let header = Header::Subject(None);
+
+        assert_eq!(header.best_language_match(&["en"]), None);
+    }
+}
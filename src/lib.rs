@@ -19,20 +19,66 @@
 //!     .map(handle_sip_message);
 //! ```
 //!
+//! ## Cargo features
+//!
+//! - `std` (enabled by default): use the standard library. Without it, xylosip is `no_std` and
+//!   only requires `core` and `alloc`. **Note**: `no_std` builds rely on `core::net` and
+//!   `core::error`, and so require Rust 1.81.
+//! - `sdp`: parsing of SDP (`application/sdp`) bodies, see the `sdp` module.
+//! - `slog`: implements `slog::Value` for `Method`, so that it can be used as a logging value.
+//! - `generate`: generation of random tags and Call-IDs (`sip::gen_tag`, `sip::gen_call_id`),
+//!   using the randomness source of the operating system.
+//! - `serde`: implements `Serialize` and `Deserialize` for the public types of the `header`,
+//!   `sip` and `request` modules, as well as `Response` (`RequestRef` is only serializable).
+//!   Serde's default representation is used, and is considered part of the API: struct fields
+//!   keep their Rust name (e.g. `request_line`, `call_id`), and enums are externally tagged by
+//!   their variant name (e.g. `{"CallID": "a84b4c76e66710"}` for a Call-ID header).
+//!
 //! [1]: https://tools.ietf.org/html/rfc3261
 //! [2]: https://tools.ietf.org/html/rfc2806
 //! [3]: https://tools.ietf.org/html/rfc2234
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// The items of `alloc` that the standard library's prelude would otherwise provide
+mod prelude {
+    pub use alloc::{
+        borrow::ToOwned,
+        format,
+        string::{ String, ToString },
+        vec,
+        vec::Vec,
+    };
+}
+
+/// `std::error` and `std::net`, or their `core` counterparts when building without `std`
+#[cfg(feature = "std")]
+use std::{ error, net, };
+#[cfg(not(feature = "std"))]
+use core::{ error, net, };
+
 mod parser;
 mod message;
+mod options;
 /// contains request-related code
 pub mod request;
 mod response;
 /// contains header-related code
 pub mod header;
+/// contains code related to message bodies
+pub mod body;
 /// Generic data structures related to SIP
 pub mod sip;
+/// contains code related to SDP bodies
+#[cfg(feature = "sdp")]
+pub mod sdp;
+/// contains code used to serialize SIP data
+pub mod encode;
 
-pub use message::Message;
-pub use request::Request;
-pub use response::Response;
+pub use message::{ Message, ParseReport, };
+pub use options::ParseOptions;
+pub use parser::rfc3261::{ scan_header_names, HeaderIter, };
+pub use request::{ Request, RequestRef, };
+pub use response::{ Capabilities, Response, };
@@ -1,7 +1,13 @@
+use crate::prelude::*;
+use core::ops::Range;
+
+use crate::header::Header;
 use crate::request::Request;
 use crate::response::Response;
 use crate::parser::rfc3261;
 use crate::parser::{ Error, ErrorKind, };
+use crate::ParseOptions;
+use crate::encode::EncodeError;
 
 /// Representation of a SIP message (either a Request or a Response)
 ///
@@ -13,18 +19,89 @@ pub enum Message {
     Response(Response),
 }
 
+/// Result of `Message::parse_diagnostics`: the headers that could be parsed, and every failure
+#[derive(PartialEq, Debug)]
+pub struct ParseReport<'a> {
+    /// the headers that could be parsed, in order
+    pub headers: Vec<Header>,
+
+    /// the span in the parsed buffer of each line that couldn't be parsed (the start line or a
+    /// header, without its terminating CRLF), along with the reason, in order
+    pub errors: Vec<(Range<usize>, ErrorKind<'a, &'a [u8]>)>,
+}
+
+impl<'a> ParseReport<'a> {
+    /// Whether no failure was found
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Message {
+    /// Serializes the message to its wire format
+    ///
+    /// See `Request::to_bytes` and `Response::to_bytes`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        match self {
+            Self::Request(req) => req.to_bytes(),
+            Self::Response(resp) => resp.to_bytes(),
+        }
+    }
+}
+
 impl<'a> Message {
     /// Attempts to parse a byte-slice representation of a SIP message
     ///
     /// This method should be the primary way to parse data coming from the network, as it is
     /// rarely known whether the next message that will arrive on the wire will be a request or a
     /// response (unless a connected protocol is used).
+    ///
+    /// A truncated message fails with `ErrorKind::Incomplete` rather than with the error of the
+    /// header or body it was cut in.
     pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &[u8]>> {
         match rfc3261::message(input) {
             Ok((_, msg)) => Ok(msg),
-            Err(nom::Err::Failure(err)) => Err(err),
-            Err(nom::Err::Error(err)) => Err(err),
-            Err(_err) => Err(Error::new(ErrorKind::UnknownError)),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Error(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+        }
+    }
+
+    /// Attempts to parse a byte-slice representation of a SIP message, according to `options`
+    ///
+    /// See `ParseOptions` for the behaviours that can be changed. `parse` is equivalent to this
+    /// method with the default options.
+    pub fn parse_with_options(input: &'a [u8], options: ParseOptions) -> Result<Self, Error<'a, &'a [u8]>> {
+        match rfc3261::message_with_options(input, options) {
+            Ok((_, msg)) => Ok(msg),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Error(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+        }
+    }
+
+    /// Parses every header of a SIP message independently, reporting all the failures
+    ///
+    /// Unlike `parse`, which stops at the first problem, this goes through the whole start line
+    /// and headers, which is what conformance testing tools need. The body is not examined, and
+    /// the presence of mandatory headers is not checked.
+    pub fn parse_diagnostics(input: &'a [u8]) -> ParseReport<'a> {
+        rfc3261::message_diagnostics(input)
+    }
+
+    /// Parses the first message of a stream-oriented transport (e.g. TCP or TLS)
+    ///
+    /// The body is read according to the Content-Length header, rather than extending to the end
+    /// of the input. Along with the message, the number of bytes it spans is returned, so that
+    /// the caller can advance its buffer to the next message. `Ok(None)` is returned when `input`
+    /// doesn't contain a complete message yet, and more bytes should be read from the stream.
+    /// Keepalive CRLFs preceding the message are skipped, and counted in the returned length.
+    pub fn parse_stream(input: &'a [u8]) -> Result<Option<(Self, usize)>, Error<'a, &'a [u8]>> {
+        match rfc3261::message_stream(input) {
+            Ok((rest, msg)) => Ok(Some((msg, input.len() - rest.len()))),
+            Err(nom::Err::Incomplete(_)) => Ok(None),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Error(err)) => Err(err.locate(input)),
         }
     }
 }
@@ -38,4 +115,121 @@ mod tests {
         let bytes = include_bytes!("../assets/invite.sip");
         assert_eq!(Message::parse(bytes).is_err(), false);
     }
+
+    #[test]
+    fn message_parse_can_read_a_response() {
+        let bytes = &crate::response::ok_200_with_body()[..];
+        assert!(matches!(Message::parse(bytes), Ok(Message::Response(_))));
+    }
+
+    #[test]
+    fn message_parse_diagnostics_reports_every_failure() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+CSeq: abc INVITE\r\n\
+Max-Forwards: 70\r\n\
+Expires: 4294967296\r\n\
+\r\n";
+        let report = Message::parse_diagnostics(bytes);
+
+        assert!(!report.is_valid());
+        assert_eq!(report.headers.len(), 2);
+        assert_eq!(report.headers[1], Header::MaxForwards(70));
+        assert_eq!(report.errors, vec![
+            (108..124, ErrorKind::InvalidCSeq(b"abc INVITE")),
+            (144..163, ErrorKind::InvalidIntegerError),
+        ]);
+        assert_eq!(&bytes[108..124], b"CSeq: abc INVITE");
+    }
+
+    #[test]
+    fn message_parse_diagnostics_reports_invalid_start_line() {
+        let report = Message::parse_diagnostics(b"SIP/2.0 700 Unknown\r\nCall-ID: a84b4c76e66710\r\n\r\n");
+
+        assert_eq!(report.errors, vec![(0..19, ErrorKind::InvalidStatusCode(b"700"))]);
+        assert_eq!(report.headers, vec![Header::CallID("a84b4c76e66710".to_string())]);
+        assert!(Message::parse_diagnostics(include_bytes!("../assets/invite.sip")).is_valid());
+    }
+
+    #[test]
+    fn message_parse_reports_request_errors_for_requests() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\nVia garbage\r\n\r\n";
+        let err = Message::parse(bytes).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Nom(&b"Via garbage\r\n\r\n"[..], nom::error::ErrorKind::Tag));
+        assert_eq!(err.offset, Some(43));
+        assert!(err.snippet.unwrap().ends_with(b"SIP/2.0\r\nVia garbage\r\n\r\n"));
+    }
+
+    #[test]
+    fn message_parse_reports_response_errors_for_responses() {
+        let bytes = b"SIP/2.0 700 Unknown\r\n\r\n";
+        let err = Message::parse(bytes).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidStatusCode(b"700"));
+    }
+
+    #[test]
+    fn message_parse_reports_truncated_messages_as_incomplete() {
+        let invite = include_bytes!("../assets/invite.sip");
+        let head = invite.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+
+        // cut in the middle of the headers
+        let err = Message::parse(&invite[..head / 2]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Incomplete(None));
+
+        // cut in the middle of the body
+        let err = Message::parse(&invite[..invite.len() - 10]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Incomplete(Some(10)));
+        assert!(Request::parse(&invite[..invite.len() - 10]).is_err());
+
+        let err = Response::parse(b"SIP/2.0 200 OK\r\nContent-Length: 4\r\n\r\nv=").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Incomplete(Some(2)));
+    }
+
+    #[test]
+    fn message_parse_stream_returns_the_consumed_length() {
+        let invite = include_bytes!("../assets/invite.sip");
+        let head = invite.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+
+        // only the headers have been received
+        assert_eq!(Message::parse_stream(&invite[..head]).unwrap(), None);
+
+        let mut stream = invite.to_vec();
+        stream.extend_from_slice(b"SIP/2.0 100 Trying\r\n");
+        let (message, consumed) = Message::parse_stream(&stream).unwrap().unwrap();
+
+        assert!(matches!(message, Message::Request(_)));
+        assert_eq!(&stream[consumed..], b"SIP/2.0 100 Trying\r\n");
+    }
+
+    #[test]
+    fn message_parse_stream_rejects_malformed_messages() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\nVia garbage\r\n\r\n";
+        assert!(Message::parse_stream(bytes).is_err());
+    }
+
+    /// Every message of the `assets` directory, completed with the body it leaves out if any
+    fn assets() -> Vec<(&'static str, Vec<u8>)> {
+        vec![
+            ("invite.sip", include_bytes!("../assets/invite.sip").to_vec()),
+            ("200ok.sip", crate::response::ok_200_with_body()),
+        ]
+    }
+
+    /// Parses `bytes`, leaving out the raw content of responses, which isn't reproduced verbatim
+    fn parse_structure(bytes: &[u8]) -> Message {
+        match Message::parse(bytes).unwrap() {
+            Message::Response(resp) => Message::Response(Response { content: vec![], ..resp }),
+            req => req,
+        }
+    }
+
+    #[test]
+    fn message_to_bytes_round_trips_assets() {
+        for (name, bytes) in assets() {
+            let message = parse_structure(&bytes);
+            let reparsed = parse_structure(&message.to_bytes().unwrap());
+
+            assert_eq!(reparsed, message, "{}", name);
+        }
+    }
 }
@@ -0,0 +1,17 @@
+/// Options controlling how messages are parsed
+///
+/// The default options parse strictly: any malformed header causes the whole message to be
+/// rejected.
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct ParseOptions {
+    /// whether a known header whose value is malformed is kept as an extension header (its name
+    /// and raw value), rather than causing the whole message to be rejected. The names of the
+    /// headers kept this way are listed in the `downgraded_headers` field of the parsed message.
+    pub lenient_headers: bool,
+
+    /// whether a header that can't be parsed at all (not even as an extension header) is
+    /// skipped, rather than causing the whole message to be rejected. The raw lines of the
+    /// headers skipped this way are kept in the `unparsed` field of the parsed message, and the
+    /// other headers are still available.
+    pub skip_malformed_headers: bool,
+}
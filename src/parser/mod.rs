@@ -1,5 +1,10 @@
-mod rfc2806;
+pub(crate) mod rfc2806;
 pub mod rfc3261;
+#[cfg(feature = "sdp")]
+pub(crate) mod rfc4566;
+
+use crate::prelude::*;
+use core::fmt;
 
 use nom::error::ParseError;
 use super::request::InvalidRequestError;
@@ -7,18 +12,43 @@ use super::request::InvalidRequestError;
 #[derive(PartialEq, Debug)]
 pub struct Error<'a, I> {
     pub kind: ErrorKind<'a, I>,
+
+    /// the position of the failure in the parsed buffer, when it could be determined
+    pub offset: Option<usize>,
+
+    /// a few bytes of the parsed buffer around the failure, when its position is known
+    pub snippet: Option<&'a [u8]>,
+
     backtrace: Vec<Error<'a, I>>
 }
 
 #[derive(PartialEq, Debug)]
 pub enum ErrorKind<'a, I> {
     Nom(I, nom::error::ErrorKind),
-    ParseIntError(std::num::ParseIntError),
-    Utf8Error(std::str::Utf8Error),
+    ParseIntError(core::num::ParseIntError),
+    Utf8Error(core::str::Utf8Error),
     InvalidHostname(&'a [u8]),
     InvalidDomainPart(&'a [u8]),
     InvalidIntegerError,
     InvalidTTLValue,
+    DuplicateUriParam,
+    InvalidCSeq(&'a [u8]),
+    InvalidStatusCode(&'a [u8]),
+    InvalidPort(&'a [u8]),
+    InvalidQValue(&'a [u8]),
+    InvalidTimestamp(&'a [u8]),
+    InvalidTime(&'a [u8]),
+    InvalidMultipartBody,
+    InvalidContentLength,
+    InvalidEmbeddedUri,
+    NestingTooDeep,
+    InvalidSdp(&'a [u8]),
+    /// a param identifying a dialog (e.g. `to-tag` in a Join header) is missing from the header
+    MissingDialogTag(&'a [u8], &'static str),
+    UnexpectedContentType,
+    /// the message is truncated: the empty line ending its headers is missing, or its body is
+    /// shorter than its Content-Length. The number of missing body bytes is given if known.
+    Incomplete(Option<usize>),
     InvalidRequest(InvalidRequestError),
     UnknownError,
 }
@@ -27,11 +57,121 @@ impl<'a, I> Error<'a, I> {
     pub fn new(kind: ErrorKind<'a, I>) -> Self {
         Self {
             kind,
+            offset: None,
+            snippet: None,
             backtrace: vec![],
         }
     }
 }
 
+/// Number of bytes kept on each side of the failure in `Error::snippet`
+const SNIPPET_CONTEXT: usize = 16;
+
+impl<'a> ErrorKind<'a, &'a [u8]> {
+    /// Returns the input the error refers to, if any
+    fn input(&self) -> Option<&'a [u8]> {
+        match self {
+            Self::Nom(input, _) => Some(input),
+            Self::InvalidHostname(input) => Some(input),
+            Self::InvalidDomainPart(input) => Some(input),
+            Self::InvalidCSeq(input) => Some(input),
+            Self::InvalidStatusCode(input) => Some(input),
+            Self::InvalidPort(input) => Some(input),
+            Self::InvalidQValue(input) => Some(input),
+            Self::InvalidTimestamp(input) => Some(input),
+            Self::InvalidTime(input) => Some(input),
+            Self::InvalidSdp(input) => Some(input),
+            Self::MissingDialogTag(input, _) => Some(input),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Error<'a, &'a [u8]> {
+    /// Records where in `buffer` the error occurred
+    ///
+    /// The position is computed from the input held by the error (or by the innermost error of
+    /// its backtrace), which must be a subslice of `buffer`.
+    pub(crate) fn locate(mut self, buffer: &'a [u8]) -> Self {
+        let start = buffer.as_ptr() as usize;
+        let offset = core::iter::once(&self.kind)
+            .chain(self.backtrace.iter().map(|err| &err.kind))
+            .filter_map(ErrorKind::input)
+            .map(|input| (input.as_ptr() as usize).wrapping_sub(start))
+            .find(|offset| *offset <= buffer.len());
+
+        if let Some(offset) = offset {
+            let from = offset.saturating_sub(SNIPPET_CONTEXT);
+            let to = core::cmp::min(offset + SNIPPET_CONTEXT, buffer.len());
+
+            self.offset = Some(offset);
+            self.snippet = Some(&buffer[from..to]);
+        }
+
+        self
+    }
+}
+
+impl<'a, I> fmt::Display for ErrorKind<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lossy = String::from_utf8_lossy;
+
+        match self {
+            Self::Nom(_, kind) => write!(f, "parse error: {}", kind.description()),
+            Self::ParseIntError(err) => write!(f, "invalid integer: {}", err),
+            Self::Utf8Error(err) => write!(f, "invalid UTF-8: {}", err),
+            Self::InvalidHostname(host) => write!(f, "invalid hostname: {}", lossy(host)),
+            Self::InvalidDomainPart(part) => write!(f, "invalid domain part: {}", lossy(part)),
+            Self::InvalidIntegerError => f.write_str("invalid integer"),
+            Self::InvalidTTLValue => f.write_str("invalid TTL value"),
+            Self::DuplicateUriParam => f.write_str("duplicate URI parameter"),
+            Self::InvalidCSeq(cseq) => write!(f, "invalid CSeq: {}", lossy(cseq)),
+            Self::InvalidStatusCode(code) => write!(f, "invalid status code: {}", lossy(code)),
+            Self::InvalidPort(port) => write!(f, "invalid port: {}", lossy(port)),
+            Self::InvalidQValue(q) => write!(f, "invalid q value: {}", lossy(q)),
+            Self::InvalidTimestamp(ts) => write!(f, "invalid timestamp: {}", lossy(ts)),
+            Self::InvalidTime(time) => write!(f, "invalid time: {}", lossy(time)),
+            Self::InvalidMultipartBody => f.write_str("invalid multipart body"),
+            Self::InvalidContentLength => f.write_str("invalid Content-Length"),
+            Self::InvalidEmbeddedUri => f.write_str("invalid embedded URI"),
+            Self::NestingTooDeep => f.write_str("URIs are nested too deeply"),
+            Self::InvalidSdp(line) => write!(f, "invalid SDP: {}", lossy(line)),
+            Self::MissingDialogTag(_, tag) => write!(f, "missing {} param", tag),
+            Self::UnexpectedContentType => f.write_str("unexpected Content-Type"),
+            Self::Incomplete(Some(needed)) => write!(f, "incomplete input: {} more bytes needed", needed),
+            Self::Incomplete(None) => f.write_str("incomplete input"),
+            Self::InvalidRequest(err) => write!(f, "invalid request: {}", err),
+            Self::UnknownError => f.write_str("unknown error"),
+        }
+    }
+}
+
+impl<'a, I> fmt::Display for Error<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {}", offset)?;
+        }
+
+        match self.snippet {
+            Some(snippet) => write!(f, " (near {:?})", String::from_utf8_lossy(snippet)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, I: fmt::Debug> crate::error::Error for Error<'a, I> {
+    fn source(&self) -> Option<&(dyn crate::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::ParseIntError(err) => Some(err),
+            ErrorKind::Utf8Error(err) => Some(err),
+            ErrorKind::InvalidRequest(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl<'a, I> ParseError<I> for Error<'a, I> {
     fn from_error_kind(input: I, kind: nom::error::ErrorKind) -> Self {
         Self::new(ErrorKind::Nom(input, kind))
@@ -43,14 +183,23 @@ impl<'a, I> ParseError<I> for Error<'a, I> {
     }
 }
 
-impl<'a, I> From<std::num::ParseIntError> for Error<'a, I> {
-    fn from(error: std::num::ParseIntError) -> Self {
+impl<'a, I> From<nom::Needed> for Error<'a, I> {
+    fn from(needed: nom::Needed) -> Self {
+        Self::new(ErrorKind::Incomplete(match needed {
+            nom::Needed::Size(size) => Some(size.get()),
+            nom::Needed::Unknown => None,
+        }))
+    }
+}
+
+impl<'a, I> From<core::num::ParseIntError> for Error<'a, I> {
+    fn from(error: core::num::ParseIntError) -> Self {
         Self::new(ErrorKind::ParseIntError(error))
     }
 }
 
-impl<'a, I> From<std::str::Utf8Error> for Error<'a, I> {
-    fn from(error: std::str::Utf8Error) -> Self {
+impl<'a, I> From<core::str::Utf8Error> for Error<'a, I> {
+    fn from(error: core::str::Utf8Error) -> Self {
         Self::new(ErrorKind::Utf8Error(error))
     }
 }
@@ -64,7 +213,7 @@ impl<'a, I> From<InvalidRequestError> for Error<'a, I> {
 type Result<'a, I, T> = nom::IResult<I, T, Error<'a, I>>;
 
 fn integer<T>(input: &[u8]) -> Result<&[u8], T>
-   where T: atoi::FromRadix10Checked
+   where T: atoi::FromRadix10SignedChecked
 {
     let (input, i) = nom::character::complete::digit1(input)?;
 
@@ -75,3 +224,46 @@ fn integer<T>(input: &[u8]) -> Result<&[u8], T>
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error as _;
+
+    #[test]
+    fn error_display_describes_the_error() {
+        let err: Error<&[u8]> = Error::new(ErrorKind::InvalidHostname(b"exa mple.com"));
+
+        assert_eq!(err.to_string(), "invalid hostname: exa mple.com");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn error_source_is_the_wrapped_error() {
+        let utf8 = String::from_utf8(vec![0xff]).unwrap_err().utf8_error();
+        let err: Error<&[u8]> = utf8.into();
+
+        assert_eq!(err.to_string(), format!("invalid UTF-8: {}", utf8));
+        assert_eq!(err.source().unwrap().to_string(), utf8.to_string());
+    }
+
+    #[test]
+    fn error_locate_records_offset_and_snippet() {
+        let buffer: &[u8] = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n";
+        let err = Error::new(ErrorKind::InvalidHostname(&buffer[20..])).locate(buffer);
+
+        assert_eq!(err.offset, Some(20));
+        assert_eq!(err.snippet, Some(&buffer[4..36]));
+        assert!(err.to_string().ends_with("at offset 20 (near \"TE sip:bob@biloxi.example.com SI\")"));
+    }
+
+    #[test]
+    fn error_from_needed_is_incomplete() {
+        let err: Error<&[u8]> = nom::Needed::new(3).into();
+        assert_eq!(err.kind, ErrorKind::Incomplete(Some(3)));
+        assert_eq!(err.to_string(), "incomplete input: 3 more bytes needed");
+
+        let err: Error<&[u8]> = nom::Needed::Unknown.into();
+        assert_eq!(err.kind, ErrorKind::Incomplete(None));
+    }
+}
@@ -1,6 +1,6 @@
 use nom::{
-    combinator::{ opt, recognize },
-    sequence::{ pair, tuple },
+    combinator::{ all_consuming, opt, recognize, verify },
+    sequence::{ pair, preceded, tuple },
     branch::alt,
     multi::{ many0, many1 },
     character::{ is_digit, is_alphanumeric },
@@ -59,23 +59,26 @@ fn pause_character(input: &[u8]) -> Result<&[u8], &[u8]> {
 
 fn local_phone_number(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(
-        many1(
-            tuple((
-                alt((phone_digit, dtmf_digit, pause_character)),
-                opt(isdn_subaddress),
-                opt(post_dial),
-                area_specifier,
-                many0(alt((area_specifier, service_provider, future_extension)))
-            ))
-        )
+        tuple((
+            many1(alt((phone_digit, dtmf_digit, pause_character))),
+            opt(isdn_subaddress),
+            opt(post_dial),
+            area_specifier,
+            many0(alt((area_specifier, service_provider, future_extension)))
+        ))
     )(input)
 }
 
+// A global number made only of visual separators (e.g. `+---`) isn't a number at all
+fn has_digit(number: &[u8]) -> bool {
+    number.iter().any(|&c| is_digit(c))
+}
+
 fn global_phone_number(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(
         tuple((
             tag("+"),
-            base_phone_number,
+            verify(base_phone_number, has_digit),
             opt(isdn_subaddress),
             opt(post_dial),
             many0(alt((area_specifier, service_provider, future_extension)))
@@ -90,6 +93,10 @@ pub fn telephone_subscriber(input: &[u8]) -> Result<&[u8], &[u8]> {
     ))(input)
 }
 
+pub fn telephone_url(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    all_consuming(preceded(tag_no_case("tel:"), telephone_subscriber))(input)
+}
+
 fn service_provider(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(pair(tag_no_case(";tsp="), hostname))(input)
 }
@@ -214,3 +221,22 @@ fn phone_context_ident(input: &[u8]) -> Result<&[u8], &[u8]> {
 fn area_specifier(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(pair(tag(";phone-context="), phone_context_ident))(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_phone_number_accepts_visual_separators_between_digits() {
+        assert_eq!(
+            global_phone_number(b"+1-800-555-0100"),
+            Ok((&b""[..], &b"+1-800-555-0100"[..]))
+        );
+    }
+
+    #[test]
+    fn global_phone_number_requires_at_least_one_digit() {
+        assert!(global_phone_number(b"+---").is_err());
+        assert!(telephone_url(b"tel:+().-").is_err());
+    }
+}
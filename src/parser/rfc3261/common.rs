@@ -1,7 +1,11 @@
+use crate::prelude::*;
 use crate::{
     sip::{
+        Host,
         Method,
+        SipUri,
         Transport,
+        Uri,
         User,
         Version,
     },
@@ -21,27 +25,38 @@ use crate::{
 };
 
 use nom::{
-    combinator::{ opt, recognize, rest },
-    sequence::{ pair, tuple, preceded, separated_pair, },
+    combinator::{ map, opt, recognize, rest, verify },
+    sequence::{ pair, tuple, preceded, terminated, separated_pair, },
     branch::alt,
-    multi::{ many0, many1, many_m_n, separated_list, separated_nonempty_list, },
+    multi::{ many0, many1, many_m_n, separated_list0, separated_list1, },
     character::{ is_digit, is_hex_digit },
     character::complete::alpha1,
     bytes::complete::{
         tag,
         tag_no_case,
         take_while,
-        take_while1,
         take_while_m_n,
     },
 };
 
+/// Decodes the `%HH` escapes of a URI component into a string
+fn unescaped(input: &[u8]) -> core::result::Result<String, nom::Err<Error<'_, &[u8]>>> {
+    String::from_utf8(super::unescape(input))
+        .map_err(|err| nom::Err::Failure(err.utf8_error().into()))
+}
+
 pub fn message_body(input: &[u8]) -> Result<&[u8], Vec<u8>> {
-    let (input, body) = rest(input)?;
+    let (input, body) = message_body_ref(input)?;
 
     Ok((input, body.to_vec()))
 }
 
+/// Takes the remainder of the input as the message body. An empty body is treated as no body at
+/// all, which is what `opt(message_body)` relies on.
+pub fn message_body_ref(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    verify(rest, |body: &[u8]| !body.is_empty())(input)
+}
+
 fn user_info(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(
         tuple((
@@ -55,28 +70,86 @@ fn user_info(input: &[u8]) -> Result<&[u8], &[u8]> {
     )(input)
 }
 
-pub fn sip_uri(input: &[u8]) -> Result<&[u8], &[u8]> {
-    recognize(preceded(
-        tag_no_case("sip:"),
-        tuple((
-            opt(user_info),
-            host_port,
-            uri_parameters,
-            headers,
-        ))
-    ))(input)
+fn uri_user_host(input: &[u8]) -> Result<'_, &[u8], SipUri> {
+    let (input, (user_info, (host, port))) = pair(
+        opt(terminated(
+            pair(
+                alt((
+                    tokens::user,
+                    telephone_subscriber,
+                )),
+                opt(preceded(tag(":"), tokens::password)),
+            ),
+            tag("@"),
+        )),
+        host_port,
+    )(input)?;
+
+    let (user, password) = match user_info {
+        Some((user, password)) => (Some(user), password),
+        None => (None, None),
+    };
+
+    let user = match user {
+        Some(user) => Some(unescaped(user)?),
+        None => None,
+    };
+    let password = match password {
+        Some(password) => Some(unescaped(password)?),
+        None => None,
+    };
+    Ok((input, SipUri {
+        secure: false,
+        user,
+        password,
+        host,
+        port,
+        params: vec![],
+        headers: vec![],
+    }))
 }
 
-pub fn sips_uri(input: &[u8]) -> Result<&[u8], &[u8]> {
-    recognize(preceded(
-        tag_no_case("sips:"),
-        tuple((
-            opt(user_info),
-            host_port,
-            uri_parameters,
-            headers,
-        ))
-    ))(input)
+fn uri_components(input: &[u8]) -> Result<'_, &[u8], SipUri> {
+    let (input, (uri, params, headers)) = tuple((
+        uri_user_host,
+        uri_parameters,
+        opt(headers),
+    ))(input)?;
+
+    Ok((input, SipUri {
+        params,
+        headers: headers.unwrap_or_default(),
+        ..uri
+    }))
+}
+
+pub fn sip_uri(input: &[u8]) -> Result<&[u8], SipUri> {
+    preceded(tag_no_case("sip:"), uri_components)(input)
+}
+
+pub fn sips_uri(input: &[u8]) -> Result<&[u8], SipUri> {
+    let (input, uri) = preceded(tag_no_case("sips:"), uri_components)(input)?;
+
+    Ok((input, SipUri {
+        secure: true,
+        ..uri
+    }))
+}
+
+/// Parses a SIP or SIPS URI that is not enclosed in angle brackets
+///
+/// Such URIs can't carry URI parameters or headers: anything following a semicolon belongs to
+/// the header the URI is part of.
+pub fn bare_sip_uri(input: &[u8]) -> Result<'_, &[u8], SipUri> {
+    let (input, (scheme, uri)) = pair(
+        alt((tag_no_case("sips:"), tag_no_case("sip:"))),
+        uri_user_host,
+    )(input)?;
+
+    Ok((input, SipUri {
+        secure: scheme.len() == 5,
+        ..uri
+    }))
 }
 
 fn top_label(input: &[u8]) -> Result<&[u8], &[u8]> {
@@ -139,8 +212,15 @@ pub fn ipv4_address(input: &[u8]) -> Result<&[u8], &[u8]> {
     )))(input)
 }
 
-pub fn port(input: &[u8]) -> Result<&[u8], i32> {
-    integer(input)
+pub fn port(input: &[u8]) -> Result<&[u8], u16> {
+    let (input, digits) = nom::character::complete::digit1(input)?;
+
+    match atoi::atoi(digits) {
+        Some(port) => Ok((input, port)),
+        None => Err(nom::Err::Failure(
+            Error::new(ErrorKind::InvalidPort(digits))
+        )),
+    }
 }
 
 fn hex4(input: &[u8]) -> Result<&[u8], &[u8]> {
@@ -185,15 +265,49 @@ fn ipv6_reference(input: &[u8]) -> Result<&[u8], &[u8]> {
     )(input)
 }
 
-pub fn host(input: &[u8]) -> Result<&[u8], &[u8]> {
+fn host_ipv4(input: &[u8]) -> Result<'_, &[u8], Host> {
+    let (input, addr) = ipv4_address(input)?;
+
+    let addr = core::str::from_utf8(addr)
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    match addr.parse() {
+        Ok(addr) => Ok((input, Host::IpV4(addr))),
+        Err(_) => Ok((input, Host::Domain(addr.to_string()))),
+    }
+}
+
+fn host_ipv6(input: &[u8]) -> Result<'_, &[u8], Host> {
+    let (input, reference) = ipv6_reference(input)?;
+
+    let addr = core::str::from_utf8(&reference[1..reference.len() - 1])
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    match addr.parse() {
+        Ok(addr) => Ok((input, Host::IpV6(addr))),
+        Err(_) => Ok((input, Host::Domain(format!("[{}]", addr)))),
+    }
+}
+
+fn host_domain(input: &[u8]) -> Result<'_, &[u8], Host> {
+    let (input, domain) = hostname(input)?;
+
+    let domain = core::str::from_utf8(domain)
+        .map(|s| s.to_string())
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    Ok((input, Host::Domain(domain)))
+}
+
+pub fn host(input: &[u8]) -> Result<&[u8], Host> {
     alt((
-        hostname,
-        ipv4_address,
-        ipv6_reference,
+        host_domain,
+        host_ipv4,
+        host_ipv6,
     ))(input)
 }
 
-pub fn host_port(input: &[u8]) -> Result<&[u8], (&[u8], Option<i32>)> {
+pub fn host_port(input: &[u8]) -> Result<&[u8], (Host, Option<u16>)> {
     pair(
         host,
         opt(preceded(tag(":"), port)),
@@ -310,9 +424,9 @@ pub fn ttl(input: &[u8]) -> Result<&[u8], i32> {
 }
 
 fn uri_parameter_maddr(input: &[u8]) -> Result<&[u8], URIParam> {
-    let (input, maddr) = preceded(tag_no_case("maddr="), host)(input)?;
+    let (input, maddr) = preceded(tag_no_case("maddr="), recognize(host))(input)?;
 
-    let maddr = std::str::from_utf8(maddr)
+    let maddr = core::str::from_utf8(maddr)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -327,28 +441,35 @@ fn uri_parameter_lr(input: &[u8]) -> Result<&[u8], URIParam> {
 
 fn uri_parameter_other(input: &[u8]) -> Result<&[u8], URIParam> {
     let (input, (name, value)) = pair(
-        take_while1(tokens::is_param_char),
+        tokens::pname,
         opt(
             preceded(
                 tag("="),
-                take_while1(tokens::is_param_char),
+                tokens::pname,
             )
         )
     )(input)?;
 
-    let name = std::str::from_utf8(name)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
+    let name = unescaped(name)?;
     let value = match value {
-        Some(v) => Some(std::str::from_utf8(v)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
+        Some(v) => Some(unescaped(v)?),
         None => None,
     };
 
     Ok((input, URIParam::Other(name, value)))
 }
 
+fn uri_parameter_gr(input: &[u8]) -> Result<'_, &[u8], URIParam> {
+    let (rest, param) = uri_parameter_other(input)?;
+
+    match param {
+        URIParam::Other(name, value) if name.eq_ignore_ascii_case("gr") => Ok((rest, URIParam::Gr(value))),
+        _ => Err(nom::Err::Error(
+            Error::new(ErrorKind::Nom(input, nom::error::ErrorKind::Tag))
+        )),
+    }
+}
+
 fn uri_parameter(input: &[u8]) -> Result<&[u8], URIParam> {
     alt((
         uri_parameter_transport,
@@ -357,32 +478,47 @@ fn uri_parameter(input: &[u8]) -> Result<&[u8], URIParam> {
         uri_parameter_ttl,
         uri_parameter_maddr,
         uri_parameter_lr,
+        uri_parameter_gr,
         uri_parameter_other,
     ))(input)
 }
 
+fn is_duplicate_uri_param(params: &[URIParam], param: &URIParam) -> bool {
+    match param {
+        // extension parameters are not validated, so duplicates are tolerated
+        URIParam::Other(_, _) => false,
+        _ => params.iter().any(|p| core::mem::discriminant(p) == core::mem::discriminant(param)),
+    }
+}
+
 fn uri_parameters(input: &[u8]) -> Result<&[u8], Vec<URIParam>> {
-    many0(
+    let (input, params) = many0(
         preceded(
             tag(";"),
             uri_parameter
         )
-    )(input)
+    )(input)?;
+
+    for (i, param) in params.iter().enumerate() {
+        if is_duplicate_uri_param(&params[..i], param) {
+            return Err(nom::Err::Failure(
+                Error::new(ErrorKind::DuplicateUriParam)
+            ));
+        }
+    }
+
+    Ok((input, params))
 }
 
 fn header(input: &[u8]) -> Result<&[u8], URIHeader> {
     let (input, (name, value)) = separated_pair(
-        take_while1(tokens::is_header_char),
+        tokens::hname,
         tag("="),
-        take_while(tokens::is_header_char)
+        tokens::hvalue,
     )(input)?;
 
-    let name = std::str::from_utf8(name)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-    let value = std::str::from_utf8(value)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
+    let name = unescaped(name)?;
+    let value = unescaped(value)?;
 
     Ok((input, URIHeader {
         name,
@@ -393,7 +529,7 @@ fn header(input: &[u8]) -> Result<&[u8], URIHeader> {
 fn headers(input: &[u8]) -> Result<&[u8], Vec<URIHeader>> {
     preceded(
         tag("?"),
-        separated_list(tag("&"), header)
+        separated_list0(tag("&"), header)
     )(input)
 }
 
@@ -497,14 +633,14 @@ fn scheme(input: &[u8]) -> Result<&[u8], &[u8]> {
 }
 
 fn segment(input: &[u8]) -> Result<&[u8], Vec<&[u8]>> {
-    separated_nonempty_list(
+    separated_list1(
         tag(";"),
         tokens::param
     )(input)
 }
 
 fn path_segments(input: &[u8]) -> Result<&[u8], Vec<Vec<&[u8]>>> {
-    separated_nonempty_list(
+    separated_list1(
         tag("/"),
         segment
     )(input)
@@ -555,14 +691,33 @@ pub fn absolute_uri(input: &[u8]) -> Result<&[u8], &[u8]> {
     ))(input)
 }
 
+fn absolute_uri_value(input: &[u8]) -> Result<'_, &[u8], Uri> {
+    let (input, uri) = absolute_uri(input)?;
+
+    let uri = core::str::from_utf8(uri)
+        .map_err(|err| nom::Err::Failure(Error::from(err)))?;
+
+    Ok((input, Uri::Absolute(uri.to_string())))
+}
+
+/// Parses a SIP or SIPS URI into a `SipUri`, falling back to keeping any
+/// other absolute URI as written
+pub fn uri(input: &[u8]) -> Result<'_, &[u8], Uri> {
+    alt((
+        map(sip_uri, Uri::Sip),
+        map(sips_uri, Uri::Sip),
+        absolute_uri_value,
+    ))(input)
+}
+
 fn gen_value(input: &[u8]) -> Result<&[u8], &str> {
     let (input, value) = alt((
-        host,
+        recognize(host),
         tokens::token,
         tokens::quoted_string,
     ))(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, value))
@@ -592,11 +747,38 @@ pub fn option_tag(input: &[u8]) -> Result<&[u8], Vec<String>> {
     Ok((input, options))
 }
 
-pub fn qvalue(input: &[u8]) -> Result<&[u8], &[u8]> {
+pub fn boolean_true(input: &[u8]) -> Result<'_, &[u8], bool> {
+    let (input, _) = tag_no_case("true")(input)?;
+
+    Ok((input, true))
+}
+
+pub fn boolean_false(input: &[u8]) -> Result<'_, &[u8], bool> {
+    let (input, _) = tag_no_case("false")(input)?;
+
+    Ok((input, false))
+}
+
+/// Parses a `true` or `false` token, ignoring case
+pub fn boolean(input: &[u8]) -> Result<'_, &[u8], bool> {
     alt((
+        boolean_true,
+        boolean_false,
+    ))(input)
+}
+
+pub fn qvalue(input: &[u8]) -> Result<&[u8], f32> {
+    let (input, q) = alt((
         recognize(pair(tag("0"), opt(pair(tag("."), take_while_m_n(0, 3, is_digit))))),
         recognize(pair(tag("1"), opt(pair(tag("."), many_m_n(0, 3, tag("0")))))),
-    ))(input)
+    ))(input)?;
+
+    match core::str::from_utf8(q).ok().and_then(|q| q.parse().ok()) {
+        Some(q) => Ok((input, q)),
+        None => Err(nom::Err::Failure(
+            Error::new(ErrorKind::InvalidQValue(q))
+        )),
+    }
 }
 
 #[cfg(test)]
@@ -680,9 +862,12 @@ mod tests {
     }
 
     #[test]
-    fn port_only_handles_reasonable_values() {
-        assert!(port(b"2147483647") == Ok((b"", 2_147_483_647)));
-        assert!(port(b"2147483648").is_err());
+    fn port_rejects_values_above_65535() {
+        assert!(port(b"65535") == Ok((b"", 65535)));
+        assert_eq!(
+            port(b"70000"),
+            Err(nom::Err::Failure(Error::new(ErrorKind::InvalidPort(b"70000"))))
+        );
     }
 
     #[test]
@@ -707,15 +892,33 @@ mod tests {
 
     #[test]
     fn host_handles_any_kind_of_name_or_address() {
-        assert!(host(b"sip.test.example.com") == Ok((b"", b"sip.test.example.com")));
-        assert!(host(b"127.0.0.1") == Ok((b"", b"127.0.0.1")));
-        assert!(host(b"[::1]") == Ok((b"", b"[::1]")));
+        assert!(host(b"sip.test.example.com") == Ok((b"", Host::Domain("sip.test.example.com".to_string()))));
+        assert!(host(b"127.0.0.1") == Ok((b"", Host::IpV4(crate::net::Ipv4Addr::LOCALHOST))));
+        assert!(host(b"[::1]") == Ok((b"", Host::IpV6(crate::net::Ipv6Addr::LOCALHOST))));
     }
 
     #[test]
     fn host_port_takes_a_host_and_an_optional_port() {
-        assert!(host_port(b"[::1]") == Ok((b"", (b"[::1]", None))));
-        assert!(host_port(b"[::1]:12345") == Ok((b"", (b"[::1]", Some(12345)))));
+        assert!(host_port(b"[::1]") == Ok((b"", (Host::IpV6(crate::net::Ipv6Addr::LOCALHOST), None))));
+        assert!(host_port(b"[::1]:12345") == Ok((b"", (Host::IpV6(crate::net::Ipv6Addr::LOCALHOST), Some(12345)))));
+    }
+
+    #[test]
+    fn boolean_ignores_case() {
+        assert_eq!(boolean(b"true"), Ok((&b""[..], true)));
+        assert_eq!(boolean(b"FALSE;x"), Ok((&b";x"[..], false)));
+        assert_eq!(boolean(b"True"), Ok((&b""[..], true)));
+        assert!(boolean(b"yes").is_err());
+    }
+
+    #[test]
+    fn qvalue_is_converted_to_f32() {
+        assert!(qvalue(b"0") == Ok((b"", 0.0)));
+        assert!(qvalue(b"0.") == Ok((b"", 0.0)));
+        assert!(qvalue(b"0.725") == Ok((b"", 0.725)));
+        assert!(qvalue(b"1.000") == Ok((b"", 1.0)));
+        assert!(qvalue(b"1.5") == Ok((b"5", 1.0)));
+        assert!(qvalue(b"2").is_err());
     }
 
     #[test]
@@ -735,4 +938,34 @@ mod tests {
         let (_, params) = uri_parameters(b";transport=udp").unwrap();
         assert_eq!(params.len(), 1);
     }
+
+    #[test]
+    fn uri_parameters_rejects_conflicting_transports() {
+        match uri_parameters(b";transport=tcp;transport=udp") {
+            Err(nom::Err::Failure(err)) => assert_eq!(err.kind, ErrorKind::DuplicateUriParam),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn uri_parameters_rejects_duplicate_flags() {
+        assert!(uri_parameters(b";lr;lr").is_err());
+    }
+
+    #[test]
+    fn uri_parameters_can_parse_gr() {
+        assert!(uri_parameters(b";gr") == Ok((b"", vec![URIParam::Gr(None)])));
+        assert!(uri_parameters(b";gr=urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6") == Ok((b"", vec![
+            URIParam::Gr(Some("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6".to_string())),
+        ])));
+        assert!(uri_parameters(b";grid=1") == Ok((b"", vec![
+            URIParam::Other("grid".to_string(), Some("1".to_string())),
+        ])));
+    }
+
+    #[test]
+    fn uri_parameters_allows_duplicate_other_params() {
+        let (_, params) = uri_parameters(b";foo=1;foo=2").unwrap();
+        assert_eq!(params.len(), 2);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{ Header, AlertInfo, },
     parser::{
@@ -19,7 +20,7 @@ use crate::{
 
 use nom::{
     sequence::{ pair, preceded, terminated },
-    multi::separated_nonempty_list,
+    multi::separated_list1,
     bytes::complete::tag_no_case,
 };
 
@@ -29,7 +30,7 @@ fn alert_param(input: &[u8]) -> Result<&[u8], AlertInfo> {
         generic_params,
     )(input)?;
 
-    let uri = std::str::from_utf8(uri)
+    let uri = core::str::from_utf8(uri)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, AlertInfo {
@@ -44,7 +45,7 @@ pub fn alert_info(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Alert-Info"),
             header_colon
         ),
-        separated_nonempty_list(comma, alert_param)
+        separated_list1(comma, alert_param)
     )(input)?;
 
     Ok((input, Header::AlertInfo(params)))
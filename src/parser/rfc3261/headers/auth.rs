@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{
         Header,
@@ -29,15 +30,16 @@ use crate::{
                 absolute_uri,
                 abs_path,
                 authority,
+                boolean,
             },
         },
     },
 };
 
 use nom::{
-    combinator::recognize,
-    sequence::{ pair, tuple, preceded, terminated },
-    multi::separated_nonempty_list,
+    combinator::{ opt, recognize },
+    sequence::{ delimited, pair, tuple, preceded, terminated },
+    multi::{ many1, separated_list1 },
     branch::alt,
     bytes::complete::{
         tag,
@@ -54,7 +56,7 @@ fn auth_param(input: &[u8]) -> Result<&[u8], (String, String)> {
         preceded(equal, alt((token, quoted_string)))
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -82,7 +84,7 @@ fn dig_resp_response(input: &[u8]) -> Result<&[u8], DigestResponseParam> {
         request_digest,
     )(input)?;
 
-    let digest = std::str::from_utf8(digest)
+    let digest = core::str::from_utf8(digest)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -98,7 +100,7 @@ fn nonce_count(input: &[u8]) -> Result<&[u8], String> {
         take_while_m_n(8, 8, is_lowercase_hexadecimal)
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -114,7 +116,7 @@ fn cnonce(input: &[u8]) -> Result<&[u8], String> {
         quoted_string,
     )(input)?;
 
-    let cnonce = std::str::from_utf8(cnonce)
+    let cnonce = core::str::from_utf8(cnonce)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -178,7 +180,7 @@ fn dig_resp_uri(input: &[u8]) -> Result<&[u8], DigestResponseParam> {
         terminated(digest_uri_value, right_double_quote)
     )(input)?;
 
-    let uri = std::str::from_utf8(uri)
+    let uri = core::str::from_utf8(uri)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -194,7 +196,7 @@ fn dig_resp_username(input: &[u8]) -> Result<&[u8], DigestResponseParam> {
         quoted_string
     )(input)?;
 
-    let username = std::str::from_utf8(username)
+    let username = core::str::from_utf8(username)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -274,7 +276,7 @@ fn realm(input: &[u8]) -> Result<&[u8], String> {
         quoted_string
     )(input)?;
 
-    let realm = std::str::from_utf8(realm)
+    let realm = core::str::from_utf8(realm)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -290,7 +292,7 @@ fn nonce(input: &[u8]) -> Result<&[u8], String> {
         quoted_string
     )(input)?;
 
-    let nonce = std::str::from_utf8(nonce)
+    let nonce = core::str::from_utf8(nonce)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -306,7 +308,7 @@ fn opaque(input: &[u8]) -> Result<&[u8], String> {
         quoted_string
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -347,13 +349,18 @@ fn algorithm(input: &[u8]) -> Result<&[u8], AlgorithmKind> {
     Ok((input, kind))
 }
 
+/// Parses Digest credentials, leniently
+///
+/// Some clients separate the scheme from the first param with several folded lines or tabs, or
+/// end the list of params with a comma. Both are accepted.
 fn credentials_digest_response(input: &[u8]) -> Result<&[u8], Credentials> {
-    let (input, params) = preceded(
+    let (input, params) = delimited(
         pair(
             tag_no_case("Digest"),
-            linear_whitespace,
+            many1(linear_whitespace),
         ),
-        separated_nonempty_list(comma, dig_resp)
+        separated_list1(comma, dig_resp),
+        opt(comma),
     )(input)?;
 
     Ok((input, Credentials::DigestResponse(params)))
@@ -362,7 +369,7 @@ fn credentials_digest_response(input: &[u8]) -> Result<&[u8], Credentials> {
 fn credentials_other_response(input: &[u8]) -> Result<&[u8], Credentials> {
     let (input, (name, params)) = pair(
         terminated(token_str, linear_whitespace),
-        separated_nonempty_list(comma, auth_param)
+        separated_list1(comma, auth_param)
     )(input)?;
 
     Ok((input, Credentials::OtherResponse(name, params)))
@@ -402,7 +409,7 @@ fn ainfo_response_auth(input: &[u8]) -> Result<&[u8], AuthenticationInfo> {
         response_digest,
     )(input)?;
 
-    let auth = std::str::from_utf8(auth)
+    let auth = core::str::from_utf8(auth)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -418,7 +425,7 @@ fn ainfo_nextnonce(input: &[u8]) -> Result<&[u8], AuthenticationInfo> {
         quoted_string,
     )(input)?;
 
-    let nextnonce = std::str::from_utf8(nextnonce)
+    let nextnonce = core::str::from_utf8(nextnonce)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -459,7 +466,7 @@ pub fn authentication_info(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Authentication-Info"),
             header_colon
         ),
-        separated_nonempty_list(comma, ainfo)
+        separated_list1(comma, ainfo)
     )(input)?;
 
     Ok((input, Header::AuthenticationInfo(infos)))
@@ -474,7 +481,7 @@ fn qop_options(input: &[u8]) -> Result<&[u8], Vec<QOPValue>> {
         preceded(
             left_double_quote,
             terminated(
-                separated_nonempty_list(tag(","), qop_value),
+                separated_list1(tag(","), qop_value),
                 right_double_quote
             )
         )
@@ -483,25 +490,6 @@ fn qop_options(input: &[u8]) -> Result<&[u8], Vec<QOPValue>> {
     Ok((input, values))
 }
 
-fn boolean_true(input: &[u8]) -> Result<&[u8], bool> {
-    let (input, _) = tag_no_case("true")(input)?;
-
-    Ok((input, true))
-}
-
-fn boolean_false(input: &[u8]) -> Result<&[u8], bool> {
-    let (input, _) = tag_no_case("false")(input)?;
-
-    Ok((input, false))
-}
-
-fn boolean(input: &[u8]) -> Result<&[u8], bool> {
-    alt((
-        boolean_true,
-        boolean_false,
-    ))(input)
-}
-
 fn stale(input: &[u8]) -> Result<&[u8], bool> {
     let (input, value) = preceded(
         pair(
@@ -522,7 +510,7 @@ fn domain(input: &[u8]) -> Result<&[u8], Vec<String>> {
             left_double_quote,
         )),
         terminated(
-            separated_nonempty_list(
+            separated_list1(
                 take_while1(is_space), alt((absolute_uri, abs_path))
             ),
             right_double_quote
@@ -530,10 +518,10 @@ fn domain(input: &[u8]) -> Result<&[u8], Vec<String>> {
     )(input)?;
 
     let domains = domains.iter().map(|d|
-        std::str::from_utf8(d)
+        core::str::from_utf8(d)
             .map(|s| s.to_string())
             .map_err(|err| nom::Err::Failure(err.into()))
-    ).collect::<std::result::Result<Vec<String>, _>>()?;
+    ).collect::<core::result::Result<Vec<String>, _>>()?;
 
     Ok((input, domains))
 }
@@ -603,7 +591,7 @@ fn digest_cln(input: &[u8]) -> Result<&[u8], DigestParam> {
 fn challenge_other(input: &[u8]) -> Result<&[u8], Challenge> {
     let (input, (name, params)) = pair(
         terminated(token_str, linear_whitespace),
-        separated_nonempty_list(comma, auth_param)
+        separated_list1(comma, auth_param)
     )(input)?;
 
     Ok((input, Challenge::Other(name, params)))
@@ -615,7 +603,7 @@ fn challenge_digest(input: &[u8]) -> Result<&[u8], Challenge> {
             tag_no_case("Digest"),
             linear_whitespace
         ),
-        separated_nonempty_list(comma, digest_cln)
+        separated_list1(comma, digest_cln)
     )(input)?;
 
     Ok((input, Challenge::Digest(digest_clns)))
@@ -658,7 +646,7 @@ pub fn proxy_require(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Proxy-Require"),
             header_colon
         ),
-        separated_nonempty_list(comma, token_str)
+        separated_list1(comma, token_str)
     )(input)?;
 
     Ok((input, Header::ProxyRequire(requires)))
@@ -675,3 +663,40 @@ pub fn www_authenticate(input: &[u8]) -> Result<&[u8], Header> {
 
     Ok((input, Header::WWWAuthenticate(challenge)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_credentials() -> Credentials {
+        Credentials::DigestResponse(vec![
+            DigestResponseParam::Username("alice".to_string()),
+            DigestResponseParam::Realm("atlanta.example.com".to_string()),
+            DigestResponseParam::Response("ccdca50cb091d587421457305d097458".to_string()),
+        ])
+    }
+
+    #[test]
+    fn authorization_accepts_lws_runs_after_digest() {
+        let inputs: [&[u8]; 4] = [
+            b"Authorization: Digest\tusername=\"alice\", realm=\"atlanta.example.com\", response=\"ccdca50cb091d587421457305d097458\"",
+            b"Authorization: Digest \t username=\"alice\", realm=\"atlanta.example.com\", response=\"ccdca50cb091d587421457305d097458\"",
+            b"Authorization: Digest\r\n username=\"alice\", realm=\"atlanta.example.com\", response=\"ccdca50cb091d587421457305d097458\"",
+            b"Authorization: Digest \r\n\t\r\n username=\"alice\",realm=\"atlanta.example.com\" ,response=\"ccdca50cb091d587421457305d097458\"",
+        ];
+
+        for input in inputs.iter() {
+            let (rest, header) = authorization(input).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(header, Header::Authorization(digest_credentials()));
+        }
+    }
+
+    #[test]
+    fn authorization_accepts_trailing_comma() {
+        let (rest, header) = authorization(b"Authorization: Digest username=\"alice\", realm=\"atlanta.example.com\", response=\"ccdca50cb091d587421457305d097458\",\r\n").unwrap();
+
+        assert_eq!(rest, b"\r\n");
+        assert_eq!(header, Header::Authorization(digest_credentials()));
+    }
+}
@@ -1,11 +1,19 @@
+use crate::prelude::*;
 use crate::{
     header::{
         Header,
         InfoParamPurpose,
         InfoParam,
         Info,
+        AnswerMode,
+        AnswerModeParam,
+        AnswerModeValue,
+        GenericParam,
+        Join,
     },
     parser::{
+        Error,
+        ErrorKind,
         Result,
         rfc3261::{
             tokens::{
@@ -19,8 +27,9 @@ use crate::{
                 token_str,
             },
             common::{
-                absolute_uri,
                 generic_param,
+                generic_params,
+                uri,
             },
         },
     },
@@ -29,12 +38,12 @@ use crate::{
 use nom::{
     combinator::{ opt, recognize },
     sequence::{ pair, preceded, terminated, },
-    multi::{ separated_list, separated_nonempty_list, },
+    multi::{ many0, separated_list1, },
     branch::alt,
     bytes::complete::{ tag, tag_no_case },
 };
 
-fn callid(input: &[u8]) -> Result<&[u8], String> {
+pub fn callid(input: &[u8]) -> Result<&[u8], String> {
     let (input, callid) = recognize(
         pair(
             word,
@@ -42,7 +51,7 @@ fn callid(input: &[u8]) -> Result<&[u8], String> {
         )
     )(input)?;
 
-    let callid = std::str::from_utf8(callid)
+    let callid = core::str::from_utf8(callid)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -64,44 +73,25 @@ pub fn call_id(input: &[u8]) -> Result<&[u8], Header> {
     Ok((input, Header::CallID(id)))
 }
 
-fn info_param_purpose_icon(input: &[u8]) -> Result<&[u8], InfoParamPurpose> {
-    let (input, _) = tag_no_case("icon")(input)?;
-
-    Ok((input, InfoParamPurpose::Icon))
-}
-
-fn info_param_purpose_info(input: &[u8]) -> Result<&[u8], InfoParamPurpose> {
-    let (input, _) = tag_no_case("info")(input)?;
-
-    Ok((input, InfoParamPurpose::Info))
-}
-
-fn info_param_purpose_card(input: &[u8]) -> Result<&[u8], InfoParamPurpose> {
-    let (input, _) = tag_no_case("card")(input)?;
-
-    Ok((input, InfoParamPurpose::Card))
-}
-
-fn info_param_purpose_other(input: &[u8]) -> Result<&[u8], InfoParamPurpose> {
-    let (input, value) = token_str(input)?;
-
-    Ok((input, InfoParamPurpose::Other(value.to_string())))
-}
-
 fn info_param_purpose(input: &[u8]) -> Result<&[u8], InfoParam> {
     let (input, purpose) = preceded(
         pair(
             tag_no_case("purpose"),
             equal
         ),
-        alt((
-            info_param_purpose_icon,
-            info_param_purpose_info,
-            info_param_purpose_card,
-            info_param_purpose_other,
-        ))
+        token_str,
     )(input)?;
 
+    let purpose = match purpose.to_ascii_lowercase().as_str() {
+        "icon" => InfoParamPurpose::Icon,
+        "info" => InfoParamPurpose::Info,
+        "card" => InfoParamPurpose::Card,
+        "call-completion" => InfoParamPurpose::CallCompletion,
+        lower if lower.starts_with("emergencycalldata.") =>
+            InfoParamPurpose::EmergencyCallData(purpose["emergencycalldata.".len()..].to_string()),
+        _ => InfoParamPurpose::Other(purpose),
+    };
+
     Ok((input, InfoParam::Purpose(purpose)))
 }
 
@@ -120,14 +110,10 @@ fn info_param(input: &[u8]) -> Result<&[u8], InfoParam> {
 
 fn info(input: &[u8]) -> Result<&[u8], Info> {
     let (input, (uri, params)) = pair(
-        preceded(left_angle_quote, terminated(absolute_uri, right_angle_quote)),
-        separated_list(semicolon, info_param)
+        preceded(left_angle_quote, terminated(uri, right_angle_quote)),
+        many0(preceded(semicolon, info_param))
     )(input)?;
 
-    let uri = std::str::from_utf8(uri)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
     Ok((input, Info {
         uri,
         params,
@@ -140,7 +126,7 @@ pub fn call_info(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Call-Info"),
             header_colon
         ),
-        separated_nonempty_list(comma, info),
+        separated_list1(comma, info),
     )(input)?;
 
     Ok((input, Header::CallInfo(infos)))
@@ -152,8 +138,223 @@ pub fn in_reply_to(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("In-Reply-To"),
             header_colon,
         ),
-        separated_nonempty_list(comma, callid)
+        separated_list1(comma, callid)
     )(input)?;
 
     Ok((input, Header::InReplyTo(callids)))
 }
+
+/// The two tags of a dialog, in the requested order, followed by the remaining params
+type DialogTags = (String, String, Vec<GenericParam>);
+
+/// Takes the two tags identifying a dialog (e.g. the `to-tag` and `from-tag` params) out of
+/// `params`, returning them along with the other params
+///
+/// Both tags are mandatory: a `MissingDialogTag` failure pointing at `input` is returned if either
+/// is missing.
+pub(super) fn dialog_tags<'a>(
+    input: &'a [u8],
+    params: Vec<GenericParam>,
+    (first, second): (&'static str, &'static str),
+) -> core::result::Result<DialogTags, nom::Err<Error<'a, &'a [u8]>>> {
+    let mut first_tag = None;
+    let mut second_tag = None;
+    let mut other = vec![];
+    for param in params {
+        match param.value {
+            Some(value) if param.name.eq_ignore_ascii_case(first) => first_tag = Some(value),
+            Some(value) if param.name.eq_ignore_ascii_case(second) => second_tag = Some(value),
+            _ => other.push(param),
+        }
+    }
+
+    let missing = |tag| nom::Err::Failure(Error::new(ErrorKind::MissingDialogTag(input, tag)));
+    let first_tag = first_tag.ok_or_else(|| missing(first))?;
+    let second_tag = second_tag.ok_or_else(|| missing(second))?;
+
+    Ok((first_tag, second_tag, other))
+}
+
+pub fn join(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (rest, (call_id, params)) = preceded(
+        pair(
+            tag_no_case("Join"),
+            header_colon,
+        ),
+        pair(callid, generic_params)
+    )(input)?;
+
+    // both tags are mandatory (RFC3911 section 7.1)
+    let (to_tag, from_tag, params) = dialog_tags(input, params, ("to-tag", "from-tag"))?;
+
+    Ok((rest, Header::Join(Join {
+        call_id,
+        to_tag,
+        from_tag,
+        params,
+    })))
+}
+
+fn answer_mode_value(input: &[u8]) -> Result<'_, &[u8], AnswerModeValue> {
+    let (input, mode) = token_str(input)?;
+
+    let mode = if mode.eq_ignore_ascii_case("Manual") {
+        AnswerModeValue::Manual
+    } else if mode.eq_ignore_ascii_case("Auto") {
+        AnswerModeValue::Auto
+    } else {
+        AnswerModeValue::Extension(mode)
+    };
+
+    Ok((input, mode))
+}
+
+fn answer_mode_param(input: &[u8]) -> Result<'_, &[u8], AnswerModeParam> {
+    let (input, param) = generic_param(input)?;
+
+    if param.name.eq_ignore_ascii_case("require") && param.value.is_none() {
+        Ok((input, AnswerModeParam::Require))
+    } else {
+        Ok((input, AnswerModeParam::Extension(param)))
+    }
+}
+
+fn answer_mode_spec(input: &[u8]) -> Result<'_, &[u8], AnswerMode> {
+    let (input, (mode, params)) = pair(
+        answer_mode_value,
+        many0(preceded(semicolon, answer_mode_param))
+    )(input)?;
+
+    Ok((input, AnswerMode {
+        mode,
+        params,
+    }))
+}
+
+pub fn answer_mode(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, mode) = preceded(
+        pair(
+            tag_no_case("Answer-Mode"),
+            header_colon,
+        ),
+        answer_mode_spec
+    )(input)?;
+
+    Ok((input, Header::AnswerMode(mode)))
+}
+
+pub fn priv_answer_mode(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, mode) = preceded(
+        pair(
+            tag_no_case("Priv-Answer-Mode"),
+            header_colon,
+        ),
+        answer_mode_spec
+    )(input)?;
+
+    Ok((input, Header::PrivAnswerMode(mode)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sip::Uri;
+
+    #[test]
+    fn call_info_exposes_purpose() {
+        let (input, header) = call_info(b"Call-Info: <http://x/photo.jpg>;purpose=icon").unwrap();
+        assert_eq!(input, b"");
+        match header {
+            Header::CallInfo(infos) => {
+                assert_eq!(infos[0].uri, Uri::Absolute("http://x/photo.jpg".to_string()));
+                assert_eq!(infos[0].purpose(), Some(&InfoParamPurpose::Icon));
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn call_info_recognizes_newer_purposes() {
+        let (_, header) = call_info(b"Call-Info: <https://ecrf.example.com/1>;purpose=EmergencyCallData.ProviderInfo, <sip:cc@example.com>;purpose=call-completion;m=BS").unwrap();
+        match header {
+            Header::CallInfo(infos) => {
+                assert_eq!(infos[0].purpose(), Some(&InfoParamPurpose::EmergencyCallData("ProviderInfo".to_string())));
+                assert_eq!(infos[1].purpose(), Some(&InfoParamPurpose::CallCompletion));
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn call_info_purpose_is_case_insensitive() {
+        let (_, header) = call_info(b"Call-Info: <sip:card@example.com>;PURPOSE=Icon").unwrap();
+        match header {
+            Header::CallInfo(infos) => {
+                match &infos[0].uri {
+                    Uri::Sip(uri) => assert_eq!(uri.user.as_deref(), Some("card")),
+                    uri => panic!("unexpected uri {:?}", uri),
+                }
+                assert_eq!(infos[0].purpose(), Some(&InfoParamPurpose::Icon));
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn call_info_purpose_is_none_when_absent() {
+        let info = Info {
+            uri: Uri::Absolute("http://x/photo.jpg".to_string()),
+            params: vec![],
+        };
+        assert_eq!(info.purpose(), None);
+    }
+
+    #[test]
+    fn join_extracts_tags_and_params() {
+        let (rest, header) = join(b"Join: 12adf2f34456gs5;to-tag=12345;from-tag=54321;early-only").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::Join(Join {
+            call_id: "12adf2f34456gs5".to_string(),
+            to_tag: "12345".to_string(),
+            from_tag: "54321".to_string(),
+            params: vec![crate::header::GenericParam {
+                name: "early-only".to_string(),
+                value: None,
+            }],
+        }));
+    }
+
+    #[test]
+    fn join_requires_both_tags() {
+        let missing = |input: &[u8]| match join(input) {
+            Err(nom::Err::Failure(Error { kind: ErrorKind::MissingDialogTag(_, tag), .. })) => Some(tag),
+            _ => None,
+        };
+
+        assert_eq!(missing(b"Join: 12adf2f34456gs5;to-tag=12345"), Some("from-tag"));
+        assert_eq!(missing(b"Join: 12adf2f34456gs5;from-tag=54321"), Some("to-tag"));
+        assert_eq!(missing(b"Join: 12adf2f34456gs5;to-tag;from-tag=54321"), Some("to-tag"));
+    }
+
+    #[test]
+    fn message_header_rejects_a_join_without_tags() {
+        assert!(crate::parser::rfc3261::headers::message_header(b"Join: 12adf2f34456gs5;to-tag=12345\r\n").is_err());
+    }
+
+    #[test]
+    fn answer_mode_can_parse_auto() {
+        assert_eq!(answer_mode(b"Answer-Mode: Auto").unwrap().1, Header::AnswerMode(AnswerMode {
+            mode: AnswerModeValue::Auto,
+            params: vec![],
+        }));
+    }
+
+    #[test]
+    fn priv_answer_mode_can_parse_require() {
+        assert_eq!(priv_answer_mode(b"Priv-Answer-Mode: Manual;require").unwrap().1, Header::PrivAnswerMode(AnswerMode {
+            mode: AnswerModeValue::Manual,
+            params: vec![AnswerModeParam::Require],
+        }));
+    }
+}
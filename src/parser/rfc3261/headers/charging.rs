@@ -0,0 +1,80 @@
+use crate::prelude::*;
+use crate::{
+    header::{ Header, GenericParam, PChargingFunctionAddresses, },
+    parser::{
+        Result,
+        rfc3261::{
+            tokens::{
+                header_colon,
+                semicolon,
+            },
+            common::generic_param,
+        },
+    },
+};
+
+use nom::{
+    sequence::{ pair, preceded, },
+    multi::separated_list1,
+    bytes::complete::tag_no_case,
+};
+
+pub fn p_charging_function_addresses(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, params) = preceded(
+        pair(
+            tag_no_case("P-Charging-Function-Addresses"),
+            header_colon,
+        ),
+        separated_list1(semicolon, generic_param)
+    )(input)?;
+
+    let mut addresses = PChargingFunctionAddresses {
+        ccf: vec![],
+        ecf: vec![],
+        params: vec![],
+    };
+
+    for param in params {
+        match (param.name.to_ascii_lowercase().as_str(), param.value) {
+            ("ccf", Some(ccf)) => addresses.ccf.push(ccf),
+            ("ecf", Some(ecf)) => addresses.ecf.push(ecf),
+            (_, value) => addresses.params.push(GenericParam {
+                name: param.name,
+                value,
+            }),
+        }
+    }
+
+    Ok((input, Header::PChargingFunctionAddresses(addresses)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p_charging_function_addresses_can_parse_repeated_addresses() {
+        let (rest, header) = p_charging_function_addresses(b"P-Charging-Function-Addresses: ccf=192.1.1.1; ccf=192.1.1.2; ecf=192.1.1.3; ecf=192.1.1.4").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::PChargingFunctionAddresses(PChargingFunctionAddresses {
+            ccf: vec!["192.1.1.1".to_string(), "192.1.1.2".to_string()],
+            ecf: vec!["192.1.1.3".to_string(), "192.1.1.4".to_string()],
+            params: vec![],
+        }));
+    }
+
+    #[test]
+    fn p_charging_function_addresses_keeps_other_params() {
+        let (_, header) = p_charging_function_addresses(b"P-Charging-Function-Addresses: ccf=[5555::b99:c88:d77:e66]; foo=bar").unwrap();
+
+        match header {
+            Header::PChargingFunctionAddresses(addresses) => {
+                assert_eq!(addresses.ccf, vec!["[5555::b99:c88:d77:e66]".to_string()]);
+                assert!(addresses.ecf.is_empty());
+                assert_eq!(addresses.params[0].name, "foo");
+            },
+            _ => panic!(),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{
         Header,
@@ -11,9 +12,15 @@ use crate::{
         Contact,
         ContactValue,
         ContactParam,
+        PAssertedIdentity,
+        PIdentity,
+        ContactPref,
+        GenericParam,
     },
+    sip::Uri,
     parser::{
         integer,
+        Error,
         Result,
         rfc3261::{
             tokens::{
@@ -28,12 +35,15 @@ use crate::{
                 right_angle_quote,
                 semicolon,
                 star,
+                is_param_char,
             },
             common::{
                 absolute_uri,
+                bare_sip_uri,
                 generic_param,
                 generic_params,
                 sip_uri,
+                sips_uri,
                 qvalue,
             },
         },
@@ -41,11 +51,11 @@ use crate::{
 };
 
 use nom::{
-    combinator::{ opt, recognize },
+    combinator::{ map, opt, recognize },
     sequence::{ pair, preceded, terminated },
-    multi::{ many0, many1, separated_nonempty_list, },
+    multi::{ many0, many1, separated_list1, },
     branch::alt,
-    bytes::complete::tag_no_case,
+    bytes::complete::{ tag_no_case, take_while1, },
 };
 
 fn contact_params_expires(input: &[u8]) -> Result<&[u8], ContactParam> {
@@ -54,7 +64,7 @@ fn contact_params_expires(input: &[u8]) -> Result<&[u8], ContactParam> {
             tag_no_case("expires"),
             equal
         ),
-        integer,
+        integer::<u32>,
     )(input)?;
 
     Ok((input, ContactParam::Expires(expires)))
@@ -69,11 +79,53 @@ fn contact_params_q(input: &[u8]) -> Result<&[u8], ContactParam> {
         qvalue,
     )(input)?;
 
-    let q = std::str::from_utf8(q)
+    Ok((input, ContactParam::Q(q)))
+}
+
+fn push_param_value(input: &[u8]) -> Result<'_, &[u8], String> {
+    let (input, value) = take_while1(is_param_char)(input)?;
+
+    let value = core::str::from_utf8(value)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
-    Ok((input, ContactParam::Q(q)))
+    Ok((input, value))
+}
+
+fn contact_params_push_provider(input: &[u8]) -> Result<'_, &[u8], ContactParam> {
+    let (input, provider) = preceded(
+        pair(
+            tag_no_case("pn-provider"),
+            equal,
+        ),
+        push_param_value,
+    )(input)?;
+
+    Ok((input, ContactParam::PushProvider(provider)))
+}
+
+fn contact_params_push_prid(input: &[u8]) -> Result<'_, &[u8], ContactParam> {
+    let (input, prid) = preceded(
+        pair(
+            tag_no_case("pn-prid"),
+            equal,
+        ),
+        push_param_value,
+    )(input)?;
+
+    Ok((input, ContactParam::PushPrid(prid)))
+}
+
+fn contact_params_push_param(input: &[u8]) -> Result<'_, &[u8], ContactParam> {
+    let (input, param) = preceded(
+        pair(
+            tag_no_case("pn-param"),
+            equal,
+        ),
+        push_param_value,
+    )(input)?;
+
+    Ok((input, ContactParam::PushParam(param)))
 }
 
 fn contact_params_extension(input: &[u8]) -> Result<&[u8], ContactParam> {
@@ -86,30 +138,80 @@ fn contact_params(input: &[u8]) -> Result<&[u8], ContactParam> {
     alt((
         contact_params_q,
         contact_params_expires,
+        contact_params_push_provider,
+        contact_params_push_prid,
+        contact_params_push_param,
         contact_params_extension,
     ))(input)
 }
 
-fn display_name(input: &[u8]) -> Result<&[u8], &[u8]> {
-    alt((
-        recognize(many1(pair(token, linear_whitespace))),
+/// Removes the whitespace separating an unquoted display name from the address
+fn trim_end(name: &[u8]) -> &[u8] {
+    let end = name.iter()
+        .rposition(|c| !c.is_ascii_whitespace())
+        .map_or(0, |last| last + 1);
+
+    &name[..end]
+}
+
+/// Decodes a display name: the backslashes of quoted pairs are removed, and the result must be
+/// valid UTF-8
+fn decode_display_name(name: &[u8]) -> core::result::Result<String, nom::Err<Error<'_, &[u8]>>> {
+    let mut decoded = Vec::with_capacity(name.len());
+    let mut bytes = name.iter();
+    while let Some(&c) = bytes.next() {
+        match c {
+            b'\\' => decoded.extend(bytes.next()),
+            c => decoded.push(c),
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|err| nom::Err::Failure(err.utf8_error().into()))
+}
+
+fn display_name(input: &[u8]) -> Result<&[u8], String> {
+    let (input, name) = alt((
+        map(recognize(many1(pair(token, linear_whitespace))), trim_end),
         quoted_string
-    ))(input)
+    ))(input)?;
+
+    Ok((input, decode_display_name(name)?))
+}
+
+fn absolute_addr(input: &[u8]) -> Result<'_, &[u8], Uri> {
+    let (input, uri) = absolute_uri(input)?;
+
+    let uri = core::str::from_utf8(uri)
+        .map(|s| s.to_string())
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    Ok((input, Uri::Absolute(uri)))
 }
 
-fn addr_spec(input: &[u8]) -> Result<&[u8], (Option<&[u8]>, &[u8])> {
+fn addr_spec(input: &[u8]) -> Result<&[u8], (Option<String>, Uri)> {
     let (input, addr) = alt((
-        sip_uri,
-        absolute_uri,
+        map(bare_sip_uri, Uri::Sip),
+        absolute_addr,
     ))(input)?;
 
     Ok((input, (None, addr)))
 }
 
-fn name_addr(input: &[u8]) -> Result<&[u8], (Option<&[u8]>, &[u8])> {
-    let (input, (dn, (_, addr))) = pair(
+fn name_addr(input: &[u8]) -> Result<&[u8], (Option<String>, Uri)> {
+    let (input, (dn, addr)) = pair(
         opt(display_name),
-        preceded(left_angle_quote, terminated(addr_spec, right_angle_quote))
+        preceded(
+            left_angle_quote,
+            terminated(
+                alt((
+                    map(sip_uri, Uri::Sip),
+                    map(sips_uri, Uri::Sip),
+                    absolute_addr,
+                )),
+                right_angle_quote
+            )
+        )
     )(input)?;
 
     Ok((input, (dn, addr)))
@@ -121,17 +223,6 @@ fn contact_param(input: &[u8]) -> Result<&[u8], Contact> {
         many0(preceded(semicolon, contact_params))
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
-
     Ok((input, Contact {
         name,
         addr,
@@ -146,7 +237,7 @@ fn contact_star(input: &[u8]) -> Result<&[u8], ContactValue> {
 }
 
 fn contact_specific(input: &[u8]) -> Result<&[u8], ContactValue> {
-    let (input, params) = separated_nonempty_list(comma, contact_param)(input)?;
+    let (input, params) = separated_list1(comma, contact_param)(input)?;
 
     Ok((input, ContactValue::Specific(params)))
 }
@@ -199,17 +290,6 @@ fn from_spec(input: &[u8]) -> Result<&[u8], From> {
         many0(preceded(semicolon, alt((from_param_tag, from_param_extension))))
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
-
     Ok((input, From {
         name,
         addr,
@@ -229,21 +309,69 @@ pub fn from(input: &[u8]) -> Result<&[u8], Header> {
     Ok((input, Header::From(from)))
 }
 
-fn rec_route(input: &[u8]) -> Result<&[u8], RecordRoute> {
+fn p_asserted_id_value(input: &[u8]) -> Result<'_, &[u8], PAssertedIdentity> {
+    let (input, (name, addr)) = alt((name_addr, addr_spec))(input)?;
+
+    Ok((input, PAssertedIdentity {
+        addr,
+        name,
+    }))
+}
+
+pub fn p_asserted_identity(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, identities) = preceded(
+        pair(
+            tag_no_case("P-Asserted-Identity"),
+            header_colon,
+        ),
+        separated_list1(comma, p_asserted_id_value)
+    )(input)?;
+
+    Ok((input, Header::PAssertedIdentity(identities)))
+}
+
+fn p_identity(input: &[u8]) -> Result<'_, &[u8], PIdentity> {
     let (input, ((name, addr), params)) = pair(
         name_addr,
         generic_params,
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
+    Ok((input, PIdentity {
+        addr,
+        name,
+        params,
+    }))
+}
+
+pub fn p_associated_uri(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, identities) = preceded(
+        pair(
+            tag_no_case("P-Associated-URI"),
+            header_colon,
+        ),
+        separated_list1(comma, p_identity)
+    )(input)?;
+
+    Ok((input, Header::PAssociatedURI(identities)))
+}
+
+pub fn p_called_party_id(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, identity) = preceded(
+        pair(
+            tag_no_case("P-Called-Party-ID"),
+            header_colon,
+        ),
+        p_identity
+    )(input)?;
+
+    Ok((input, Header::PCalledPartyID(identity)))
+}
+
+fn rec_route(input: &[u8]) -> Result<&[u8], RecordRoute> {
+    let (input, ((name, addr), params)) = pair(
+        name_addr,
+        generic_params,
+    )(input)?;
 
     Ok((input, RecordRoute {
         addr,
@@ -258,7 +386,7 @@ pub fn record_route(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Record-Route"),
             header_colon,
         ),
-        separated_nonempty_list(comma, rec_route)
+        separated_list1(comma, rec_route)
     )(input)?;
 
     Ok((input, Header::RecordRoute(routes)))
@@ -270,16 +398,6 @@ fn rplyto_spec(input: &[u8]) -> Result<&[u8], ReplyTo> {
         generic_params,
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
-
     Ok((input, ReplyTo {
         addr,
         name,
@@ -305,16 +423,6 @@ fn route_param(input: &[u8]) -> Result<&[u8], Route> {
         generic_params,
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
-
     Ok((input, Route {
         addr,
         name,
@@ -328,7 +436,7 @@ pub fn route(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Route"),
             header_colon,
         ),
-        separated_nonempty_list(comma, route_param)
+        separated_list1(comma, route_param)
     )(input)?;
 
     Ok((input, Header::Route(params)))
@@ -365,17 +473,6 @@ pub fn to(input: &[u8]) -> Result<&[u8], Header> {
         )
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
-    let name = match name {
-        Some(n) => Some(std::str::from_utf8(n)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
-    };
-
     Ok((input, Header::To(To {
         addr,
         name,
@@ -383,19 +480,79 @@ pub fn to(input: &[u8]) -> Result<&[u8], Header> {
     })))
 }
 
+fn contact_pref(input: &[u8]) -> Result<'_, &[u8], ContactPref> {
+    let (input, params) = preceded(star, generic_params)(input)?;
+
+    let is_flag = |param: &GenericParam, flag: &str| param.value.is_none()
+        && param.name.eq_ignore_ascii_case(flag);
+
+    Ok((input, ContactPref {
+        require: params.iter().any(|param| is_flag(param, "require")),
+        explicit: params.iter().any(|param| is_flag(param, "explicit")),
+        params: params.into_iter()
+            .filter(|param| !is_flag(param, "require") && !is_flag(param, "explicit"))
+            .collect(),
+    }))
+}
+
+pub fn accept_contact(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, prefs) = preceded(
+        pair(
+            alt((
+                tag_no_case("Accept-Contact"),
+                tag_no_case("a"),
+            )),
+            header_colon,
+        ),
+        separated_list1(comma, contact_pref)
+    )(input)?;
+
+    Ok((input, Header::AcceptContact(prefs)))
+}
+
+pub fn reject_contact(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, prefs) = preceded(
+        pair(
+            alt((
+                tag_no_case("Reject-Contact"),
+                tag_no_case("j"),
+            )),
+            header_colon,
+        ),
+        separated_list1(comma, contact_pref)
+    )(input)?;
+
+    Ok((input, Header::RejectContact(prefs)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::header::*;
+    use crate::sip::SipUri;
+
+    fn sip(uri: &str) -> Uri {
+        Uri::Sip(SipUri::parse(uri.as_bytes()).unwrap())
+    }
 
     #[test]
     fn contact_params_expires_extracts_value() {
         assert_eq!(contact_params_expires(b"expires=18").unwrap().1, ContactParam::Expires(18));
     }
 
+    #[test]
+    fn contact_params_expires_reads_unsigned_32_bits() {
+        assert_eq!(contact_params_expires(b"expires=4294967295").unwrap().1, ContactParam::Expires(u32::MAX));
+
+        match contact_params_expires(b"expires=4294967296") {
+            Err(nom::Err::Failure(err)) => assert_eq!(err.kind, crate::parser::ErrorKind::InvalidIntegerError),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn contact_params_q_extracts_value() {
-        assert_eq!(contact_params_q(b"q=1.0").unwrap().1, ContactParam::Q("1.0".to_string()));
+        assert_eq!(contact_params_q(b"q=1.0").unwrap().1, ContactParam::Q(1.0));
     }
 
     #[test]
@@ -416,33 +573,163 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn contact_can_parse_push_notification_params() {
+        let (input, header) = contact(b"Contact: <sip:alice@192.0.2.1:5060>;pn-provider=apns;pn-prid=ZTY4ZDJlMzODE1NmUgKi0K:remote;pn-param=DEF123GHIJ.com.example.yourapp.voip;expires=3600").unwrap();
+        assert_eq!(input, b"");
+        match header {
+            Header::Contact(ContactValue::Specific(contacts)) => assert_eq!(contacts[0].params, vec![
+                ContactParam::PushProvider("apns".to_string()),
+                ContactParam::PushPrid("ZTY4ZDJlMzODE1NmUgKi0K:remote".to_string()),
+                ContactParam::PushParam("DEF123GHIJ.com.example.yourapp.voip".to_string()),
+                ContactParam::Expires(3600),
+            ]),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn p_associated_uri_can_parse_a_list() {
+        let (input, header) = p_associated_uri(b"P-Associated-URI: <sip:user1-business@example.com>, \"User 1\" <tel:+1-212-555-1234>").unwrap();
+        assert_eq!(input, b"");
+        match header {
+            Header::PAssociatedURI(identities) => {
+                assert_eq!(identities.len(), 2);
+                assert_eq!(identities[0].name, None);
+                assert_eq!(identities[1].name, Some("User 1".to_string()));
+                assert_eq!(identities[1].addr, Uri::Absolute("tel:+1-212-555-1234".to_string()));
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn p_called_party_id_keeps_params() {
+        let (input, header) = p_called_party_id(b"P-Called-Party-ID: <sip:user1-business@example.com>;cpid=1").unwrap();
+        assert_eq!(input, b"");
+        match header {
+            Header::PCalledPartyID(identity) => assert_eq!(identity.params, vec![GenericParam {
+                name: "cpid".to_string(),
+                value: Some("1".to_string()),
+            }]),
+            _ => panic!(),
+        }
+        assert!(p_called_party_id(b"P-Called-Party-ID: sip:user1-business@example.com").is_err());
+    }
+
+    #[test]
+    fn contact_can_parse_gruu() {
+        let (input, header) = contact(b"Contact: <sip:callee@192.0.2.1;gr=urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>;expires=3600").unwrap();
+        assert_eq!(input, b"");
+        match header {
+            Header::Contact(ContactValue::Specific(contacts)) => {
+                assert!(contacts[0].is_gruu());
+                assert_eq!(contacts[0].gruu(), Some("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6"));
+            },
+            _ => panic!(),
+        }
+
+        let (_, header) = contact(b"Contact: <sip:callee@192.0.2.1;gr>").unwrap();
+        match header {
+            Header::Contact(ContactValue::Specific(contacts)) => {
+                assert!(contacts[0].is_gruu());
+                assert_eq!(contacts[0].gruu(), None);
+            },
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn name_addr_extracts_addr() {
-        assert!(name_addr(b"<sip:example.com>").unwrap().1 == (None, b"sip:example.com"));
-        assert!(name_addr(b"<sip:example.com:5060>").unwrap().1 == (None, b"sip:example.com:5060"));
-        assert!(name_addr(b"<sips:john@example.com>").unwrap().1 == (None, b"sips:john@example.com"));
+        assert!(name_addr(b"<sip:example.com>").unwrap().1 == (None, sip("sip:example.com")));
+        assert!(name_addr(b"<sip:example.com:5060>").unwrap().1 == (None, sip("sip:example.com:5060")));
+        assert!(name_addr(b"<sips:john@example.com>").unwrap().1 == (None, sip("sips:john@example.com")));
     }
 
     #[test]
     fn display_name_can_handle_quoted_and_unquoted_strings() {
-        assert!(display_name(b"John ").unwrap().1 == b"John ");
-        assert!(display_name(b"\"John\"").unwrap().1 == b"John");
+        assert!(display_name(b"John Doe ").unwrap().1 == "John Doe");
+        assert!(display_name(b"\"John\"").unwrap().1 == "John");
+        assert!(display_name(b"\"Doe, \\\"JD\\\" \\\\ John\"").unwrap().1 == "Doe, \"JD\" \\ John");
+        assert!(display_name(b"\"Bj\xc3\xb6rk\"").unwrap().1 == "Bj\u{f6}rk");
+        assert!(display_name(b"\"Bj\xf6rk\"").is_err());
+    }
+
+    #[test]
+    fn from_holds_the_decoded_display_name() {
+        let (_, header) = from(b"From: \"Doe, \\\"JD\\\" John\" <sip:jd@atlanta.example.com>;tag=9fxced76sl").unwrap();
+
+        match header {
+            Header::From(from) => assert_eq!(from.name, Some("Doe, \"JD\" John".to_string())),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn decoded_display_name_is_encoded_back_to_the_same_quoted_string() {
+        let bytes = b"From: \"Doe, \\\"JD\\\" \\\\ John\" <sip:jd@atlanta.example.com>;tag=9fxced76sl";
+        let (_, header) = from(bytes).unwrap();
+
+        match &header {
+            Header::From(from) => assert_eq!(
+                crate::encode::display_name(from.name.as_ref().unwrap()).unwrap().as_bytes(),
+                &b"\"Doe, \\\"JD\\\" \\\\ John\""[..]
+            ),
+            _ => panic!(),
+        }
+        assert_eq!(header.to_string().as_bytes(), &bytes[..]);
+    }
+
+    #[test]
+    fn quoted_display_name_keeps_its_trailing_spaces_when_encoded() {
+        let bytes = b"From: \"Alice  \" <sip:alice@atlanta.example.com>;tag=9fxced76sl";
+        let (_, header) = from(bytes).unwrap();
+
+        assert_eq!(header.to_string().as_bytes(), &bytes[..]);
     }
 
     #[test]
     fn name_addr_extracts_addr_and_name() {
-        assert!(name_addr(b"John <sip:example.com>").unwrap().1 == (Some(b"John "), b"sip:example.com"));
-        assert!(name_addr(b"\"John Doe\" <sip:example.com>").unwrap().1 == (Some(b"John Doe"), b"sip:example.com"));
+        assert!(name_addr(b"John <sip:example.com>").unwrap().1 == (Some("John".to_string()), sip("sip:example.com")));
+        assert!(name_addr(b"\"John Doe\" <sip:example.com>").unwrap().1 == (Some("John Doe".to_string()), sip("sip:example.com")));
+    }
+
+    #[test]
+    fn name_addr_keeps_uri_params_and_headers() {
+        let (_, (_, addr)) = name_addr(b"<sip:j@x.com;transport=tcp?subject=x>;tag=1").unwrap();
+        match addr {
+            Uri::Sip(uri) => {
+                assert!(uri.params == vec![URIParam::Transport(crate::sip::Transport::TCP)]);
+                assert!(uri.headers.len() == 1);
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn name_addr_keeps_absolute_uris() {
+        let (_, (_, addr)) = name_addr(b"<mailto:carol@chicago.com>").unwrap();
+        assert!(addr == Uri::Absolute("mailto:carol@chicago.com".to_string()));
+    }
+
+    #[test]
+    fn from_without_angle_brackets_leaves_params_to_the_header() {
+        let (_, header) = from(b"From: sip:j@x.com;tag=1").unwrap();
+        assert_eq!(header, Header::From(From {
+            addr: sip("sip:j@x.com"),
+            name: None,
+            params: vec![FromParam::Tag("1".to_string())],
+        }));
     }
 
     #[test]
     fn contact_param_can_parse_full_contact() {
         assert!(contact_param(b"\"John\" <sip:j@example.com>;expires=8;q=1.0").unwrap().1 == Contact {
-            addr: "sip:j@example.com".to_string(),
+            addr: sip("sip:j@example.com"),
             name: Some("John".to_string()),
             params: vec![
                 ContactParam::Expires(8),
-                ContactParam::Q("1.0".to_string())
+                ContactParam::Q(1.0)
             ]
         })
     }
@@ -451,4 +738,79 @@ mod tests {
     fn to_can_parse_whole_to_line() {
         assert!(to(b"To: Bob <sip:bob@biloxi.example.com>").is_ok());
     }
+
+    #[test]
+    fn from_keeps_comma_inside_quoted_display_name() {
+        let (input, header) = from(b"From: \"Doe, John\" <sip:j@x.com>;tag=1").unwrap();
+        assert_eq!(input, b"");
+        assert_eq!(header, Header::From(From {
+            addr: sip("sip:j@x.com"),
+            name: Some("Doe, John".to_string()),
+            params: vec![FromParam::Tag("1".to_string())],
+        }));
+    }
+
+    #[test]
+    fn contact_does_not_split_on_comma_inside_quoted_display_name() {
+        let (input, header) = contact(b"Contact: \"Doe, John\" <sip:j@x.com>;q=0.5, <sip:k@y.com>").unwrap();
+        assert_eq!(input, b"");
+        assert_eq!(header, Header::Contact(ContactValue::Specific(vec![
+            Contact {
+                addr: sip("sip:j@x.com"),
+                name: Some("Doe, John".to_string()),
+                params: vec![ContactParam::Q(0.5)],
+            },
+            Contact {
+                addr: sip("sip:k@y.com"),
+                name: None,
+                params: vec![],
+            },
+        ])));
+    }
+
+    #[test]
+    fn contact_handles_several_quoted_display_names_with_commas() {
+        let (_, header) = contact(b"Contact: \"A, B\" <sip:a@x.com>, \"C, D\" <sip:c@x.com>").unwrap();
+        match header {
+            Header::Contact(ContactValue::Specific(contacts)) => {
+                assert_eq!(contacts.len(), 2);
+                assert_eq!(contacts[0].name, Some("A, B".to_string()));
+                assert_eq!(contacts[1].name, Some("C, D".to_string()));
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn accept_contact_can_parse_compact_form() {
+        let (rest, header) = accept_contact(b"a: *;audio;require;explicit, *;video").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::AcceptContact(vec![
+            ContactPref {
+                require: true,
+                explicit: true,
+                params: vec![GenericParam { name: "audio".to_string(), value: None }],
+            },
+            ContactPref {
+                require: false,
+                explicit: false,
+                params: vec![GenericParam { name: "video".to_string(), value: None }],
+            },
+        ]));
+    }
+
+    #[test]
+    fn reject_contact_can_parse_feature_tags() {
+        let (_, header) = reject_contact(b"j: *;+sip.automata;methods=\"INVITE\"").unwrap();
+
+        match header {
+            Header::RejectContact(prefs) => {
+                assert!(!prefs[0].require);
+                assert_eq!(prefs[0].params[0].name, "+sip.automata");
+                assert_eq!(prefs[0].params[1].value, Some("INVITE".to_string()));
+            },
+            _ => panic!(),
+        }
+    }
 }
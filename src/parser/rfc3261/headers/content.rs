@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{
         Header,
@@ -38,10 +39,10 @@ use crate::{
 };
 
 use nom::{
-    combinator::recognize,
+    combinator::{ not, recognize },
     sequence::{ pair, tuple, preceded, },
     branch::alt,
-    multi::{ many0, separated_nonempty_list, separated_list },
+    multi::{ many0, separated_list1, separated_list0 },
     character::is_alphabetic,
     bytes::complete::{ tag, tag_no_case, take_while_m_n, },
 };
@@ -108,7 +109,7 @@ fn m_type_x_extension(input: &[u8]) -> Result<&[u8], MediaType> {
         )
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, MediaType::XExtension(value.to_string())))
@@ -156,7 +157,7 @@ fn m_subtype_x_extension(input: &[u8]) -> Result<&[u8], MediaSubType> {
         )
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, MediaSubType::XExtension(value.to_string())))
@@ -177,7 +178,7 @@ fn m_parameter(input: &[u8]) -> Result<&[u8], MediaParam> {
         preceded(equal, alt((token, quoted_string)))
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, MediaParam {
@@ -186,22 +187,6 @@ fn m_parameter(input: &[u8]) -> Result<&[u8], MediaParam> {
     }))
 }
 
-fn media_range(input: &[u8]) -> Result<&[u8], Media> {
-    let (input, ((r#type, subtype), params)) = pair(
-        pair(
-            m_type,
-            preceded(slash, m_subtype)
-        ),
-        many0(preceded(semicolon, m_parameter))
-    )(input)?;
-
-    Ok((input, Media {
-        r#type,
-        subtype,
-        params,
-    }))
-}
-
 fn accept_param_q(input: &[u8]) -> Result<&[u8], AcceptParam> {
     let (input, q) = preceded(
         pair(
@@ -211,10 +196,7 @@ fn accept_param_q(input: &[u8]) -> Result<&[u8], AcceptParam> {
         qvalue
     )(input)?;
 
-    let q = std::str::from_utf8(q)
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
-    Ok((input, AcceptParam::Q(q.to_string())))
+    Ok((input, AcceptParam::Q(q)))
 }
 
 fn accept_param_extension(input: &[u8]) -> Result<&[u8], AcceptParam> {
@@ -230,6 +212,26 @@ fn accept_param(input: &[u8]) -> Result<&[u8], AcceptParam> {
     ))(input)
 }
 
+/// The q parameter separates the media-range parameters from the accept-params
+fn media_range(input: &[u8]) -> Result<&[u8], Media> {
+    let (input, ((r#type, subtype), params)) = pair(
+        pair(
+            m_type,
+            preceded(slash, m_subtype)
+        ),
+        many0(preceded(
+            semicolon,
+            preceded(not(pair(tag_no_case("q"), equal)), m_parameter),
+        ))
+    )(input)?;
+
+    Ok((input, Media {
+        r#type,
+        subtype,
+        params,
+    }))
+}
+
 fn accept_range(input: &[u8]) -> Result<&[u8], Accept> {
     let (input, (media, params)) = pair(
         media_range,
@@ -248,7 +250,7 @@ pub fn accept(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Accept"),
             header_colon,
         ),
-        separated_list(comma, accept_range),
+        separated_list0(comma, accept_range),
     )(input)?;
 
     Ok((input, Header::Accept(medias)))
@@ -291,7 +293,7 @@ pub fn accept_encoding(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Accept-Encoding"),
             header_colon,
         ),
-        separated_list(comma, encoding)
+        separated_list0(comma, encoding)
     )(input)?;
 
     Ok((input, Header::AcceptEncoding(encodings)))
@@ -309,7 +311,7 @@ fn language_range_other(input: &[u8]) -> Result<&[u8], LanguageRange> {
         many0(pair(tag("-"), take_while_m_n(1, 8, is_alphabetic)))
     ))(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, LanguageRange::Other(value.to_string())))
@@ -340,7 +342,7 @@ pub fn accept_language(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Accept-Language"),
             header_colon,
         ),
-        separated_list(comma, language)
+        separated_list0(comma, language)
     )(input)?;
 
     Ok((input, Header::AcceptLanguage(languages)))
@@ -401,7 +403,7 @@ pub fn content_encoding(input: &[u8]) -> Result<&[u8], Header> {
             )),
             header_colon,
         ),
-        separated_nonempty_list(comma, token_str)
+        separated_list1(comma, token_str)
     )(input)?;
 
     Ok((input, Header::ContentEncoding(encodings)))
@@ -521,7 +523,7 @@ fn language_tag(input: &[u8]) -> Result<&[u8], String> {
         )
     )(input)?;
 
-    let tag = std::str::from_utf8(tag)
+    let tag = core::str::from_utf8(tag)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -534,8 +536,44 @@ pub fn content_language(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Content-Language"),
             header_colon,
         ),
-        separated_nonempty_list(comma, language_tag)
+        separated_list1(comma, language_tag)
     )(input)?;
 
     Ok((input, Header::ContentLanguage(tags)))
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_separates_media_params_from_accept_params() {
+        let h = b"Accept: text/html;level=1;q=0.5;foo=bar";
+        let accept = match accept(h).unwrap().1 {
+            Header::Accept(mut accept) => accept.remove(0),
+            other => panic!("unexpected header: {:?}", other),
+        };
+
+        assert_eq!(accept.media.params, vec![
+            MediaParam { name: "level".to_string(), value: "1".to_string() },
+        ]);
+        assert_eq!(accept.q(), 0.5);
+        assert_eq!(accept.param("level"), Some("1"));
+        assert_eq!(accept.param("FOO"), Some("bar"));
+        assert_eq!(accept.param("q"), None);
+    }
+
+    #[test]
+    fn accept_encoding_empty_means_identity_only() {
+        let (_, empty) = accept_encoding(b"Accept-Encoding: ").unwrap();
+        assert_eq!(empty, Header::AcceptEncoding(vec![]));
+        assert!(empty.identity_only());
+
+        let (_, identity) = accept_encoding(b"Accept-Encoding: identity, gzip;q=0").unwrap();
+        assert!(identity.identity_only());
+
+        let (_, gzip) = accept_encoding(b"Accept-Encoding: gzip, identity").unwrap();
+        assert!(!gzip.identity_only());
+    }
+}
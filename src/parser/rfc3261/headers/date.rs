@@ -1,6 +1,9 @@
+use crate::prelude::*;
 use crate::{
     header::Header,
     parser::{
+        Error,
+        ErrorKind,
         Result,
         rfc3261::tokens::header_colon,
     },
@@ -47,17 +50,26 @@ fn wkday(input: &[u8]) -> Result<&[u8], &[u8]> {
     ))(input)
 }
 
+fn two_digits(digits: &[u8]) -> u8 {
+    (digits[0] - b'0') * 10 + (digits[1] - b'0')
+}
+
+/// Parses a time of day, from 00:00:00 to 23:59:60 (a leap second may be inserted)
 fn time(input: &[u8]) -> Result<&[u8], &[u8]> {
-    // TODO: Limit from 00:00:00 to 23:59:59
-    recognize(
-        tuple((
-            take_while_m_n(2, 2, is_digit),
-            tag(":"),
-            take_while_m_n(2, 2, is_digit),
-            tag(":"),
-            take_while_m_n(2, 2, is_digit),
-        ))
-    )(input)
+    let (rest, (hours, _, minutes, _, seconds)) = tuple((
+        take_while_m_n(2, 2, is_digit),
+        tag(":"),
+        take_while_m_n(2, 2, is_digit),
+        tag(":"),
+        take_while_m_n(2, 2, is_digit),
+    ))(input)?;
+
+    let time = &input[..input.len() - rest.len()];
+    if two_digits(hours) > 23 || two_digits(minutes) > 59 || two_digits(seconds) > 60 {
+        return Err(nom::Err::Failure(Error::new(ErrorKind::InvalidTime(time))));
+    }
+
+    Ok((rest, time))
 }
 
 fn date1(input: &[u8]) -> Result<&[u8], &[u8]> {
@@ -84,7 +96,7 @@ fn rfc1123_date(input: &[u8]) -> Result<&[u8], &str> {
         ))
     )(input)?;
 
-    let date = std::str::from_utf8(date)
+    let date = core::str::from_utf8(date)
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
     Ok((input, date))
@@ -101,3 +113,33 @@ pub fn date(input: &[u8]) -> Result<&[u8], Header> {
 
     Ok((input, Header::Date(date.to_string())))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_can_parse_rfc1123_date() {
+        let (rest, header) = date(b"Date: Sat, 13 Nov 2010 23:29:00 GMT").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::Date("Sat, 13 Nov 2010 23:29:00 GMT".to_string()));
+    }
+
+    #[test]
+    fn date_accepts_leap_seconds() {
+        assert!(date(b"Date: Sat, 31 Dec 2016 23:59:60 GMT").is_ok());
+    }
+
+    #[test]
+    fn date_rejects_out_of_range_times() {
+        assert_eq!(
+            date(b"Date: Sat, 13 Nov 2010 24:00:00 GMT"),
+            Err(nom::Err::Failure(Error::new(ErrorKind::InvalidTime(b"24:00:00"))))
+        );
+        assert_eq!(
+            date(b"Date: Sat, 13 Nov 2010 12:60:00 GMT"),
+            Err(nom::Err::Failure(Error::new(ErrorKind::InvalidTime(b"12:60:00"))))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{ Header, ErrorInfo, },
     parser::{
@@ -19,7 +20,7 @@ use crate::{
 
 use nom::{
     sequence::{ pair, tuple, preceded, terminated, },
-    multi::separated_nonempty_list,
+    multi::separated_list1,
     bytes::complete::tag_no_case,
 };
 
@@ -32,7 +33,7 @@ fn error_uri(input: &[u8]) -> Result<&[u8], ErrorInfo> {
         generic_params
     ))(input)?;
 
-    let uri = std::str::from_utf8(uri)
+    let uri = core::str::from_utf8(uri)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -48,7 +49,7 @@ pub fn error_info(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Error-Info"),
             header_colon,
         ),
-        separated_nonempty_list(comma, error_uri)
+        separated_list1(comma, error_uri)
     )(input)?;
 
     Ok((input, Header::ErrorInfo(errors)))
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+use crate::{
+    header::{ Header, IdentityInfo, },
+    parser::{
+        Result,
+        rfc3261::{
+            tokens::{
+                header_colon,
+                left_angle_quote,
+                right_angle_quote,
+                quoted_string,
+            },
+            common::{
+                generic_params,
+                absolute_uri,
+            },
+        },
+    },
+};
+
+use nom::{
+    sequence::{ pair, tuple, preceded, terminated, },
+    bytes::complete::tag_no_case,
+};
+
+pub fn identity(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, signature) = preceded(
+        pair(
+            tag_no_case("Identity"),
+            header_colon,
+        ),
+        quoted_string
+    )(input)?;
+
+    let signature = core::str::from_utf8(signature)
+        .map(|s| s.to_string())
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    Ok((input, Header::Identity(signature)))
+}
+
+pub fn identity_info(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, (uri, params)) = preceded(
+        pair(
+            tag_no_case("Identity-Info"),
+            header_colon,
+        ),
+        tuple((
+            preceded(
+                left_angle_quote,
+                terminated(absolute_uri, right_angle_quote)
+            ),
+            generic_params
+        ))
+    )(input)?;
+
+    let uri = core::str::from_utf8(uri)
+        .map(|s| s.to_string())
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    let (alg, params): (Vec<_>, Vec<_>) = params.into_iter()
+        .partition(|param| param.name.eq_ignore_ascii_case("alg"));
+
+    Ok((input, Header::IdentityInfo(IdentityInfo {
+        uri,
+        alg: alg.into_iter().find_map(|param| param.value),
+        params,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_can_parse_signature() {
+        let (rest, header) = identity(b"Identity: \"ZYNBbHC00VMZr2kZt6VmCvPonWJMGvQTBDqghoWeLxJfzB2a1pxAr3VgrB0SsSAa\"").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::Identity("ZYNBbHC00VMZr2kZt6VmCvPonWJMGvQTBDqghoWeLxJfzB2a1pxAr3VgrB0SsSAa".to_string()));
+    }
+
+    #[test]
+    fn identity_info_can_parse_uri_and_alg() {
+        let (rest, header) = identity_info(b"Identity-Info: <https://atlanta.example.com/atlanta.cer>;alg=rsa-sha1").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::IdentityInfo(IdentityInfo {
+            uri: "https://atlanta.example.com/atlanta.cer".to_string(),
+            alg: Some("rsa-sha1".to_string()),
+            params: vec![],
+        }));
+        assert_eq!(header.to_string(), "Identity-Info: <https://atlanta.example.com/atlanta.cer>;alg=rsa-sha1");
+    }
+}
@@ -8,11 +8,18 @@ mod call;
 mod contact;
 mod via;
 mod warning;
+mod identity;
+mod charging;
+mod refer;
 
+use crate::prelude::*;
 use crate::{
-    header::{ Header, RetryParam, RetryAfter, },
+    header::{ Header, RetryParam, RetryAfter, Timestamp, },
+    sip::CSeq,
     parser::{
         integer,
+        Error,
+        ErrorKind,
         rfc3261::{
             tokens::{
                 header_colon,
@@ -42,9 +49,9 @@ use nom::{
     combinator::{ opt, recognize },
     sequence::{ pair, tuple, preceded, terminated, },
     branch::alt,
-    multi::{ many0, separated_list, },
+    multi::{ many0, separated_list0, },
     character::complete::{ digit0, digit1 },
-    bytes::complete::{ tag, tag_no_case, take_while, },
+    bytes::complete::{ tag, tag_no_case, take_while1, },
 };
 
 use crate::parser::Result;
@@ -55,25 +62,39 @@ fn allow(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Allow"),
             header_colon,
         ),
-        separated_list(comma, method)
+        separated_list0(comma, method)
     )(input)?;
 
     Ok((input, Header::Allow(methods)))
 }
 
+fn cseq_value(input: &[u8]) -> Result<'_, &[u8], CSeq> {
+    let (input, (sequence, method)) = pair(
+        integer::<u32>,
+        preceded(linear_whitespace, method)
+    )(input)?;
+
+    // RFC3261 requires the sequence number to be less than 2**31
+    if sequence > i32::MAX as u32 {
+        return Err(nom::Err::Failure(Error::new(ErrorKind::InvalidIntegerError)));
+    }
+
+    Ok((input, CSeq { sequence, method }))
+}
+
 fn cseq(input: &[u8]) -> Result<&[u8], Header> {
-    let (input, (cseq, method)) = preceded(
-        pair(
-            tag_no_case("CSeq"),
-            header_colon,
-        ),
-        pair(
-            integer,
-            preceded(linear_whitespace, method)
-        )
+    let (input, _) = pair(
+        tag_no_case("CSeq"),
+        header_colon,
     )(input)?;
 
-    Ok((input, Header::CSeq(cseq, method)))
+    let (input, cseq) = cseq_value(input)
+        .map_err(|_| {
+            let line = &input[..input.iter().position(|&c| c == b'\r').unwrap_or(input.len())];
+            nom::Err::Failure(Error::new(ErrorKind::InvalidCSeq(line)))
+        })?;
+
+    Ok((input, Header::CSeq(cseq)))
 }
 
 fn expires(input: &[u8]) -> Result<&[u8], Header> {
@@ -82,7 +103,7 @@ fn expires(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Expires"),
             header_colon,
         ),
-        integer,
+        integer::<u32>,
     )(input)?;
 
     Ok((input, Header::Expires(e)))
@@ -113,7 +134,7 @@ fn mime_version(input: &[u8]) -> Result<&[u8], Header> {
         )))
     )(input)?;
 
-    let version = std::str::from_utf8(version)
+    let version = core::str::from_utf8(version)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -126,12 +147,37 @@ fn min_expires(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Min-Expires"),
             header_colon,
         ),
-        integer,
+        integer::<u32>,
     )(input)?;
 
     Ok((input, Header::MinExpires(me)))
 }
 
+/// Decodes a TEXT-UTF8 header value, replacing each folded line break (and the whitespace around
+/// it) by a single space
+fn unfolded(input: &[u8]) -> core::result::Result<String, nom::Err<Error<'_, &[u8]>>> {
+    let mut text = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i..].starts_with(b"\r\n") {
+            while matches!(text.last(), Some(b' ') | Some(b'\t')) {
+                text.pop();
+            }
+            i += 2;
+            while matches!(input.get(i), Some(b' ') | Some(b'\t')) {
+                i += 1;
+            }
+            text.push(b' ');
+        } else {
+            text.push(input[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(text)
+        .map_err(|err| nom::Err::Failure(err.utf8_error().into()))
+}
+
 fn organization(input: &[u8]) -> Result<&[u8], Header> {
     let (input, org) = preceded(
         pair(
@@ -142,9 +188,7 @@ fn organization(input: &[u8]) -> Result<&[u8], Header> {
     )(input)?;
 
     let org = match org {
-        Some(org) => Some(std::str::from_utf8(org)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
+        Some(org) => Some(unfolded(org)?),
         None => None,
     };
 
@@ -212,7 +256,7 @@ fn retry_after(input: &[u8]) -> Result<&[u8], Header> {
     )(input)?;
 
     let comment = match comment {
-        Some(comment) => Some(std::str::from_utf8(comment)
+        Some(comment) => Some(core::str::from_utf8(comment)
             .map(|s| s.to_string())
             .map_err(|err| nom::Err::Failure(err.into()))?),
         None => None,
@@ -247,7 +291,7 @@ fn server(input: &[u8]) -> Result<&[u8], Header> {
         )),
     )(input)?;
 
-    let s = std::str::from_utf8(s)
+    let s = core::str::from_utf8(s)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -266,7 +310,7 @@ fn user_agent(input: &[u8]) -> Result<&[u8], Header> {
         )),
     )(input)?;
 
-    let ua = std::str::from_utf8(ua)
+    let ua = core::str::from_utf8(ua)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -283,9 +327,7 @@ fn subject(input: &[u8]) -> Result<&[u8], Header> {
     )(input)?;
 
     let subject = match subject {
-        Some(subject) => Some(std::str::from_utf8(subject)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
+        Some(subject) => Some(unfolded(subject)?),
         None => None,
     };
 
@@ -308,6 +350,15 @@ fn supported(input: &[u8]) -> Result<&[u8], Header> {
     Ok((input, Header::Supported(others)))
 }
 
+/// Checks that a timestamp or delay is a number, and returns it as written
+fn timestamp_value(value: &[u8]) -> core::result::Result<String, nom::Err<Error<'_, &[u8]>>> {
+    core::str::from_utf8(value)
+        .ok()
+        .filter(|value| value.parse::<f64>().is_ok())
+        .map(|value| value.to_string())
+        .ok_or_else(|| nom::Err::Failure(Error::new(ErrorKind::InvalidTimestamp(value))))
+}
+
 fn delay(input: &[u8]) -> Result<&[u8], Option<String>> {
     let (input, delay) = opt(preceded(
         linear_whitespace,
@@ -320,17 +371,15 @@ fn delay(input: &[u8]) -> Result<&[u8], Option<String>> {
     ))(input)?;
 
     let delay = match delay {
-        Some(delay) => Some(std::str::from_utf8(delay)
-            .map(|s| s.to_string())
-            .map_err(|err| nom::Err::Failure(err.into()))?),
-        None => None,
+        Some(b"") | None => None,
+        Some(delay) => Some(timestamp_value(delay)?),
     };
 
     Ok((input, delay))
 }
 
 fn timestamp(input: &[u8]) -> Result<&[u8], Header> {
-    let (input, (ts, delay)) = preceded(
+    let (input, (value, delay)) = preceded(
         pair(
             tag_no_case("Timestamp"),
             header_colon,
@@ -346,11 +395,10 @@ fn timestamp(input: &[u8]) -> Result<&[u8], Header> {
         )
     )(input)?;
 
-    let ts = std::str::from_utf8(ts)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
-    Ok((input, Header::Timestamp(ts, delay)))
+    Ok((input, Header::Timestamp(Timestamp {
+        value: timestamp_value(value)?,
+        delay,
+    })))
 }
 
 fn unsupported(input: &[u8]) -> Result<&[u8], Header> {
@@ -373,12 +421,12 @@ fn header_value(input: &[u8]) -> Result<&[u8], String> {
     let (input, value) = recognize(
         many0(alt((
             utf8_char1,
-            take_while(is_utf8_cont),
+            take_while1(is_utf8_cont),
             linear_whitespace,
         )))
     )(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -394,10 +442,42 @@ fn extension_header(input: &[u8]) -> Result<&[u8], Header> {
     Ok((input, Header::Extension(name, value)))
 }
 
+/// Parses the headers found in nearly every request, by dispatching on their name
+///
+/// Going through every known header parser until one matches is the costliest part of parsing a
+/// header, so the mandatory headers of a request (along with Contact and Content-Length) are
+/// looked up by name first. Any other name, or a value the dedicated parser doesn't accept, is
+/// left to the full list of parsers in `message_header`.
+fn common_header(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (_, name) = token(input)?;
+    let is = |names: &[&str]| names.iter().any(|n| name.eq_ignore_ascii_case(n.as_bytes()));
+
+    if is(&["Via", "v"]) {
+        via::via(input)
+    } else if is(&["From", "f"]) {
+        contact::from(input)
+    } else if is(&["To", "t"]) {
+        contact::to(input)
+    } else if is(&["Call-ID", "i"]) {
+        call::call_id(input)
+    } else if is(&["CSeq"]) {
+        cseq(input)
+    } else if is(&["Max-Forwards"]) {
+        max_forwards(input)
+    } else if is(&["Contact", "m"]) {
+        contact::contact(input)
+    } else if is(&["Content-Length", "l"]) {
+        content::content_length(input)
+    } else {
+        Err(nom::Err::Error(Error::new(ErrorKind::Nom(input, nom::error::ErrorKind::Tag))))
+    }
+}
+
 pub fn message_header(input: &[u8]) -> Result<&[u8], Header> {
     let (input, header) = terminated(
         // alt() only supports 21 entries
         alt((
+            common_header,
             alt((
                 content::accept,
                 content::accept_encoding,
@@ -447,6 +527,19 @@ pub fn message_header(input: &[u8]) -> Result<&[u8], Header> {
             alt((
                 warning::warning,
                 auth::www_authenticate,
+                call::answer_mode,
+                call::join,
+                call::priv_answer_mode,
+                contact::p_asserted_identity,
+                contact::p_associated_uri,
+                contact::p_called_party_id,
+                refer::refer_sub,
+                refer::target_dialog,
+                charging::p_charging_function_addresses,
+                identity::identity,
+                identity::identity_info,
+                contact::accept_contact,
+                contact::reject_contact,
                 extension_header,
             ))
         )),
@@ -456,10 +549,53 @@ pub fn message_header(input: &[u8]) -> Result<&[u8], Header> {
     Ok((input, header))
 }
 
+/// Parses a header, keeping it as an extension header if the value of a known header is malformed
+///
+/// Along with the header, whether it had to be downgraded to an extension header is returned.
+/// Headers that aren't well-formed extension headers either are still rejected.
+pub fn message_header_lenient(input: &[u8]) -> Result<'_, &[u8], (Header, bool)> {
+    match message_header(input) {
+        Ok((input, header)) => Ok((input, (header, false))),
+        Err(nom::Err::Incomplete(needed)) => Err(nom::Err::Incomplete(needed)),
+        Err(err) => match terminated(extension_header, newline)(input) {
+            Ok((input, header)) => Ok((input, (header, true))),
+            Err(_) => Err(err),
+        },
+    }
+}
+
+/// Consumes a header line without interpreting it
+///
+/// The line ends at the first CRLF not followed by whitespace (folded lines are part of the
+/// header), or at the end of the input. The terminating CRLF is consumed, but not returned. This
+/// is used to skip over headers that `message_header` could not make sense of.
+pub fn raw_header_line(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    if input.is_empty() {
+        return Err(nom::Err::Error(
+            Error::new(ErrorKind::Nom(input, nom::error::ErrorKind::Eof))
+        ));
+    }
+
+    let mut end = 0;
+    while end < input.len() {
+        if input[end..].starts_with(b"\r\n") {
+            match input.get(end + 2) {
+                Some(b' ') | Some(b'\t') => end += 2,
+                _ => return Ok((&input[end + 2..], &input[..end])),
+            }
+        } else {
+            end += 1;
+        }
+    }
+
+    Ok((&input[end..], input))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::header::*;
+    use crate::sip::{ Host, Method, Transport, };
 
     #[test]
     fn message_header_can_parse_via() {
@@ -469,7 +605,9 @@ mod tests {
             Header::Via(vias) => {
                 let via = &vias[0];
                 assert_eq!(via.protocol, "SIP/2.0/TCP");
-                assert_eq!(via.sent_by, "client.atlanta.example.com:5060");
+                assert_eq!(via.transport(), &Transport::TCP);
+                assert_eq!(via.sent_by, Host::Domain("client.atlanta.example.com".to_string()));
+                assert_eq!(via.port, Some(5060));
 
                 match &via.params[0] {
                     ViaParam::Branch(v) => assert_eq!(v, &"z9hG4bK74b43"),
@@ -481,6 +619,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn message_header_parses_the_via_transport() {
+        let h = b"v: SIP/2.0/tls pc33.atlanta.example.com, SIP/2.0/WS df7jal23ls0d.invalid;rport\r\n";
+        match message_header(h).unwrap().1 {
+            Header::Via(vias) => {
+                assert_eq!(vias[0].protocol, "SIP/2.0/tls");
+                assert_eq!(vias[0].transport(), &Transport::TLS);
+                assert_eq!(vias[0].response_port(), 5061);
+                assert_eq!(vias[1].transport(), &Transport::Extension("WS".to_string()));
+            },
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn common_header_dispatches_on_the_header_name() {
+        assert_eq!(common_header(b"max-forwards: 70\r\n"), Ok((&b"\r\n"[..], Header::MaxForwards(70))));
+        assert_eq!(common_header(b"l: 0\r\n"), Ok((&b"\r\n"[..], Header::ContentLength(0))));
+        assert!(matches!(common_header(b"Route: <sip:ss1.atlanta.example.com;lr>\r\n"), Err(nom::Err::Error(_))));
+        assert!(matches!(common_header(b"Timestamp: 54\r\n"), Err(nom::Err::Error(_))));
+        assert!(matches!(message_header(b"Timestamp: 54\r\n"), Ok((_, Header::Timestamp(_)))));
+    }
+
+    #[test]
+    fn message_header_can_parse_timestamp_with_delay() {
+        let header = message_header(b"Timestamp: 54.25 0.5\r\n").unwrap().1;
+        match header {
+            Header::Timestamp(timestamp) => {
+                assert_eq!(timestamp.value, "54.25");
+                assert_eq!(timestamp.as_f64(), Some(54.25));
+                assert_eq!(timestamp.round_trip_hint(), Some(0.5));
+            },
+            other => panic!("unexpected header: {:?}", other),
+        }
+
+        match message_header(b"Timestamp: 54\r\n").unwrap().1 {
+            Header::Timestamp(timestamp) => assert_eq!(timestamp.round_trip_hint(), None),
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn timestamp_keeps_its_precision() {
+        let header = message_header(b"Timestamp: 1700000000.123 0.000125\r\n").unwrap().1;
+        assert_eq!(header.to_string(), "Timestamp: 1700000000.123 0.000125");
+
+        match header {
+            Header::Timestamp(timestamp) => {
+                assert_eq!(timestamp.as_f64(), Some(1700000000.123));
+                assert_eq!(timestamp.round_trip_hint(), Some(0.000125));
+            },
+            other => panic!("unexpected header: {:?}", other),
+        }
+
+        assert!(matches!(message_header(b"Timestamp: 54 .\r\n"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn message_header_can_parse_via_with_trailing_dot_fqdn() {
+        let h = b"Via: SIP/2.0/UDP client.atlanta.example.com.:5060;branch=z9hG4bK74b43\r\n";
+        match message_header(h).unwrap().1 {
+            Header::Via(vias) => {
+                assert_eq!(vias[0].sent_by, Host::Domain("client.atlanta.example.com.".to_string()));
+                assert_eq!(vias[0].sent_by, Host::Domain("client.atlanta.example.com".to_string()));
+                assert_eq!(vias[0].port, Some(5060));
+                assert_eq!(vias[0].to_string(), "SIP/2.0/UDP client.atlanta.example.com.:5060;branch=z9hG4bK74b43");
+            },
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
     #[test]
     fn message_header_can_parse_max_forwards() {
         let h = b"Max-Forwards: 70\r\n";
@@ -493,6 +702,202 @@ mod tests {
 
         }
     }
+
+    #[test]
+    fn message_header_can_parse_cseq() {
+        let h = b"CSeq: 2147483647 INVITE\r\n";
+        assert_eq!(message_header(h), Ok((&b""[..], Header::CSeq(CSeq {
+            sequence: 2147483647,
+            method: Method::Invite,
+        }))));
+    }
+
+    #[test]
+    fn message_header_rejects_non_numeric_cseq() {
+        let h = b"CSeq: abc INVITE\r\n";
+        match message_header(h) {
+            Err(nom::Err::Failure(err)) => assert_eq!(err.kind, ErrorKind::InvalidCSeq(b"abc INVITE")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn message_header_rejects_out_of_range_cseq() {
+        for h in &[&b"CSeq: 2147483648 INVITE\r\n"[..], &b"CSeq: 4294967296 INVITE\r\n"[..]] {
+            match message_header(h) {
+                Err(nom::Err::Failure(err)) => assert!(matches!(err.kind, ErrorKind::InvalidCSeq(_))),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn message_header_reads_expires_as_unsigned_32_bits() {
+        assert_eq!(message_header(b"Expires: 4294967295\r\n").unwrap().1, Header::Expires(u32::MAX));
+        assert_eq!(message_header(b"Min-Expires: 4294967295\r\n").unwrap().1, Header::MinExpires(u32::MAX));
+
+        for h in &[&b"Expires: 4294967296\r\n"[..], &b"Min-Expires: 4294967296\r\n"[..]] {
+            match message_header(h) {
+                Err(nom::Err::Failure(err)) => assert_eq!(err.kind, ErrorKind::InvalidIntegerError),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn subject_and_organization_are_unfolded() {
+        let (rest, header) = message_header(b"s: Need more\r\n boxes,\r\n\t\tand  tape\r\n").unwrap();
+        assert_eq!(header, Header::Subject(Some("Need more boxes, and  tape".to_string())));
+        assert_eq!(rest, b"");
+
+        let (_, header) = message_header("Organization: Caf\u{e9} \r\n Biloxi\r\n".as_bytes()).unwrap();
+        assert_eq!(header, Header::Organization(Some("Caf\u{e9} Biloxi".to_string())));
+    }
+
+    #[test]
+    fn empty_subject_and_organization_have_no_value() {
+        for h in &[&b"Subject:\r\n"[..], &b"Subject: \r\n"[..], &b"s:\r\n"[..]] {
+            assert_eq!(message_header(h), Ok((&b""[..], Header::Subject(None))));
+        }
+        assert_eq!(message_header(b"Organization:\r\n"), Ok((&b""[..], Header::Organization(None))));
+    }
+
+    #[test]
+    fn allow_can_be_empty() {
+        for h in &[&b"Allow:\r\n"[..], &b"Allow: \r\n"[..]] {
+            assert_eq!(message_header(h), Ok((&b""[..], Header::Allow(vec![]))));
+        }
+    }
+
+    #[test]
+    fn allow_tolerates_whitespace_around_commas() {
+        let h = b"Allow: INVITE ,ACK ,\tOPTIONS,  BYE\r\n";
+        assert_eq!(message_header(h), Ok((&b""[..], Header::Allow(vec![
+            Method::Invite,
+            Method::Ack,
+            Method::Options,
+            Method::Bye,
+        ]))));
+    }
+
+    #[test]
+    fn message_header_lenient_downgrades_malformed_known_headers() {
+        assert_eq!(
+            message_header_lenient(b"Max-Forwards: 70abc\r\n"),
+            Ok((&b""[..], (Header::Extension("Max-Forwards".to_string(), "70abc".to_string()), true))),
+        );
+        assert_eq!(
+            message_header_lenient(b"Max-Forwards: 70\r\n"),
+            Ok((&b""[..], (Header::MaxForwards(70), false))),
+        );
+        assert!(message_header_lenient(b"Max-Forwards: 70\rabc\r\n").is_err());
+        assert!(message_header_lenient(b"\r\n").is_err());
+    }
+
+    #[test]
+    fn raw_header_line_stops_at_crlf() {
+        assert_eq!(raw_header_line(b"CSeq: abc\r\nTo: x\r\n"), Ok((&b"To: x\r\n"[..], &b"CSeq: abc"[..])));
+    }
+
+    #[test]
+    fn raw_header_line_includes_folded_lines() {
+        assert_eq!(raw_header_line(b"X: a\r\n b\r\n\r\n"), Ok((&b"\r\n"[..], &b"X: a\r\n b"[..])));
+    }
+
+    #[test]
+    fn raw_header_line_consumes_unterminated_input() {
+        assert_eq!(raw_header_line(b"X: a"), Ok((&b""[..], &b"X: a"[..])));
+        assert!(raw_header_line(b"").is_err());
+    }
+
+    #[test]
+    fn extension_header_value_stops_at_bare_crlf() {
+        let h = b"X-Foo: bar\r\nX-Evil: 1\r\n";
+        let (rest, header) = message_header(h).unwrap();
+        assert_eq!(header, Header::Extension("X-Foo".to_string(), "bar".to_string()));
+        assert_eq!(rest, b"X-Evil: 1\r\n");
+
+        let (rest, header) = message_header(rest).unwrap();
+        assert_eq!(header, Header::Extension("X-Evil".to_string(), "1".to_string()));
+        assert_eq!(rest, b"");
+    }
+
+    #[test]
+    fn extension_header_value_keeps_folded_lines() {
+        let h = b"X-Foo: bar\r\n baz\r\n";
+        let (rest, header) = message_header(h).unwrap();
+        assert_eq!(header, Header::Extension("X-Foo".to_string(), "bar\r\n baz".to_string()));
+        assert_eq!(rest, b"");
+    }
+
+    #[test]
+    fn extension_header_value_rejects_bare_cr_or_lf() {
+        assert!(message_header(b"X-Foo: bar\rX-Evil: 1\r\n").is_err());
+        assert!(message_header(b"X-Foo: bar\nX-Evil: 1\r\n").is_err());
+    }
+
+    #[test]
+    fn extension_header_value_accepts_utf8() {
+        let h = "X-Foo: café\r\n".as_bytes();
+        let (_, header) = message_header(h).unwrap();
+        assert_eq!(header, Header::Extension("X-Foo".to_string(), "café".to_string()));
+    }
+    #[test]
+    fn message_header_round_trips_through_display() {
+        let lines = [
+            "Max-Forwards: 70",
+            "Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43;received=192.0.2.101",
+            "Via: SIP/2.0/UDP [::1]:5060;branch=z9hG4bK1, SIP/2.0/TCP pc33.atlanta.example.com;branch=z9hG4bK2",
+            "From: \"Alice\" <sip:alice@atlanta.example.com>;tag=9fxced76sl",
+            "From: \"Doe, \\\"JD\\\" John\" <sip:jd@atlanta.example.com>;tag=9fxced76sl",
+            "To: <sip:bob@biloxi.example.com>",
+            "Contact: <sip:alice@client.atlanta.example.com;transport=tcp>;expires=3600",
+            "Contact: *",
+            "CSeq: 1 INVITE",
+            "Call-ID: 3848276298220188511@atlanta.example.com",
+            "Content-Type: application/sdp",
+            "Content-Length: 151",
+            "Route: <sip:ss1.atlanta.example.com;lr>, <sip:ss2.biloxi.example.com;lr>",
+            "Allow: INVITE, ACK, OPTIONS, CANCEL, BYE",
+            "Authorization: Digest username=\"alice\", realm=\"atlanta.example.com\", nonce=\"dc3a5ab2\", uri=\"sip:bob@biloxi.example.com\", response=\"ccdca50cb091d587421457305d097458\", algorithm=MD5",
+            "WWW-Authenticate: Digest realm=\"atlanta.example.com\", qop=\"auth\", nonce=\"ea9c8e88df84f1cec4341ae6cbe5a359\", stale=false, algorithm=MD5",
+            "Accept: application/sdp;level=1, application/x-private, text/html",
+            "Accept-Language: da, en-gb;q=0.8, en;q=0.7",
+            "Subject: Need more boxes",
+            "Warning: 307 isi.edu \"Session parameter 'foo' not understood\"",
+            "Retry-After: 18000;duration=3600",
+            "Timestamp: 54",
+            "Call-Info: <http://www.example.com/alice/photo.jpg>;purpose=icon",
+            "Answer-Mode: Auto;require",
+            "P-Asserted-Identity: \"Cullen Jennings\" <sip:fluffy@cisco.com>, <tel:+14085264000>",
+            "Identity: \"CyI4+nAkHrH3ntmaxgr01TMxTmtjP7MASwliNRdupRI1vpkXRvZXx1ja9k3W+v1\"",
+            "Identity-Info: <https://atlanta.example.com/atlanta.cer>;alg=rsa-sha1",
+            "Accept-Contact: *;audio;require, *;methods=\"INVITE,BYE\";explicit",
+            "Reject-Contact: *;video;class=business",
+            "P-Charging-Function-Addresses: ccf=192.1.1.1;ccf=192.1.1.2;ecf=192.1.1.3",
+            "P-Associated-URI: <sip:user1-business@example.com>, <sip:+1-212-555-1234@example.com;user=phone>",
+            "P-Called-Party-ID: \"John Doe\" <sip:user1-business@example.com>;foo",
+            "Refer-Sub: false;foo=bar",
+            "Join: 12adf2f34456gs5;to-tag=12345;from-tag=54321;early-only",
+            "Target-Dialog: 7654@client.atlanta.example.com;local-tag=kkaz-;remote-tag=6544;early-only",
+            "X-Custom: some value",
+        ];
+
+        for line in lines.iter() {
+            let input = format!("{}\r\n", line);
+            let (_, header) = message_header(input.as_bytes()).unwrap();
+            assert_eq!(header.to_string(), *line);
+        }
+    }
+
+    #[test]
+    fn header_display_renders_canonical_names() {
+        assert_eq!(Header::MaxForwards(70).to_string(), "Max-Forwards: 70");
+
+        let (_, header) = message_header(b"i: abc@example.com\r\n").unwrap();
+        assert_eq!(header.to_string(), "Call-ID: abc@example.com");
+    }
+
 /*
     #[test]
     fn message_header_can_parse_route() {
@@ -0,0 +1,99 @@
+use crate::{
+    header::{ Header, TargetDialog, },
+    parser::{
+        Result,
+        rfc3261::{
+            tokens::header_colon,
+            common::{
+                boolean,
+                generic_params,
+            },
+        },
+    },
+};
+
+use super::call::{ callid, dialog_tags, };
+
+use nom::{
+    sequence::{ pair, preceded, },
+    bytes::complete::tag_no_case,
+};
+
+pub fn refer_sub(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (input, (value, params)) = preceded(
+        pair(
+            tag_no_case("Refer-Sub"),
+            header_colon,
+        ),
+        pair(boolean, generic_params)
+    )(input)?;
+
+    Ok((input, Header::ReferSub(value, params)))
+}
+
+pub fn target_dialog(input: &[u8]) -> Result<'_, &[u8], Header> {
+    let (rest, (call_id, params)) = preceded(
+        pair(
+            tag_no_case("Target-Dialog"),
+            header_colon,
+        ),
+        pair(callid, generic_params)
+    )(input)?;
+
+    // both tags are needed to identify the dialog (RFC4538)
+    let (local_tag, remote_tag, params) = dialog_tags(input, params, ("local-tag", "remote-tag"))?;
+
+    Ok((rest, Header::TargetDialog(TargetDialog {
+        call_id,
+        local_tag,
+        remote_tag,
+        params,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::GenericParam;
+
+    #[test]
+    fn refer_sub_can_parse_value_and_params() {
+        let (rest, header) = refer_sub(b"Refer-Sub: FALSE;foo=bar").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::ReferSub(false, vec![GenericParam {
+            name: "foo".to_string(),
+            value: Some("bar".to_string()),
+        }]));
+        assert_eq!(refer_sub(b"Refer-Sub: true").unwrap().1, Header::ReferSub(true, vec![]));
+        assert!(refer_sub(b"Refer-Sub: maybe").is_err());
+    }
+
+    #[test]
+    fn target_dialog_extracts_tags() {
+        let (rest, header) = target_dialog(b"Target-Dialog: fa77as7dad8-sd98ajzz@host.example.com;local-tag=kkaz-;remote-tag=6544;early-only").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::TargetDialog(TargetDialog {
+            call_id: "fa77as7dad8-sd98ajzz@host.example.com".to_string(),
+            local_tag: "kkaz-".to_string(),
+            remote_tag: "6544".to_string(),
+            params: vec![GenericParam {
+                name: "early-only".to_string(),
+                value: None,
+            }],
+        }));
+    }
+
+    #[test]
+    fn target_dialog_requires_both_tags() {
+        let missing = |input: &[u8]| match target_dialog(input) {
+            Err(nom::Err::Failure(err)) => Some(err.kind.to_string()),
+            _ => None,
+        };
+
+        assert_eq!(missing(b"Target-Dialog: fa77as7dad8@host.example.com;local-tag=kkaz-").as_deref(), Some("missing remote-tag param"));
+        assert_eq!(missing(b"Target-Dialog: fa77as7dad8@host.example.com;remote-tag=6544").as_deref(), Some("missing local-tag param"));
+        assert_eq!(missing(b"Target-Dialog: fa77as7dad8@host.example.com").as_deref(), Some("missing local-tag param"));
+    }
+}
@@ -1,5 +1,7 @@
+use crate::prelude::*;
 use crate::{
     header::{ Header, ViaParam, Via },
+    sip::{ Host, Transport, },
     parser::{
         Error,
         integer,
@@ -29,19 +31,17 @@ use crate::{
 };
 
 use nom::{
-    combinator::{ opt, recognize },
+    combinator::{ consumed, opt, recognize },
     sequence::{ pair, tuple, preceded },
-    multi::{ many0, separated_nonempty_list, },
+    multi::{ many0, separated_list1, },
     branch::alt,
     bytes::complete::tag_no_case,
 };
 
-fn sent_by(input: &[u8]) -> Result<&[u8], &[u8]> {
-    recognize(
-        pair(
-            host,
-            opt(pair(colon, port))
-        )
+fn sent_by(input: &[u8]) -> Result<&[u8], (Host, Option<u16>)> {
+    pair(
+        host,
+        opt(preceded(colon, port))
     )(input)
 }
 
@@ -51,22 +51,22 @@ fn protocol_name(input: &[u8]) -> Result<&[u8], &str> {
         token,
     ))(input)?;
 
-    let value = std::str::from_utf8(value)
+    let value = core::str::from_utf8(value)
         .map_err(|err| nom::Err::Failure(Error::from(err)))?;
 
     Ok((input, value))
 }
 
-fn sent_protocol(input: &[u8]) -> Result<&[u8], &[u8]> {
-    recognize(
-        tuple((
-            protocol_name,
-            slash,
-            token,
-            slash,
-            transport,
-        ))
-    )(input)
+fn sent_protocol(input: &[u8]) -> Result<&[u8], (&[u8], Transport)> {
+    let (input, (protocol, (_, _, _, _, transport))) = consumed(tuple((
+        protocol_name,
+        slash,
+        token,
+        slash,
+        transport,
+    )))(input)?;
+
+    Ok((input, (protocol, transport)))
 }
 
 fn via_extension(input: &[u8]) -> Result<&[u8], ViaParam> {
@@ -96,7 +96,7 @@ fn via_received(input: &[u8]) -> Result<&[u8], ViaParam> {
         alt((ipv4_address, ipv6_address)),
     )(input)?;
 
-    let addr = std::str::from_utf8(addr)
+    let addr = core::str::from_utf8(addr)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(Error::from(err)))?;
 
@@ -109,10 +109,10 @@ fn via_maddr(input: &[u8]) -> Result<&[u8], ViaParam> {
             tag_no_case("maddr"),
             equal,
         ),
-        host,
+        recognize(host),
     )(input)?;
 
-    let maddr = std::str::from_utf8(maddr)
+    let maddr = core::str::from_utf8(maddr)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(Error::from(err)))?;
 
@@ -142,23 +142,21 @@ fn via_params(input: &[u8]) -> Result<&[u8], ViaParam> {
 }
 
 fn via_parm(input: &[u8]) -> Result<&[u8], Via> {
-    let (input, (protocol, sent_by, params)) = tuple((
+    let (input, ((protocol, transport), (sent_by, port), params)) = tuple((
         sent_protocol,
         preceded(linear_whitespace, sent_by),
         many0(preceded(semicolon, via_params))
     ))(input)?;
 
-    let protocol = std::str::from_utf8(protocol)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(Error::from(err)))?;
-
-    let sent_by = std::str::from_utf8(sent_by)
+    let protocol = core::str::from_utf8(protocol)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(Error::from(err)))?;
 
     Ok((input, Via {
         protocol,
+        transport,
         sent_by,
+        port,
         params,
     }))
 }
@@ -169,7 +167,7 @@ pub fn via(input: &[u8]) -> Result<&[u8], Header> {
             alt((tag_no_case("Via"), tag_no_case("v"))),
             header_colon,
         ),
-        separated_nonempty_list(comma, via_parm)
+        separated_list1(comma, via_parm)
     )(input)?;
 
     Ok((input, Header::Via(vias)))
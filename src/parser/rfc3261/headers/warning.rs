@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{
     header::{ Header, Warning, WarningAgent, },
     parser::{
@@ -18,7 +19,7 @@ use crate::{
 
 use nom::{
     sequence::{ pair, tuple, preceded },
-    multi::separated_nonempty_list,
+    multi::separated_list1,
     branch::alt,
     character::is_digit,
     bytes::complete::{
@@ -31,10 +32,6 @@ use nom::{
 fn warning_agent_host_port(input: &[u8]) -> Result<&[u8], WarningAgent> {
     let (input, (host, port)) = host_port(input)?;
 
-    let host = std::str::from_utf8(host)
-        .map(|s| s.to_string())
-        .map_err(|err| nom::Err::Failure(err.into()))?;
-
     Ok((input, WarningAgent::HostPort(host, port)))
 }
 
@@ -59,10 +56,10 @@ fn warning_value(input: &[u8]) -> Result<&[u8], Warning> {
         preceded(tag(" "), quoted_string)
     ))(input)?;
 
-    let code = std::str::from_utf8(code)
+    let code = core::str::from_utf8(code)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
-    let text = std::str::from_utf8(text)
+    let text = core::str::from_utf8(text)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -79,8 +76,27 @@ pub fn warning(input: &[u8]) -> Result<&[u8], Header> {
             tag_no_case("Warning"),
             header_colon,
         ),
-        separated_nonempty_list(comma, warning_value)
+        separated_list1(comma, warning_value)
     )(input)?;
 
     Ok((input, Header::Warning(warnings)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sip::Host;
+
+    #[test]
+    fn warning_can_parse_ipv6_agent() {
+        let (rest, header) = warning(b"Warning: 370 [::1]:5060 \"Insufficient bandwidth\"").unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(header, Header::Warning(vec![Warning {
+            code: "370".to_string(),
+            agent: WarningAgent::HostPort(Host::IpV6("::1".parse().unwrap()), Some(5060)),
+            text: "Insufficient bandwidth".to_string(),
+        }]));
+        assert_eq!(header.to_string(), "Warning: 370 [::1]:5060 \"Insufficient bandwidth\"");
+    }
+}
@@ -1,38 +1,409 @@
 mod common;
 mod headers;
+mod multipart;
 mod request;
 mod response;
 mod tokens;
 
-use nom::branch::alt;
+use crate::prelude::*;
+use core::{ convert::TryFrom, ops::Range, };
+
+use nom::{
+    branch::alt,
+    bytes::complete::take_until,
+    combinator::all_consuming,
+    multi::many0,
+    sequence::{ preceded, terminated },
+};
 
 use crate::{
-    message::Message,
-    parser::Result,
+    ParseOptions,
+    header::Header,
+    message::{ Message, ParseReport, },
+    sip::SipUri,
+    parser::{ Error, ErrorKind, Result },
 };
 
-pub use common::hostname;
+pub use common::{ host_port, hostname, };
+pub(crate) use tokens::{ is_header_char, is_param_char, is_password_char, is_user_char, };
+
+pub use multipart::multipart_body;
+pub use request::{ request, request_best_effort, request_headers, request_ref, request_with_options, };
+pub use response::{ response, response_with_options, };
+
+pub fn sip_uri(input: &[u8]) -> Result<'_, &[u8], SipUri> {
+    all_consuming(alt((
+        common::sip_uri,
+        common::sips_uri,
+    )))(input)
+}
+
+/// Maximum number of URIs embedded in one another accepted by `embedded_uri`
+const MAX_URI_NESTING: usize = 8;
+
+/// Decodes the `%HH` escapes of `input`. Malformed escapes are kept as they are.
+fn unescape(input: &[u8]) -> Vec<u8> {
+    let hex = |c: u8| (c as char).to_digit(16);
+
+    let mut unescaped = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let escaped = match input.get(i..i + 3) {
+            Some([b'%', high, low]) => hex(*high).zip(hex(*low)),
+            _ => None,
+        };
+
+        match escaped {
+            Some((high, low)) => {
+                unescaped.push((high * 16 + low) as u8);
+                i += 3;
+            },
+            None => {
+                unescaped.push(input[i]);
+                i += 1;
+            },
+        }
+    }
+
+    unescaped
+}
+
+/// Parses the SIP or SIPS URI embedded in the (unescaped) value of a URI header, if any
+///
+/// The URI may be enclosed in angle brackets. The headers of the embedded URI are checked as
+/// well, so that URIs nested more than `MAX_URI_NESTING` levels deep are rejected with
+/// `ErrorKind::NestingTooDeep`.
+pub fn embedded_uri(value: &str) -> core::result::Result<Option<SipUri>, Error<'static, &'static [u8]>> {
+    embedded_uri_at_depth(value.as_bytes(), 0)
+}
 
-pub use request::request;
-pub use response::response;
+fn embedded_uri_at_depth(value: &[u8], depth: usize) -> core::result::Result<Option<SipUri>, Error<'static, &'static [u8]>> {
+    if depth >= MAX_URI_NESTING {
+        return Err(Error::new(ErrorKind::NestingTooDeep));
+    }
+
+    let value = match value {
+        [b'<', uri @ .., b'>'] => uri,
+        uri => uri,
+    };
+
+    let is_sip = |scheme: &[u8]| value.len() >= scheme.len()
+        && value[..scheme.len()].eq_ignore_ascii_case(scheme);
+    if !is_sip(b"sip:") && !is_sip(b"sips:") {
+        return Ok(None);
+    }
 
-pub fn message_request(input: &[u8]) -> Result<&[u8], Message> {
-    let (input, req) = request(input)?;
+    let uri = match sip_uri(value) {
+        Ok((_, uri)) => uri,
+        Err(_) => return Err(Error::new(ErrorKind::InvalidEmbeddedUri)),
+    };
+
+    for header in uri.headers.iter() {
+        embedded_uri_at_depth(header.value.as_bytes(), depth + 1)?;
+    }
+
+    Ok(Some(uri))
+}
+
+pub fn message_request(input: &[u8], options: ParseOptions) -> Result<&[u8], Message> {
+    let (input, req) = request_with_options(input, options)?;
 
     Ok((input, Message::Request(req)))
 }
 
-pub fn message_response(input: &[u8]) -> Result<&[u8], Message> {
-    let (input, resp) = response(input)?;
+pub fn message_response(input: &[u8], options: ParseOptions) -> Result<&[u8], Message> {
+    let (input, resp) = response_with_options(input, options)?;
 
     Ok((input, Message::Response(resp)))
 }
 
+/// Parses a request or a response
 pub fn message(input: &[u8]) -> Result<&[u8], Message> {
-    alt((
-        message_request,
-        message_response,
-    ))(input)
+    message_with_options(input, ParseOptions::default())
+}
+
+/// Parses a request or a response, according to `options`
+///
+/// The input must hold the whole message, see `whole`.
+pub fn message_with_options(input: &[u8], options: ParseOptions) -> Result<'_, &[u8], Message> {
+    whole(input, |input| message_unframed(input, options), message_content_length)
+}
+
+/// Whether the message in `input` is a response, its start line being a Status-Line
+///
+/// A Status-Line starts with the SIP version, whose name is case-insensitive like any other token,
+/// while a method can't contain a slash.
+fn is_response(input: &[u8]) -> bool {
+    matches!(input.get(..4), Some(start) if start.eq_ignore_ascii_case(b"SIP/"))
+}
+
+/// Parses a request or a response, whether it is whole or not
+///
+/// Only a Status-Line can start with the SIP version, so the parser is selected upfront rather
+/// than by trial, and the error returned is the one of the parser that applied.
+fn message_unframed(input: &[u8], options: ParseOptions) -> Result<'_, &[u8], Message> {
+    if is_response(input) {
+        message_response(input, options)
+    } else {
+        message_request(input, options)
+    }
+}
+
+fn message_content_length(message: &Message) -> core::result::Result<usize, nom::Err<Error<'static, &'static [u8]>>> {
+    match message {
+        Message::Request(req) => content_length(req.headers.iter().map(|header| &header.header)),
+        Message::Response(resp) => content_length(resp.headers.iter()),
+    }
+}
+
+/// Returns the length of the start line and headers of a message, up to and including the empty
+/// line ending them, or `nom::Err::Incomplete` if that line hasn't been received yet
+fn head_length(input: &[u8]) -> Result<'_, &[u8], usize> {
+    match input.windows(4).position(|window| window == b"\r\n\r\n") {
+        Some(position) => Ok((input, position + 4)),
+        None => Err(nom::Err::Incomplete(nom::Needed::Unknown)),
+    }
+}
+
+/// Returns the value of the Content-Length header, or 0 if there is none
+pub fn content_length<'a>(mut headers: impl Iterator<Item = &'a Header>) -> core::result::Result<usize, nom::Err<Error<'static, &'static [u8]>>> {
+    let content_length = headers
+        .find_map(|header| match header {
+            Header::ContentLength(length) => Some(*length),
+            _ => None,
+        })
+        .unwrap_or(0);
+
+    usize::try_from(content_length).map_err(|_| nom::Err::Failure(
+        Error::new(ErrorKind::InvalidContentLength)
+    ))
+}
+
+/// Applies `parser` to a message that must be whole, such as the content of a datagram
+///
+/// A message is truncated when the empty line ending its headers is missing, or when its body is
+/// shorter than the Content-Length header announces (`length` reads it off the parsed message).
+/// `nom::Err::Incomplete` is returned in both cases, with the number of missing body bytes in
+/// the latter, rather than the error of a parser running out of input.
+pub fn whole<'a, O>(
+    input: &'a [u8],
+    parser: impl FnOnce(&'a [u8]) -> Result<'a, &'a [u8], O>,
+    length: impl FnOnce(&O) -> core::result::Result<usize, nom::Err<Error<'static, &'static [u8]>>>,
+) -> Result<'a, &'a [u8], O> {
+    let (_, head) = head_length(input)?;
+    let (rest, output) = parser(input)?;
+
+    let missing = length(&output)?.saturating_sub(input.len() - head);
+    if missing > 0 {
+        return Err(nom::Err::Incomplete(nom::Needed::new(missing)));
+    }
+
+    Ok((rest, output))
+}
+
+/// Parses the first message of a stream (e.g. a TCP connection)
+///
+/// On stream transports, the body of a message ends where its Content-Length says it does, and
+/// the next message starts right after. A missing Content-Length is taken to mean there is no
+/// body. `nom::Err::Incomplete` is returned when the input doesn't hold a whole message yet.
+///
+/// CRLFs preceding the message, such as keepalives (RFC5626 section 3.5.1), are skipped, as
+/// RFC3261 section 7.5 recommends.
+pub fn message_stream(input: &[u8]) -> Result<'_, &[u8], Message> {
+    let (input, _) = many0(tokens::newline)(input)?;
+    let (_, head) = head_length(input)?;
+    let head = &input[..head];
+
+    let (_, mut message) = message_unframed(head, ParseOptions::default())?;
+
+    let end = head.len() + message_content_length(&message)?;
+    if input.len() < end {
+        return Err(nom::Err::Incomplete(nom::Needed::new(end - input.len())));
+    }
+
+    let body = match &input[head.len()..end] {
+        b"" => None,
+        body => Some(body.to_vec()),
+    };
+
+    match &mut message {
+        Message::Request(req) => req.body = body,
+        Message::Response(resp) => {
+            resp.content = input[..end].to_vec();
+            resp.body = body;
+        },
+    }
+
+    Ok((&input[end..], message))
+}
+
+/// Returns the kind of a parsing error, whatever its severity
+fn error_kind<'a>(err: nom::Err<Error<'a, &'a [u8]>>) -> ErrorKind<'a, &'a [u8]> {
+    match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => err.kind,
+        nom::Err::Incomplete(needed) => Error::<&[u8]>::from(needed).kind,
+    }
+}
+
+/// Parses the start line and every header of a message independently, collecting all failures
+///
+/// When the start line or a header can't be parsed, its span in `input` is recorded along with
+/// the error, and parsing resumes with the next line. The body isn't examined, and neither is
+/// the presence of mandatory headers.
+pub fn message_diagnostics(input: &[u8]) -> ParseReport<'_> {
+    let mut report = ParseReport {
+        headers: vec![],
+        errors: vec![],
+    };
+
+    let start_line = if is_response(input) {
+        response::status_line(input).map(|(rest, _)| rest)
+    } else {
+        request::request_line(input).map(|(rest, _)| rest)
+    };
+    let rest = match start_line {
+        Ok(rest) => rest,
+        Err(err) => match headers::raw_header_line(input) {
+            Ok((rest, raw)) => {
+                report.errors.push((0..raw.len(), error_kind(err)));
+                rest
+            },
+            Err(_) => {
+                report.errors.push((0..0, error_kind(err)));
+                return report;
+            },
+        },
+    };
+
+    for (span, header) in HeaderIter::starting_at(input, Some(rest)).spanned() {
+        match header {
+            Ok(header) => report.headers.push(header),
+            Err(err) => report.errors.push((span, err.kind)),
+        }
+    }
+
+    report
+}
+
+/// Returns what follows the next CRLF, if there is one
+fn skip_line(input: &[u8]) -> Option<&[u8]> {
+    preceded(take_until("\r\n"), tokens::newline)(input)
+        .ok()
+        .map(|(rest, _)| rest)
+}
+
+/// Yields the name of every header of a message, without parsing their values
+///
+/// The start line is skipped, as are the continuation lines of folded headers. Scanning stops at
+/// the empty line ending the headers, or at the first line that doesn't start with a header name
+/// followed by a colon. The names are yielded as written (e.g. compact forms aren't expanded), and
+/// borrow from `input`.
+pub fn scan_header_names(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = skip_line(input);
+
+    core::iter::from_fn(move || loop {
+        let line = rest?;
+
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            rest = skip_line(line);
+            continue;
+        }
+
+        match terminated(tokens::token, tokens::header_colon)(line) {
+            Ok((value, name)) => {
+                rest = skip_line(value);
+                return Some(name);
+            },
+            Err(_) => {
+                rest = None;
+                return None;
+            },
+        }
+    })
+}
+
+/// Lazily parses the headers of a message, one at a time
+///
+/// The start line is skipped without being validated, and iteration stops at the empty line
+/// ending the headers (or at the end of the input). Each header is parsed only when the next item
+/// is requested, so callers looking for a few headers can stop early without paying for the rest.
+///
+/// A header that can't be parsed is yielded as an error, and iteration resumes with the following
+/// header.
+pub struct HeaderIter<'a> {
+    input: &'a [u8],
+    rest: Option<&'a [u8]>,
+    body: Option<&'a [u8]>,
+}
+
+impl<'a> HeaderIter<'a> {
+    /// Creates an iterator over the headers of the message in `input`
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::starting_at(input, skip_line(input))
+    }
+
+    /// Creates an iterator over the headers of the message in `input`, starting at `rest`
+    ///
+    /// `rest` must be a subslice of `input`, usually what follows the start line.
+    fn starting_at(input: &'a [u8], rest: Option<&'a [u8]>) -> Self {
+        Self {
+            input,
+            rest,
+            body: None,
+        }
+    }
+
+    /// Yields the span of each header along with it
+    ///
+    /// The span is the position in the input of the header line (including its continuation
+    /// lines), without its terminating CRLF.
+    pub fn spanned(&mut self) -> impl Iterator<Item = (Range<usize>, <Self as Iterator>::Item)> + '_ {
+        core::iter::from_fn(move || self.next_spanned())
+    }
+
+    /// Returns what follows the empty line ending the headers, once iteration has reached it
+    pub(crate) fn body(&self) -> Option<&'a [u8]> {
+        self.body
+    }
+
+    fn next_spanned(&mut self) -> Option<(Range<usize>, <Self as Iterator>::Item)> {
+        let rest = self.rest?;
+        let start = self.input.len() - rest.len();
+
+        if rest.is_empty() {
+            self.rest = None;
+            return None;
+        }
+        if let Ok((body, _)) = tokens::newline(rest) {
+            self.rest = None;
+            self.body = Some(body);
+            return None;
+        }
+
+        let err = match headers::message_header(rest) {
+            Ok((after, header)) => {
+                self.rest = Some(after);
+                // without the CRLF `message_header` ends with
+                let end = self.input.len() - after.len() - 2;
+                return Some((start..end, Ok(header)));
+            },
+            Err(nom::Err::Failure(err)) | Err(nom::Err::Error(err)) => err.locate(self.input),
+            Err(nom::Err::Incomplete(needed)) => needed.into(),
+        };
+
+        let (after, raw) = headers::raw_header_line(rest).ok()?;
+        self.rest = Some(after);
+
+        Some((start..start + raw.len(), Err(err)))
+    }
+}
+
+impl<'a> Iterator for HeaderIter<'a> {
+    type Item = core::result::Result<Header, Error<'a, &'a [u8]>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|(_, header)| header)
+    }
 }
 
 #[cfg(test)]
@@ -44,4 +415,139 @@ mod tests {
         let bytes = include_bytes!("../../../assets/invite.sip");
         assert_eq!(message(bytes).is_err(), false);
     }
+
+    #[test]
+    fn message_recognizes_responses_whatever_the_case_of_the_version() {
+        let bytes = b"sip/2.0 180 Ringing\r\nContent-Length: 0\r\n\r\n";
+
+        assert!(matches!(message(bytes), Ok((_, Message::Response(_)))));
+        assert!(message_diagnostics(bytes).is_valid());
+    }
+
+    #[test]
+    fn scan_header_names_yields_names_in_order() {
+        let bytes = b"INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+Subject: lunch\r\n \tat noon\r\n\
+i: a84b4c76e66710\r\n\
+Content-Length: 4\r\n\
+\r\n\
+Max-Forwards: 70\r\n";
+        let names: Vec<&[u8]> = scan_header_names(bytes).collect();
+
+        assert_eq!(names, vec![&b"Via"[..], b"Subject", b"i", b"Content-Length"]);
+    }
+
+    #[test]
+    fn scan_header_names_stops_at_malformed_line() {
+        let bytes = b"SIP/2.0 200 OK\r\nCSeq: 1 INVITE\r\nnot a header\r\nTo: <sip:bob@biloxi.com>\r\n\r\n";
+        let names: Vec<&[u8]> = scan_header_names(bytes).collect();
+
+        assert_eq!(names, vec![&b"CSeq"[..]]);
+        assert_eq!(scan_header_names(b"").count(), 0);
+    }
+
+    #[test]
+    fn header_iter_yields_headers_lazily() {
+        let bytes = b"INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Max-Forwards: 70\r\n\
+Call-ID: a84b4c76e66710\r\n\
+\r\n\
+CSeq: 1 INVITE\r\n";
+        let mut headers = HeaderIter::new(bytes);
+
+        assert_eq!(headers.next(), Some(Ok(Header::MaxForwards(70))));
+        assert_eq!(headers.next(), Some(Ok(Header::CallID("a84b4c76e66710".to_string()))));
+        assert_eq!(headers.next(), None);
+        assert_eq!(headers.next(), None);
+    }
+
+    #[test]
+    fn header_iter_resumes_after_a_malformed_header() {
+        let bytes = b"SIP/2.0 200 OK\r\nCSeq: abc\r\nMax-Forwards: 70\r\n\r\n";
+        let headers: Vec<_> = HeaderIter::new(bytes).collect();
+
+        assert_eq!(headers.len(), 2);
+        assert!(headers[0].as_ref().unwrap_err().offset.is_some());
+        assert_eq!(headers[1], Ok(Header::MaxForwards(70)));
+        assert_eq!(HeaderIter::new(b"").count(), 0);
+    }
+
+    #[test]
+    fn header_iter_spans_exclude_the_crlf() {
+        let bytes = b"SIP/2.0 200 OK\r\nCSeq: abc\r\nSubject: lunch\r\n at noon\r\n\r\nbody";
+        let mut headers = HeaderIter::new(bytes);
+        let spans: Vec<_> = headers.spanned().map(|(span, header)| (&bytes[span], header.is_ok())).collect();
+
+        assert_eq!(spans, vec![(&b"CSeq: abc"[..], false), (&b"Subject: lunch\r\n at noon"[..], true)]);
+        assert_eq!(headers.body(), Some(&b"body"[..]));
+    }
+
+    #[test]
+    fn message_stream_uses_content_length_to_frame_messages() {
+        let first = b"SIP/2.0 200 OK\r\nContent-Length: 4\r\n\r\nv=0\n";
+        let second = b"SIP/2.0 180 Ringing\r\n\r\n";
+        let stream = [&first[..], &second[..]].concat();
+
+        let (rest, first) = message_stream(&stream).unwrap();
+        match first {
+            Message::Response(resp) => assert_eq!(resp.body, Some(b"v=0\n".to_vec())),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(rest, second);
+    }
+
+    #[test]
+    fn message_stream_needs_the_whole_body() {
+        let partial = b"SIP/2.0 200 OK\r\nContent-Length: 4\r\n\r\nv=";
+
+        assert_eq!(message_stream(partial), Err(nom::Err::Incomplete(nom::Needed::new(2))));
+        assert_eq!(message_stream(b"SIP/2.0 200 OK\r\n"), Err(nom::Err::Incomplete(nom::Needed::Unknown)));
+    }
+
+    #[test]
+    fn message_stream_skips_keepalives() {
+        let message = b"SIP/2.0 180 Ringing\r\n\r\n";
+        let stream = [&b"\r\n\r\n\r\n"[..], &message[..], &b"\r\n\r\n"[..]].concat();
+
+        let (rest, first) = message_stream(&stream).unwrap();
+        assert!(matches!(first, Message::Response(_)));
+        assert_eq!(rest, b"\r\n\r\n");
+        assert_eq!(message_stream(rest), Err(nom::Err::Incomplete(nom::Needed::Unknown)));
+    }
+
+    /// Escapes every byte that isn't alphanumeric
+    fn escape(value: &str) -> String {
+        value.bytes()
+            .map(|c| match c {
+                c if c.is_ascii_alphanumeric() => (c as char).to_string(),
+                c => format!("%{:02X}", c),
+            })
+            .collect()
+    }
+
+    /// Builds a URI in which `depth` Refer-To URIs are embedded in one another
+    fn nested_uri(depth: usize) -> String {
+        (0..depth).fold("sip:carol@chicago.example.com".to_string(), |uri, _| {
+            format!("sip:bob@biloxi.example.com?Refer-To={}", escape(&format!("<{}>", uri)))
+        })
+    }
+
+    #[test]
+    fn embedded_uri_decodes_nested_uris() {
+        let uri = embedded_uri(&nested_uri(2)).unwrap().unwrap();
+        assert_eq!(uri.user, Some("bob".to_string()));
+
+        let inner = embedded_uri(&uri.headers[0].value).unwrap().unwrap();
+        let innermost = embedded_uri(&inner.headers[0].value).unwrap().unwrap();
+        assert_eq!(innermost.user, Some("carol".to_string()));
+
+        assert_eq!(embedded_uri("12345@atlanta.example.com"), Ok(None));
+    }
+
+    #[test]
+    fn embedded_uri_rejects_deeply_nested_uris() {
+        let err = embedded_uri(&nested_uri(20)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NestingTooDeep);
+    }
 }
@@ -0,0 +1,104 @@
+use crate::prelude::*;
+use crate::{
+    body::BodyPart,
+    parser::{
+        Error,
+        ErrorKind,
+        Result,
+        rfc3261::{
+            headers,
+            tokens,
+        },
+    },
+};
+
+use nom::{
+    sequence::terminated,
+    multi::many0,
+    bytes::complete::take_while,
+};
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn body_part(input: &[u8]) -> Result<'_, &[u8], BodyPart> {
+    let (body, headers) = terminated(
+        many0(headers::message_header),
+        tokens::newline,
+    )(input)?;
+
+    Ok((&body[body.len()..], BodyPart {
+        headers,
+        body: body.to_vec(),
+    }))
+}
+
+/// Parses a multipart body (RFC2046) delimited by `boundary`
+///
+/// The preamble and epilogue are discarded, the epilogue is returned as the remaining input.
+pub fn multipart_body<'a>(input: &'a [u8], boundary: &[u8]) -> Result<'a, &'a [u8], Vec<BodyPart>> {
+    let missing_delimiter = || nom::Err::Error(
+        Error::new(ErrorKind::InvalidMultipartBody)
+    );
+
+    let mut delimiter = b"\r\n--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    // the first delimiter doesn't need to be preceded by a CRLF when there is no preamble
+    let mut input = if input.starts_with(&delimiter[2..]) {
+        &input[delimiter.len() - 2..]
+    } else {
+        let start = find(input, &delimiter).ok_or_else(missing_delimiter)?;
+        &input[start + delimiter.len()..]
+    };
+
+    let mut parts = vec![];
+    loop {
+        if input.starts_with(b"--") {
+            return Ok((&input[2..], parts));
+        }
+
+        let (rest, _) = take_while(|c| c == b' ' || c == b'\t')(input)?;
+        let (rest, _) = tokens::newline(rest)?;
+
+        let end = find(rest, &delimiter).ok_or_else(missing_delimiter)?;
+        let (_, part) = body_part(&rest[..end])?;
+        parts.push(part);
+
+        input = &rest[end + delimiter.len()..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Header;
+
+    #[test]
+    fn multipart_body_can_parse_parts() {
+        let body = b"preamble\r\n\
+--boundary1\r\n\
+Content-Type: application/sdp\r\n\
+\r\n\
+v=0\r\n\
+--boundary1\r\n\
+\r\n\
+no headers\r\n\
+--boundary1--\r\n\
+epilogue";
+        let (rest, parts) = multipart_body(body, b"boundary1").unwrap();
+
+        assert_eq!(rest, b"\r\nepilogue");
+        assert_eq!(parts.len(), 2);
+        assert!(matches!(parts[0].headers[0], Header::ContentType(_)));
+        assert_eq!(parts[0].body, b"v=0");
+        assert!(parts[1].headers.is_empty());
+        assert_eq!(parts[1].body, b"no headers");
+    }
+
+    #[test]
+    fn multipart_body_requires_a_delimiter() {
+        assert!(multipart_body(b"v=0\r\n", b"boundary1").is_err());
+    }
+}
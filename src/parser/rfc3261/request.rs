@@ -1,5 +1,8 @@
+use crate::prelude::*;
 use crate::{
-    request::{ Request, RequestLine, },
+    ParseOptions,
+    header::{ Header, HeaderError, NamedHeader, },
+    request::{ PartialRequest, Request, RequestLine, RequestRef, },
     parser::{
         Error,
         Result,
@@ -7,69 +10,199 @@ use crate::{
             common,
             tokens,
             headers,
+            HeaderIter,
         },
     },
 };
 
 use nom::{
-    combinator::opt,
+    combinator::{ map, opt },
     sequence::{ tuple, preceded, terminated },
-    branch::alt,
     multi::many0,
     bytes::complete::tag,
 };
 
-fn request_uri(input: &[u8]) -> Result<&[u8], &[u8]> {
-    alt((
-        common::sip_uri,
-        common::sips_uri,
-        common::absolute_uri,
-    ))(input)
-}
-
-fn request_line(input: &[u8]) -> Result<&[u8], RequestLine> {
+pub(super) fn request_line(input: &[u8]) -> Result<&[u8], RequestLine> {
     let (input, (method, uri, version)) = terminated(
         tuple((
             common::method,
-            preceded(tag(" "), request_uri),
+            preceded(tag(" "), common::uri),
             preceded(tag(" "), common::sip_version),
         )),
         tokens::newline,
     )(input)?;
 
-    let uri = std::str::from_utf8(uri)
-        .map_err(|err| nom::Err::Failure(Error::from(err)))?;
-
     Ok((input, RequestLine {
         method,
-        uri: uri.to_string(),
+        uri,
         version,
     }))
 }
 
+/// Parses a header, along with its name as it appears in the input and whether it was downgraded
+/// to an extension header
+pub(super) fn named_header(input: &[u8], options: ParseOptions) -> Result<'_, &[u8], (String, Header, bool)> {
+    let (_, name) = tokens::token(input)?;
+    let (input, (header, downgraded)) = if options.lenient_headers {
+        headers::message_header_lenient(input)?
+    } else {
+        map(headers::message_header, |header| (header, false))(input)?
+    };
+
+    let name = core::str::from_utf8(name)
+        .map_err(|err| nom::Err::Failure(Error::from(err)))?;
+
+    Ok((input, (name.to_string(), header, downgraded)))
+}
+
+/// The headers of a message, as parsed by `message_headers`
+#[derive(Default)]
+pub(super) struct MessageHeaders {
+    /// the names the headers were received under
+    pub names: Vec<String>,
+    pub headers: Vec<Header>,
+    /// the names of the headers downgraded to extension headers
+    pub downgraded: Vec<String>,
+    /// the raw lines of the skipped headers
+    pub unparsed: Vec<Vec<u8>>,
+}
+
+/// Parses the headers of a message according to `options`, along with the empty line ending them
+pub(super) fn message_headers(mut input: &[u8], options: ParseOptions) -> Result<'_, &[u8], MessageHeaders> {
+    let mut parsed = MessageHeaders::default();
+
+    loop {
+        match named_header(input, options) {
+            Ok((rest, (name, header, downgraded))) => {
+                if downgraded {
+                    parsed.downgraded.push(name.clone());
+                }
+                parsed.names.push(name);
+                parsed.headers.push(header);
+                input = rest;
+            },
+            Err(nom::Err::Error(_)) | Err(nom::Err::Failure(_))
+                if options.skip_malformed_headers && tokens::newline(input).is_err() => {
+                let (rest, raw) = headers::raw_header_line(input)?;
+                parsed.unparsed.push(raw.to_vec());
+                input = rest;
+            },
+            Err(nom::Err::Error(_)) => {
+                let (rest, _) = tokens::newline(input)?;
+                return Ok((rest, parsed));
+            },
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Gives each header the name it was received under
+fn with_names(headers: Vec<NamedHeader>, names: Vec<String>) -> Vec<NamedHeader> {
+    headers.into_iter()
+        .zip(names)
+        .map(|(header, name)| NamedHeader::new(header.header, Some(name)))
+        .collect()
+}
+
 pub fn request(input: &[u8]) -> Result<&[u8], Request> {
+    request_with_options(input, ParseOptions::default())
+}
+
+pub fn request_with_options(input: &[u8], options: ParseOptions) -> Result<'_, &[u8], Request> {
     let (input, (request_line, headers, body)) = tuple((
             request_line,
-            many0(headers::message_header),
-            preceded(tokens::newline, opt(common::message_body)),
+            |input| message_headers(input, options),
+            opt(common::message_body),
         ))(input)?;
 
+    let MessageHeaders { names, headers, downgraded, unparsed } = headers;
+
     Request::new(request_line, headers, body)
-        .map(|request| (input, request))
+        .map(|request| (input, Request {
+            headers: with_names(request.headers, names),
+            downgraded_headers: downgraded,
+            unparsed,
+            ..request
+        }))
         .map_err(|err| nom::Err::Failure(err.into()))
 }
 
+pub fn request_ref(input: &[u8]) -> Result<'_, &[u8], RequestRef<'_>> {
+    let options = ParseOptions::default();
+    let (input, (request_line, headers, body)) = tuple((
+            request_line,
+            |input| message_headers(input, options),
+            opt(common::message_body_ref),
+        ))(input)?;
+
+    let MessageHeaders { names, headers, .. } = headers;
+
+    RequestRef::new(request_line, headers, body)
+        .map(|request| (input, RequestRef { headers: with_names(request.headers, names), ..request }))
+        .map_err(|err| nom::Err::Failure(err.into()))
+}
+
+/// Parses the Request-Line and headers of a request, stopping at the empty line before the body
+///
+/// The body is left untouched in the remaining input. Mandatory headers are not checked.
+pub fn request_headers(input: &[u8]) -> Result<'_, &[u8], (RequestLine, Vec<Header>)> {
+    tuple((
+        request_line,
+        terminated(many0(headers::message_header), tokens::newline),
+    ))(input)
+}
+
+/// Parses as much of a request as possible, without failing on malformed headers
+///
+/// Headers that can't be parsed are returned as a `HeaderError` holding the raw header line, and
+/// parsing resumes with the next header. A malformed Request-Line is skipped in the same way.
+pub fn request_best_effort(input: &[u8]) -> PartialRequest {
+    let (rest, request_line) = match request_line(input) {
+        Ok((rest, request_line)) => (rest, Some(request_line)),
+        Err(_) => match headers::raw_header_line(input) {
+            Ok((rest, _)) => (rest, None),
+            Err(_) => (input, None),
+        },
+    };
+
+    let mut iter = HeaderIter::starting_at(input, Some(rest));
+    let headers = iter.spanned()
+        .map(|(span, header)| header.map_err(|_| HeaderError { raw: input[span].to_vec() }))
+        .collect();
+    let body = iter.body()
+        .and_then(|body| common::message_body(body).ok())
+        .map(|(_, body)| body);
+
+    (request_line, headers, body)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sip::*;
+    use crate::header::URIParam;
 
     #[test]
     fn request_line_can_parse_full_request_line() {
         let rl = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n";
         let parsed = request_line(rl).unwrap().1;
         assert_eq!(parsed.method, Method::Invite);
-        assert_eq!(parsed.uri, "sip:bob@biloxi.example.com");
+        assert_eq!(parsed.uri.to_string(), "sip:bob@biloxi.example.com");
+        assert!(matches!(parsed.uri, Uri::Sip(SipUri { secure: false, .. })));
+
+        let rl = b"INVITE sips:bob@biloxi.example.com;transport=tcp SIP/2.0\r\n";
+        let parsed = request_line(rl).unwrap().1;
+        match parsed.uri {
+            Uri::Sip(uri) => {
+                assert!(uri.secure);
+                assert_eq!(uri.params, vec![URIParam::Transport(Transport::TCP)]);
+            },
+            _ => panic!(),
+        }
+
+        let rl = b"INVITE tel:+14155551212 SIP/2.0\r\n";
+        let parsed = request_line(rl).unwrap().1;
+        assert_eq!(parsed.uri, Uri::Absolute("tel:+14155551212".to_string()));
         assert_eq!(parsed.version, Version::Two);
     }
 }
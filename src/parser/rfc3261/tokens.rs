@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::parser::Result;
 
 use nom::{
@@ -67,13 +68,15 @@ pub fn newline(input: &[u8]) -> Result<&[u8], &[u8]> {
     tag(b"\r\n")(input)
 }
 
+/// Parses LWS: whitespace, possibly spanning a folded line
+///
+/// Whitespace followed by a CRLF that doesn't start a folded line is not part of the fold, so
+/// that the whitespace alone is still recognized (e.g. trailing whitespace of a header).
 pub fn linear_whitespace(input: &[u8]) -> Result<&[u8], &[u8]> {
-    let (input, ws) = recognize(
-        pair(
-            opt(pair(space0, newline)),
-            space1,
-        )
-    )(input)?;
+    let (input, ws) = alt((
+        recognize(tuple((space0, newline, space1))),
+        space1,
+    ))(input)?;
 
     Ok((input, ws))
 }
@@ -105,7 +108,7 @@ pub fn token(input: &[u8]) -> Result<&[u8], &[u8]> {
 pub fn token_str(input: &[u8]) -> Result<&[u8], String> {
     let (input, tokens) = token(input)?;
 
-    let tokens = std::str::from_utf8(tokens)
+    let tokens = core::str::from_utf8(tokens)
         .map(|s| s.to_string())
         .map_err(|err| nom::Err::Failure(err.into()))?;
 
@@ -374,7 +377,7 @@ fn comment_text(input: &[u8]) -> Result<&[u8], &[u8]> {
 pub fn comment(input: &[u8]) -> Result<&[u8], &[u8]> {
     let (input, comment) = preceded(
         left_parenthesis,
-        terminated(alt((comment_text, comment, quoted_pair)),
+        terminated(recognize(many0(alt((comment_text, comment, quoted_pair)))),
             right_parenthesis)
     )(input)?;
 
@@ -438,6 +441,10 @@ pub fn quoted_string(input: &[u8]) -> Result<&[u8], &[u8]> {
 
 const PASSWORD_CHARS: &'static [u8] = b"&=+$,";
 
+pub fn is_password_char(i: u8) -> bool {
+    is_unreserved(i) || PASSWORD_CHARS.contains(&i)
+}
+
 pub fn password(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(
         many0(alt((
@@ -450,6 +457,10 @@ pub fn password(input: &[u8]) -> Result<&[u8], &[u8]> {
 
 const USER_RESERVED_CHARS: &'static [u8] = b"&=+$,;?/";
 
+pub fn is_user_char(i: u8) -> bool {
+    is_unreserved(i) || USER_RESERVED_CHARS.contains(&i)
+}
+
 pub fn user(input: &[u8]) -> Result<&[u8], &[u8]> {
     recognize(
         many1(alt((
@@ -463,17 +474,42 @@ pub fn user(input: &[u8]) -> Result<&[u8], &[u8]> {
 const UNRESERVED_PARAM_CHARS: &'static [u8] = b"[]/:&+$";
 
 pub fn is_param_char(i: u8) -> bool {
-    // TODO: Handle escaped characters
     is_unreserved(i) || UNRESERVED_PARAM_CHARS.contains(&i)
 }
 
+pub fn pname(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    recognize(
+        many1(alt((
+            take_while1(is_param_char),
+            escaped,
+        )))
+    )(input)
+}
+
 const UNRESERVED_HEADER_CHARS: &'static [u8] = b"[]/?:+$";
 
 pub fn is_header_char(i: u8) -> bool {
-    // TODO: Handle escaped characters
     is_unreserved(i) || UNRESERVED_HEADER_CHARS.contains(&i)
 }
 
+pub fn hname(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    recognize(
+        many1(alt((
+            take_while1(is_header_char),
+            escaped,
+        )))
+    )(input)
+}
+
+pub fn hvalue(input: &[u8]) -> Result<'_, &[u8], &[u8]> {
+    recognize(
+        many0(alt((
+            take_while1(is_header_char),
+            escaped,
+        )))
+    )(input)
+}
+
 pub fn is_uric(i: u8) -> bool {
     // TODO: Handle escaped characters
     is_reserved(i) || is_unreserved(i)
@@ -0,0 +1,228 @@
+use crate::prelude::*;
+use crate::{
+    sdp::{
+        Attribute,
+        Connection,
+        MediaDescription,
+        Origin,
+        SessionDescription,
+        Timing,
+    },
+    parser::{
+        Error,
+        ErrorKind,
+        Result,
+    },
+};
+
+use nom::{
+    combinator::{ all_consuming, opt },
+    sequence::{ terminated, separated_pair },
+    branch::alt,
+    multi::many0,
+    bytes::complete::{ tag, take, take_till },
+};
+
+type Line<'a> = (u8, &'a [u8]);
+
+/// Result of interpreting the value of a single line
+type LineResult<'a, T> = core::result::Result<T, nom::Err<Error<'a, &'a [u8]>>>;
+
+fn line(input: &[u8]) -> Result<'_, &[u8], Line<'_>> {
+    let (input, (kind, value)) = terminated(
+        separated_pair(
+            take(1usize),
+            tag("="),
+            take_till(|c| c == b'\r' || c == b'\n'),
+        ),
+        opt(alt((tag("\r\n"), tag("\n")))),
+    )(input)?;
+
+    Ok((input, (kind[0], value)))
+}
+
+fn invalid(value: &[u8]) -> nom::Err<Error<'_, &[u8]>> {
+    nom::Err::Failure(Error::new(ErrorKind::InvalidSdp(value)))
+}
+
+/// Splits the value of a line into exactly `count` fields separated by spaces
+fn fields(value: &[u8], count: usize) -> LineResult<'_, Vec<String>> {
+    let fields = core::str::from_utf8(value)
+        .map_err(|err| nom::Err::Failure(err.into()))?
+        .split(' ')
+        .map(|field| field.to_string())
+        .collect::<Vec<_>>();
+
+    if fields.len() == count && fields.iter().all(|field| !field.is_empty()) {
+        Ok(fields)
+    } else {
+        Err(invalid(value))
+    }
+}
+
+fn number<'a, T: core::str::FromStr>(value: &'a [u8], field: &str) -> LineResult<'a, T> {
+    field.parse().map_err(|_| invalid(value))
+}
+
+fn origin(value: &[u8]) -> LineResult<'_, Origin> {
+    let mut fields = fields(value, 6)?.into_iter();
+    let mut next = || fields.next().unwrap();
+
+    Ok(Origin {
+        username: next(),
+        session_id: next(),
+        session_version: next(),
+        network_type: next(),
+        address_type: next(),
+        address: next(),
+    })
+}
+
+fn connection(value: &[u8]) -> LineResult<'_, Connection> {
+    let mut fields = fields(value, 3)?.into_iter();
+    let mut next = || fields.next().unwrap();
+
+    Ok(Connection {
+        network_type: next(),
+        address_type: next(),
+        address: next(),
+    })
+}
+
+fn timing(value: &[u8]) -> LineResult<'_, Timing> {
+    let fields = fields(value, 2)?;
+
+    Ok(Timing {
+        start: number(value, &fields[0])?,
+        stop: number(value, &fields[1])?,
+    })
+}
+
+fn attribute(value: &[u8]) -> LineResult<'_, Attribute> {
+    let attribute = core::str::from_utf8(value)
+        .map_err(|err| nom::Err::Failure(err.into()))?;
+
+    let (name, value) = match attribute.find(':') {
+        Some(colon) => (&attribute[..colon], Some(attribute[colon + 1..].to_string())),
+        None => (attribute, None),
+    };
+
+    Ok(Attribute {
+        name: name.to_string(),
+        value,
+    })
+}
+
+fn media(value: &[u8]) -> LineResult<'_, MediaDescription> {
+    let fields = core::str::from_utf8(value)
+        .map_err(|err| nom::Err::Failure(err.into()))?
+        .split(' ')
+        .collect::<Vec<_>>();
+
+    if fields.len() < 4 || fields.iter().any(|field| field.is_empty()) {
+        return Err(invalid(value));
+    }
+
+    let (port, port_count) = match fields[1].find('/') {
+        Some(slash) => (&fields[1][..slash], Some(number(value, &fields[1][slash + 1..])?)),
+        None => (fields[1], None),
+    };
+
+    Ok(MediaDescription {
+        media: fields[0].to_string(),
+        port: number(value, port)?,
+        port_count,
+        protocol: fields[2].to_string(),
+        formats: fields[3..].iter().map(|format| format.to_string()).collect(),
+        connection: None,
+        attributes: vec![],
+    })
+}
+
+pub fn session_description(input: &[u8]) -> Result<'_, &[u8], SessionDescription> {
+    let (rest, lines) = all_consuming(many0(line))(input)?;
+
+    let version = match lines.first() {
+        Some((b'v', value)) => core::str::from_utf8(value)
+            .ok()
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| invalid(value))?,
+        _ => return Err(invalid(input)),
+    };
+
+    let mut origin_line = None;
+    let mut session_name = None;
+    let mut connection_line = None;
+    let mut timings = vec![];
+    let mut attributes = vec![];
+    let mut medias: Vec<MediaDescription> = vec![];
+
+    for (kind, value) in lines.into_iter().skip(1) {
+        match (kind, medias.last_mut()) {
+            (b'm', _) => medias.push(media(value)?),
+            (b'c', Some(media)) => media.connection = Some(connection(value)?),
+            (b'a', Some(media)) => media.attributes.push(attribute(value)?),
+            (_, Some(_)) => {},
+            (b'o', None) => origin_line = Some(origin(value)?),
+            (b's', None) => session_name = Some(String::from_utf8_lossy(value).to_string()),
+            (b'c', None) => connection_line = Some(connection(value)?),
+            (b't', None) => timings.push(timing(value)?),
+            (b'a', None) => attributes.push(attribute(value)?),
+            (_, None) => {},
+        }
+    }
+
+    match (origin_line, session_name) {
+        (Some(origin), Some(session_name)) if !timings.is_empty() => Ok((rest, SessionDescription {
+            version,
+            origin,
+            session_name,
+            connection: connection_line,
+            timing: timings,
+            attributes,
+            media: medias,
+        })),
+        _ => Err(invalid(input)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_description_can_parse_an_offer() {
+        let sdp = b"v=0\r\n\
+o=alice 2890844526 2890844526 IN IP4 client.atlanta.example.com\r\n\
+s=-\r\n\
+c=IN IP4 192.0.2.101\r\n\
+t=0 0\r\n\
+m=audio 49172 RTP/AVP 0 8\r\n\
+a=rtpmap:0 PCMU/8000\r\n\
+a=sendrecv\r\n";
+        let sdp = session_description(sdp).unwrap().1;
+
+        assert_eq!(sdp.version, 0);
+        assert_eq!(sdp.origin.username, "alice");
+        assert_eq!(sdp.origin.address, "client.atlanta.example.com");
+        assert_eq!(sdp.session_name, "-");
+        assert_eq!(sdp.connection.unwrap().address, "192.0.2.101");
+        assert_eq!(sdp.timing, vec![Timing { start: 0, stop: 0 }]);
+
+        let audio = &sdp.media[0];
+        assert_eq!(audio.media, "audio");
+        assert_eq!(audio.port, 49172);
+        assert_eq!(audio.protocol, "RTP/AVP");
+        assert_eq!(audio.formats, vec!["0", "8"]);
+        assert_eq!(audio.attributes, vec![
+            Attribute { name: "rtpmap".to_string(), value: Some("0 PCMU/8000".to_string()) },
+            Attribute { name: "sendrecv".to_string(), value: None },
+        ]);
+    }
+
+    #[test]
+    fn session_description_requires_the_mandatory_lines() {
+        assert!(session_description(b"v=0\no=- 1 1 IN IP4 192.0.2.1\n").is_err());
+        assert!(session_description(b"o=- 1 1 IN IP4 192.0.2.1\ns=-\nt=0 0\n").is_err());
+    }
+}
@@ -1,10 +1,20 @@
+use crate::prelude::*;
+use core::fmt;
+
 use crate::{
-    sip::{ Method, Version, },
-    header::{ self, Header, },
+    encode::{ self, EncodeError, },
+    ParseOptions,
+    Response,
+    body::{ self, BodyPart },
+    sip::{ CSeq, Host, Method, SipUri, StatusCode, Uri, Version, },
+    header::{ self, Header, HeaderError, NamedHeader, Credentials, DigestResponseParam, URIParam, },
     parser::{ rfc3261, Error, ErrorKind },
 };
+#[cfg(feature = "sdp")]
+use crate::sdp::SessionDescription;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Invite {
     /// the parsed Request-Line
     pub request_line: RequestLine,
@@ -13,7 +23,7 @@ pub struct Invite {
     pub call_id: String,
 
     /// the command sequence of the INVITE
-    pub cseq: (i32, Method),
+    pub cseq: CSeq,
 
     /// the remote user sending the INVITE
     pub from: header::From,
@@ -24,7 +34,7 @@ pub struct Invite {
     /// local user the INVITE is for
     pub to: header::To,
 
-    /// the upstream UAs this request has passed through
+    /// the Vias of the topmost Via header, the last upstream UAs this request has passed through
     pub via: Vec<header::Via>,
 
     /// mandatory and optional headers extracted from the INVITE
@@ -38,6 +48,24 @@ pub struct Invite {
 pub enum InvalidInviteError {
     #[error("mandatory header missing: Contact")]
     MissingContactHeader,
+    #[error("not an INVITE request")]
+    NotAnInvite,
+}
+
+/// Error returned by `Invite::parse`, when the input either isn't a valid SIP request, or isn't a
+/// valid INVITE
+#[derive(PartialEq, Debug, thiserror::Error)]
+pub enum ParseInviteError<'a> {
+    #[error("{0}")]
+    Parse(Error<'a, &'a [u8]>),
+    #[error(transparent)]
+    Invalid(#[from] InvalidInviteError),
+}
+
+impl<'a> From<Error<'a, &'a [u8]>> for ParseInviteError<'a> {
+    fn from(err: Error<'a, &'a [u8]>) -> Self {
+        Self::Parse(err)
+    }
 }
 
 impl Invite {
@@ -49,13 +77,15 @@ impl Invite {
         let mut contact = None;
 
         for header in r.headers.iter() {
-            match header {
+            match &header.header {
                 Header::Contact(c) => contact = Some(c),
                 _ => {},
             };
         }
 
-        if contact.is_none() {
+        if r.request_line.method != Method::Invite {
+            Err(InvalidInviteError::NotAnInvite)
+        } else if contact.is_none() {
             Err(InvalidInviteError::MissingContactHeader)
         } else {
             Ok(Self {
@@ -66,11 +96,20 @@ impl Invite {
                 max_forwards: r.max_forwards,
                 to: r.to,
                 via: r.via,
-                headers: r.headers,
+                headers: r.headers.into_iter().map(|header| header.header).collect(),
                 body: r.body,
             })
         }
     }
+
+    /// Parses a byte-slice representation of a SIP request, and validates it as an INVITE
+    ///
+    /// This is equivalent to `Request::parse` followed by `Invite::from_request`.
+    pub fn parse(input: &[u8]) -> Result<Self, ParseInviteError> {
+        let request = Request::parse(input)?;
+
+        Ok(Self::from_request(request)?)
+    }
 }
 
 /// Representation of a SIP Request-Line
@@ -89,18 +128,38 @@ impl Invite {
 ///    |- method                         |- Protocol version
 /// ```
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestLine {
     /// the parsed Method
     pub method: Method,
 
     /// the URI describing the user or service being addressed
-    pub uri: String,
+    pub uri: Uri,
 
     /// the version of the SIP protocol this request adheres to. There is virtually only one version
     /// in use: 2.0.
     pub version: Version,
 }
 
+impl RequestLine {
+    /// Returns the scheme of the Request-URI (e.g. `sip`, `sips`, `tel`)
+    ///
+    /// The scheme of SIP and SIPS URIs is lowercase, other schemes are returned as written.
+    pub fn scheme(&self) -> &str {
+        match &self.uri {
+            Uri::Sip(uri) if uri.secure => "sips",
+            Uri::Sip(_) => "sip",
+            Uri::Absolute(uri) => uri.split(':').next().unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for RequestLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.method, self.uri, self.version)
+    }
+}
+
 /// Representation of a SIP Request
 ///
 /// A SIP request is composed of its Request-Line, a number of mandatory and optional headers, and
@@ -135,6 +194,7 @@ pub struct RequestLine {
 /// a=rtpmap:0 PCMU/8000                                                 |
 /// ```
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Request {
     /// the parsed Request-Line
     pub request_line: RequestLine,
@@ -143,7 +203,7 @@ pub struct Request {
     pub call_id: String,
 
     /// the command sequence of the request
-    pub cseq: (i32, Method),
+    pub cseq: CSeq,
 
     /// the remote user making the request
     pub from: header::From,
@@ -154,16 +214,104 @@ pub struct Request {
     /// local user the request is for
     pub to: header::To,
 
-    /// the upstream UAs this request has passed through
+    /// the Vias of the topmost Via header, the last upstream UAs this request has passed through
     pub via: Vec<header::Via>,
 
-    /// mandatory and optional headers extracted from the request
-    pub headers: Vec<Header>,
+    /// mandatory and optional headers extracted from the request, along with the names they
+    /// were received under
+    pub headers: Vec<NamedHeader>,
+
+    /// the names of the headers kept as extension headers because their value was malformed.
+    /// Always empty unless the request was parsed with `ParseOptions::lenient_headers`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub downgraded_headers: Vec<String>,
+
+    /// the raw lines of the headers that were skipped because they couldn't be parsed. Always
+    /// empty unless the request was parsed with `ParseOptions::skip_malformed_headers`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unparsed: Vec<Vec<u8>>,
 
     /// the optional body of the request. This is completely unparsed and unvalidated.
     pub body: Option<Vec<u8>>,
 }
 
+/// Branch parameters starting with this value were generated by an RFC3261 compliant element
+const MAGIC_COOKIE: &str = "z9hG4bK";
+
+/// Key identifying the server transaction a request belongs to (RFC3261 section 17.2.3)
+///
+/// ACK requests are matched to the INVITE transaction they acknowledge, so the method of an ACK
+/// is replaced by INVITE in the key.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransactionKey {
+    /// the key of requests whose branch parameter starts with the magic cookie
+    Rfc3261 {
+        branch: String,
+        sent_by: Host,
+        port: Option<u16>,
+        method: Method,
+    },
+
+    /// the key of requests sent by RFC2543 elements, whose branch parameter (if any) can't be
+    /// relied upon
+    ///
+    /// The To tag isn't part of the key: the ACK of a non-2xx response carries the tag of the
+    /// response, which the INVITE didn't have, and must still match the INVITE transaction.
+    Rfc2543 {
+        request_uri: String,
+        from_tag: Option<String>,
+        call_id: String,
+        cseq: CSeq,
+        via: header::Via,
+    },
+}
+
+/// Replaces ACK by INVITE, as an ACK belongs to the transaction of the INVITE
+fn transaction_method(method: &Method) -> Method {
+    match method {
+        Method::Ack => Method::Invite,
+        method => method.clone(),
+    }
+}
+
+/// Builds the key of a request whose top Via has a branch starting with the magic cookie
+fn rfc3261_transaction_key(via: &header::Via, method: Method) -> Option<TransactionKey> {
+    match via.branch() {
+        Some(branch) if branch.starts_with(MAGIC_COOKIE) => Some(TransactionKey::Rfc3261 {
+            branch: branch.to_string(),
+            sent_by: via.sent_by.clone(),
+            port: via.port,
+            method: transaction_method(&method),
+        }),
+        _ => None,
+    }
+}
+
+/// Builds the key of a request sent by an RFC2543 element
+fn rfc2543_transaction_key(
+    request_uri: &Uri,
+    from: &header::From,
+    call_id: &str,
+    cseq: &CSeq,
+    via: &header::Via,
+) -> TransactionKey {
+    TransactionKey::Rfc2543 {
+        request_uri: request_uri.to_string(),
+        from_tag: from.tag().map(|tag| tag.to_string()),
+        call_id: call_id.to_string(),
+        cseq: CSeq {
+            sequence: cseq.sequence,
+            method: transaction_method(&cseq.method),
+        },
+        via: via.clone(),
+    }
+}
+
+/// The result of parsing a request leniently: its Request-Line (if valid), every header (parsed
+/// or not), and its body
+pub type PartialRequest = (Option<RequestLine>, Vec<Result<Header, HeaderError>>, Option<Vec<u8>>);
+
 #[derive(PartialEq, Debug, Copy, Clone, thiserror::Error)]
 pub enum InvalidRequestError {
     #[error("mandatory header missing: Call-ID")]
@@ -178,10 +326,247 @@ pub enum InvalidRequestError {
     MissingToHeader,
     #[error("mandatory header missing: Via")]
     MissingViaHeader,
+    #[error("Contact: * is only allowed in REGISTER requests")]
+    StarContactOutsideRegister,
+    #[error("Contact: * must be the only Contact")]
+    StarContactNotAlone,
+    #[error("the CSeq method doesn't match the Request-Line method")]
+    CSeqMethodMismatch,
+}
+
+/// Checks the use of the `*` Contact, which removes every binding of a REGISTER request
+///
+/// RFC3261 (section 10.2.2) only allows it in REGISTER requests, as their only Contact.
+fn check_star_contact(method: &Method, headers: &[Header]) -> Option<InvalidRequestError> {
+    let contacts: Vec<_> = headers.iter()
+        .filter_map(|header| match header {
+            Header::Contact(contact) => Some(contact),
+            _ => None,
+        })
+        .collect();
+
+    if !contacts.contains(&&header::ContactValue::Any) {
+        None
+    } else if method != &Method::Register {
+        Some(InvalidRequestError::StarContactOutsideRegister)
+    } else if contacts.len() > 1 {
+        Some(InvalidRequestError::StarContactNotAlone)
+    } else {
+        None
+    }
+}
+
+/// Checks that the CSeq method is the method of the Request-Line (RFC3261 section 8.1.1.5)
+///
+/// This holds for ACK and CANCEL requests as well: their CSeq carries the sequence number of the
+/// INVITE they relate to, but their own method (sections 9.1 and 17.1.1.3).
+fn check_cseq_method(method: &Method, cseq: &CSeq) -> Option<InvalidRequestError> {
+    if method != &cseq.method {
+        Some(InvalidRequestError::CSeqMethodMismatch)
+    } else {
+        None
+    }
+}
+
+/// Checks performed when building a request with `Request::with_validation`
+///
+/// The default validation is the one of `Request::new` (and of parsing): every header RFC3261
+/// requires in a request (section 8.1.1) must be present, and the headers must be consistent with
+/// the method. `RequestValidation::none()` performs no check at all.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct RequestValidation {
+    /// whether a Call-ID header is required
+    pub call_id: bool,
+
+    /// whether a CSeq header is required
+    pub cseq: bool,
+
+    /// whether a From header is required
+    pub from: bool,
+
+    /// whether a Max-Forwards header is required
+    pub max_forwards: bool,
+
+    /// whether a To header is required
+    pub to: bool,
+
+    /// whether a Via header is required
+    pub via: bool,
+
+    /// whether `Contact: *` is only accepted alone in a REGISTER request, and the CSeq method must
+    /// match the method of the Request-Line (except in ACK and CANCEL requests)
+    pub consistency: bool,
+}
+
+impl Default for RequestValidation {
+    fn default() -> Self {
+        Self {
+            call_id: true,
+            cseq: true,
+            from: true,
+            max_forwards: true,
+            to: true,
+            via: true,
+            consistency: true,
+        }
+    }
+}
+
+impl RequestValidation {
+    /// A validation which doesn't check anything
+    pub fn none() -> Self {
+        Self {
+            call_id: false,
+            cseq: false,
+            from: false,
+            max_forwards: false,
+            to: false,
+            via: false,
+            consistency: false,
+        }
+    }
+}
+
+/// A request whose mandatory headers may be missing
+///
+/// This is what `Request::from_parts_unchecked` and `Request::with_validation` build. The fields
+/// of the headers `Request` requires are optional here: they are `None` (or empty, for `via`)
+/// when the header is missing, rather than filled in with made up values.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UncheckedRequest {
+    /// the parsed Request-Line
+    pub request_line: RequestLine,
+
+    /// the call ID of the request, if any
+    pub call_id: Option<String>,
+
+    /// the command sequence of the request, if any
+    pub cseq: Option<CSeq>,
+
+    /// the remote user making the request, if known
+    pub from: Option<header::From>,
+
+    /// the max forwards (ttl) of the request, if any
+    pub max_forwards: Option<i32>,
+
+    /// local user the request is for, if known
+    pub to: Option<header::To>,
+
+    /// the Vias of the topmost Via header, if any
+    pub via: Vec<header::Via>,
+
+    /// mandatory and optional headers extracted from the request
+    pub headers: Vec<Header>,
+
+    /// the optional body of the request. This is completely unparsed and unvalidated.
+    pub body: Option<Vec<u8>>,
+}
+
+impl UncheckedRequest {
+    /// Checks the request according to `validation`
+    pub fn validate(&self, validation: &RequestValidation) -> Result<(), InvalidRequestError> {
+        if validation.call_id && self.call_id.is_none() {
+            return Err(InvalidRequestError::MissingCallIDHeader);
+        } else if validation.cseq && self.cseq.is_none() {
+            return Err(InvalidRequestError::MissingCSeqHeader);
+        } else if validation.from && self.from.is_none() {
+            return Err(InvalidRequestError::MissingFromHeader);
+        } else if validation.max_forwards && self.max_forwards.is_none() {
+            return Err(InvalidRequestError::MissingMaxForwardsHeader);
+        } else if validation.to && self.to.is_none() {
+            return Err(InvalidRequestError::MissingToHeader);
+        } else if validation.via && self.via.is_empty() {
+            return Err(InvalidRequestError::MissingViaHeader);
+        }
+
+        if validation.consistency {
+            let method = &self.request_line.method;
+            let err = check_star_contact(method, &self.headers)
+                .or_else(|| self.cseq.as_ref().and_then(|cseq| check_cseq_method(method, cseq)));
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts the request to a `Request`, checking it with the default `RequestValidation`
+    pub fn into_request(self) -> Result<Request, InvalidRequestError> {
+        self.validate(&RequestValidation::default())?;
+
+        Ok(Request {
+            request_line: self.request_line,
+            call_id: self.call_id.ok_or(InvalidRequestError::MissingCallIDHeader)?,
+            cseq: self.cseq.ok_or(InvalidRequestError::MissingCSeqHeader)?,
+            from: self.from.ok_or(InvalidRequestError::MissingFromHeader)?,
+            max_forwards: self.max_forwards.ok_or(InvalidRequestError::MissingMaxForwardsHeader)?,
+            to: self.to.ok_or(InvalidRequestError::MissingToHeader)?,
+            via: self.via,
+            headers: self.headers.into_iter().map(NamedHeader::from).collect(),
+            downgraded_headers: vec![],
+            unparsed: vec![],
+            body: self.body,
+        })
+    }
+
+    /// Returns the key of the server transaction this request belongs to
+    ///
+    /// See `Request::transaction_key`. Returns `None` when there is no Via header, or when an
+    /// RFC2543 key is needed and one of the headers it is made of is missing.
+    pub fn transaction_key(&self) -> Option<TransactionKey> {
+        let via = self.via.first()?;
+
+        rfc3261_transaction_key(via, self.request_line.method.clone())
+            .or_else(|| self.rfc2543_transaction_key())
+    }
+
+    /// Returns the key of the server transaction of a request sent by an RFC2543 element
+    ///
+    /// See `Request::transaction_key_2543`. Returns `None` as well when there is no Via header, or
+    /// when one of the headers the key is made of is missing.
+    pub fn transaction_key_2543(&self) -> Option<TransactionKey> {
+        let via = self.via.first()?;
+
+        match rfc3261_transaction_key(via, self.request_line.method.clone()) {
+            Some(_) => None,
+            None => self.rfc2543_transaction_key(),
+        }
+    }
+
+    fn rfc2543_transaction_key(&self) -> Option<TransactionKey> {
+        Some(rfc2543_transaction_key(
+            &self.request_line.uri,
+            self.from.as_ref()?,
+            self.call_id.as_ref()?,
+            self.cseq.as_ref()?,
+            self.via.first()?,
+        ))
+    }
+}
+
+/// Error returned by `Request::next_hop` when the next hop isn't a SIP or SIPS URI
+#[derive(PartialEq, Debug, Copy, Clone, thiserror::Error)]
+pub enum NextHopError {
+    #[error("the Request-URI is not a SIP or SIPS URI")]
+    InvalidRequestUri,
+    #[error("the topmost Route is not a SIP or SIPS URI")]
+    InvalidRoute,
 }
 
 impl Request {
+    /// Builds a request from its parts, checking them with the default `RequestValidation`
     pub fn new(request_line: RequestLine, headers: Vec<Header>, body: Option<Vec<u8>>) -> Result<Self, InvalidRequestError> {
+        Self::from_parts_unchecked(request_line, headers, body).into_request()
+    }
+
+    /// Builds a request from its parts, without checking them
+    ///
+    /// This is meant for elements that must handle partial requests anyway, such as stateless
+    /// proxies. The mandatory headers that are missing are left out of the `UncheckedRequest`.
+    pub fn from_parts_unchecked(request_line: RequestLine, headers: Vec<Header>, body: Option<Vec<u8>>) -> UncheckedRequest {
         let mut call_id = None;
         let mut cseq = None;
         let mut from = None;
@@ -192,45 +577,488 @@ impl Request {
         for header in headers.iter() {
             match header {
                 Header::CallID(id) => call_id = Some(id.clone()),
-                Header::CSeq(c, m) => cseq = Some((*c, m.clone())),
+                Header::CSeq(c) => cseq = Some(c.clone()),
                 Header::From(f) => from = Some(f.clone()),
                 Header::MaxForwards(mf) => max_forwards = Some(*mf),
                 Header::To(t) => to = Some(t.clone()),
-                Header::Via(v) => via = Some(v.clone()),
+                // the transaction is identified by the topmost Via, so later Via headers are left
+                // in `headers` only
+                Header::Via(v) if via.is_none() => via = Some(v.clone()),
                 _ => {},
             };
         }
 
-        if call_id.is_none() {
-            Err(InvalidRequestError::MissingCallIDHeader)
-        } else if cseq.is_none() {
-            Err(InvalidRequestError::MissingCSeqHeader)
-        } else if from.is_none() {
-            Err(InvalidRequestError::MissingFromHeader)
-        } else if max_forwards.is_none() {
-            Err(InvalidRequestError::MissingMaxForwardsHeader)
-        } else if to.is_none() {
-            Err(InvalidRequestError::MissingToHeader)
-        } else if via.is_none() {
-            Err(InvalidRequestError::MissingViaHeader)
-        } else {
-            Ok(Self {
-                request_line,
-                call_id: call_id.unwrap(),
-                cseq: cseq.unwrap(),
-                from: from.unwrap(),
-                max_forwards: max_forwards.unwrap(),
-                to: to.unwrap(),
-                via: via.unwrap(),
-                headers: headers,
-                body,
-            })
+        UncheckedRequest {
+            request_line,
+            call_id,
+            cseq,
+            from,
+            max_forwards,
+            to,
+            via: via.unwrap_or_default(),
+            headers,
+            body,
         }
     }
 
+    /// Builds a request from its parts, checking them according to `validation`
+    ///
+    /// Use `UncheckedRequest::into_request` to get a `Request` once every mandatory header is known
+    /// to be present.
+    pub fn with_validation(
+        request_line: RequestLine,
+        headers: Vec<Header>,
+        body: Option<Vec<u8>>,
+        validation: RequestValidation,
+    ) -> Result<UncheckedRequest, InvalidRequestError> {
+        let request = Self::from_parts_unchecked(request_line, headers, body);
+        request.validate(&validation)?;
+
+        Ok(request)
+    }
+
     pub fn method(&self) -> &Method {
         &self.request_line.method
     }
+
+    /// Returns the first header called `name`
+    ///
+    /// Names are compared case-insensitively, and compact forms are accepted (`v` finds the Via
+    /// header).
+    pub fn header(&self, name: &str) -> Option<&Header> {
+        self.headers.iter()
+            .find(|header| header.is_named(name))
+            .map(|header| &header.header)
+    }
+
+    /// Returns every header called `name`, in order
+    ///
+    /// Names are compared the same way as by `Request::header`.
+    pub fn headers_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Header> {
+        self.headers.iter()
+            .filter(move |header| header.is_named(name))
+            .map(|header| &header.header)
+    }
+
+    /// Whether the request looks like an OPTIONS keep-alive ("ping")
+    ///
+    /// This is a heuristic: an OPTIONS request without a body, addressed to a SIP or SIPS URI
+    /// without a user part (e.g. `sip:proxy.example.com`), is taken to be a keep-alive or a
+    /// capability query aimed at the server itself, rather than at a user.
+    pub fn is_keepalive_options(&self) -> bool {
+        let addresses_server = match &self.request_line.uri {
+            Uri::Sip(uri) => uri.user.is_none(),
+            Uri::Absolute(_) => false,
+        };
+
+        self.request_line.method == Method::Options && self.body.is_none() && addresses_server
+    }
+
+    /// Returns the key of the server transaction this request belongs to
+    ///
+    /// The key is made of the branch, sent-by and method when the branch of the top Via starts
+    /// with the RFC3261 magic cookie, and falls back to `transaction_key_2543` otherwise.
+    pub fn transaction_key(&self) -> TransactionKey {
+        rfc3261_transaction_key(&self.via[0], self.method().clone())
+            .unwrap_or_else(|| self.rfc2543_transaction_key())
+    }
+
+    /// Returns the key of the server transaction of a request sent by an RFC2543 element
+    ///
+    /// The key is made of the Request-URI, the From tag, the Call-ID, the CSeq and the top Via.
+    /// Returns `None` if the branch of the top Via starts with the RFC3261 magic cookie, in which
+    /// case `transaction_key` must be used instead.
+    pub fn transaction_key_2543(&self) -> Option<TransactionKey> {
+        match rfc3261_transaction_key(&self.via[0], self.method().clone()) {
+            Some(_) => None,
+            None => Some(self.rfc2543_transaction_key()),
+        }
+    }
+
+    fn rfc2543_transaction_key(&self) -> TransactionKey {
+        rfc2543_transaction_key(&self.request_line.uri, &self.from, &self.call_id, &self.cseq, &self.via[0])
+    }
+
+    /// Whether the sender supports changes of the From and To URIs within a dialog (RFC4916)
+    ///
+    /// This is advertised by the `from-change` option tag of the Supported header, and is a
+    /// prerequisite to updating the connected identity through an UPDATE or re-INVITE request.
+    pub fn supports_from_change(&self) -> bool {
+        self.headers.iter().any(|header| match &header.header {
+            Header::Supported(tags) => tags.iter().any(|tag| tag == "from-change"),
+            _ => false,
+        })
+    }
+
+    /// Whether the request requires quality of service preconditions to be met (RFC3312)
+    ///
+    /// This is signalled by the `precondition` option tag of the Require header. The preconditions
+    /// themselves are negotiated in the SDP body.
+    pub fn requires_precondition(&self) -> bool {
+        self.headers.iter().any(|header| match &header.header {
+            Header::Require(tags) => tags.iter().any(|tag| tag == "precondition"),
+            _ => false,
+        })
+    }
+
+    /// Returns the Content-Type of the request, if any
+    pub fn content_type(&self) -> Option<&header::Media> {
+        self.headers.iter().find_map(|header| match &header.header {
+            Header::ContentType(media) => Some(media),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the first Contact header, if any
+    pub fn contacts(&self) -> Option<&header::ContactValue> {
+        self.headers.iter().find_map(|header| match &header.header {
+            Header::Contact(contact) => Some(contact),
+            _ => None,
+        })
+    }
+
+    /// Returns every Contact of the request, across all Contact headers, in order
+    ///
+    /// The `*` Contact of a REGISTER request doesn't designate any address, and is skipped.
+    pub fn all_contacts(&self) -> impl Iterator<Item = &header::Contact> + '_ {
+        self.headers.iter()
+            .filter_map(|header| match &header.header {
+                Header::Contact(header::ContactValue::Specific(contacts)) => Some(contacts),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Returns every Via of the request, across all Via headers, in order
+    ///
+    /// The first one is the topmost Via, added by the element the request was received from.
+    pub fn vias(&self) -> impl Iterator<Item = &header::Via> + '_ {
+        self.headers.iter()
+            .filter_map(|header| match &header.header {
+                Header::Via(vias) => Some(vias),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Whether the request already went through the element with the given sent-by and branch
+    ///
+    /// Proxies use this to detect loops (RFC3261 section 16.3): a Via matching their own means
+    /// the request came back to them. `sent_by` is the host, optionally followed by `:port`, as
+    /// written in the Via. Hosts are compared as `Host`s are, a missing port stands for the
+    /// default port of the Via transport, and branches are compared exactly. A `sent_by` that
+    /// isn't a valid host and port matches no Via.
+    pub fn has_via_from(&self, sent_by: &str, branch: &str) -> bool {
+        let (host, port) = match nom::combinator::all_consuming(rfc3261::host_port)(sent_by.as_bytes()) {
+            Ok((_, sent_by)) => sent_by,
+            Err(_) => return false,
+        };
+
+        self.vias().any(|via| {
+            let default_port = via.transport().default_port();
+
+            via.sent_by == host
+                && via.port.unwrap_or(default_port) == port.unwrap_or(default_port)
+                && via.branch() == Some(branch)
+        })
+    }
+
+    /// Returns every Route of the request, across all Route headers, in order
+    pub fn routes(&self) -> impl Iterator<Item = &header::Route> + '_ {
+        self.headers.iter()
+            .filter_map(|header| match &header.header {
+                Header::Route(routes) => Some(routes),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Returns every Record-Route of the request, across all Record-Route headers, in order
+    pub fn record_routes(&self) -> impl Iterator<Item = &header::RecordRoute> + '_ {
+        self.headers.iter()
+            .filter_map(|header| match &header.header {
+                Header::RecordRoute(routes) => Some(routes),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Returns the option tags of the Supported headers
+    ///
+    /// A list spread over several Supported headers is equivalent to a single header carrying the
+    /// whole list, so the tags of every Supported header are returned, in order.
+    pub fn supported(&self) -> Vec<&str> {
+        self.headers.iter()
+            .filter_map(|header| match &header.header {
+                Header::Supported(tags) => Some(tags),
+                _ => None,
+            })
+            .flatten()
+            .map(|tag| tag.as_str())
+            .collect()
+    }
+
+    /// Returns the value of the first Expires header, if any
+    pub fn expires(&self) -> Option<u32> {
+        self.headers.iter().find_map(|header| match &header.header {
+            Header::Expires(expires) => Some(*expires),
+            _ => None,
+        })
+    }
+
+    /// Splits a multipart body into its parts
+    ///
+    /// No parts are returned if the request has no body, or if its Content-Type isn't multipart.
+    /// See `body::parse_multipart` for the errors.
+    pub fn body_parts(&self) -> Result<Vec<BodyPart>, Error<'_, &[u8]>> {
+        match (self.content_type(), &self.body) {
+            (Some(content_type), Some(body)) if content_type.r#type == header::MediaType::Multipart => {
+                body::parse_multipart(content_type, body)
+            },
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Returns the body part referenced by a `cid:` URI (RFC2392)
+    ///
+    /// This is how, for example, a Geolocation header points to the location object carried in
+    /// the body of the request. Fails if the body can't be split into parts, see `body_parts`.
+    pub fn body_part_by_cid(&self, cid: &str) -> Result<Option<BodyPart>, Error<'_, &[u8]>> {
+        Ok(self.body_parts()?
+            .into_iter()
+            .find(|part| part.matches_cid(cid)))
+    }
+
+    /// Parses the body of the request as an SDP session description
+    ///
+    /// Fails with `ErrorKind::UnexpectedContentType` unless the Content-Type of the request is
+    /// `application/sdp`.
+    #[cfg(feature = "sdp")]
+    pub fn parse_sdp(&self) -> Result<SessionDescription, Error<'_, &[u8]>> {
+        let is_sdp = match self.content_type() {
+            Some(header::Media {
+                r#type: header::MediaType::Application,
+                subtype: header::MediaSubType::IETFExtension(subtype),
+                ..
+            }) => subtype.eq_ignore_ascii_case("sdp"),
+            _ => false,
+        };
+
+        if !is_sdp {
+            return Err(Error::new(ErrorKind::UnexpectedContentType));
+        }
+
+        SessionDescription::parse(self.body.as_deref().unwrap_or_default())
+    }
+
+    /// Builds a response to the request, carrying the headers copied from it
+    ///
+    /// As required by RFC3261 (section 8.2.6), the Via headers are copied in order, along with the
+    /// From, To, Call-ID and CSeq headers. A Timestamp header is only copied into a 100 (Trying)
+    /// response (section 8.2.6.1). The response has no body.
+    ///
+    /// **Note**: No tag is added to the To header. A UAS must add one to its responses other than
+    /// 100 (Trying) when the request had none.
+    ///
+    /// Fails if the reason phrase or a copied header can't be encoded (see `Response::to_bytes`).
+    pub fn create_response(&self, status: StatusCode, reason: &str) -> Result<Response, EncodeError> {
+        let headers = self.headers.iter()
+            .map(|header| &header.header)
+            .filter(|header| match header {
+                Header::Via(_) | Header::From(_) | Header::To(_) | Header::CallID(_) | Header::CSeq(_) => true,
+                Header::Timestamp(_) => status == StatusCode::Trying,
+                _ => false,
+            })
+            .cloned()
+            .collect();
+
+        let mut response = Response {
+            version: self.request_line.version,
+            status_code: status,
+            reason_phrase: reason.to_string(),
+            content: vec![],
+            headers,
+            unparsed: vec![],
+            downgraded_headers: vec![],
+            body: None,
+        };
+        response.content = response.to_bytes()?;
+
+        Ok(response)
+    }
+
+    /// Serializes the request to its wire format
+    ///
+    /// The Content-Length header is computed from the body, whatever value it had when the request
+    /// was parsed. Headers are written under the names they were received with (see
+    /// `header_names`), so that their casing and compact forms are preserved when forwarding.
+    ///
+    /// Fails if a value contains a line break, see `encode::EncodeError::HeaderInjection`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let headers = self.headers.iter().map(|header| (header.written_name(), &header.header));
+
+        encode::message(&self.request_line, headers, self.body.as_deref())
+    }
+
+    /// Returns the scheme of the Request-URI
+    ///
+    /// Servers that don't support the scheme are expected to reply with a 416 (Unsupported URI
+    /// Scheme) response.
+    pub fn request_uri_scheme(&self) -> &str {
+        self.request_line.scheme()
+    }
+
+    /// Removes Route entries identical to the one immediately preceding them
+    ///
+    /// The route set is made of every Route header, in order, so duplicates spanning two Route
+    /// headers are removed as well. Route headers left empty are dropped. Consecutive identical
+    /// entries are usually the symptom of a routing loop.
+    pub fn dedup_adjacent_routes(&mut self) {
+        let mut previous: Option<header::Route> = None;
+
+        for header in self.headers.iter_mut() {
+            if let Header::Route(routes) = &mut header.header {
+                routes.retain(|route| {
+                    if previous.as_ref() == Some(route) {
+                        false
+                    } else {
+                        previous = Some(route.clone());
+                        true
+                    }
+                });
+            }
+        }
+
+        self.headers.retain(|header| match &header.header {
+            Header::Route(routes) => !routes.is_empty(),
+            _ => true,
+        });
+    }
+
+    /// Returns the URI of the element the request should be sent to
+    ///
+    /// Without Route headers, this is the Request-URI. When the topmost Route has the `lr`
+    /// parameter, the next hop is a loose router (RFC3261 section 16.12), and the request is sent
+    /// to it. Otherwise, the request was formatted for a strict router (RFC3261 section
+    /// 12.2.1.1): the Request-URI holds the address of that router, and the Route headers hold the
+    /// rest of the route set, so the request is sent to the Request-URI.
+    pub fn next_hop(&self) -> Result<SipUri, NextHopError> {
+        let request_uri = || match &self.request_line.uri {
+            Uri::Sip(uri) => Ok(uri.clone()),
+            Uri::Absolute(_) => Err(NextHopError::InvalidRequestUri),
+        };
+
+        let top_route = self.headers.iter()
+            .find_map(|header| match &header.header {
+                Header::Route(routes) => routes.first(),
+                _ => None,
+            });
+
+        let route = match top_route {
+            Some(route) => match &route.addr {
+                Uri::Sip(uri) => uri,
+                Uri::Absolute(_) => return Err(NextHopError::InvalidRoute),
+            },
+            None => return request_uri(),
+        };
+
+        if route.params.contains(&URIParam::LR) {
+            Ok(route.clone())
+        } else {
+            request_uri()
+        }
+    }
+
+    /// Returns a copy of the request safe to write to logs
+    ///
+    /// The credentials in Authorization and Proxy-Authorization headers are replaced by a
+    /// placeholder (the realm, URI, algorithm and QOP are kept, as they are useful when
+    /// troubleshooting), as is the value of any P-Asserted-Identity header. Everything else is
+    /// left untouched.
+    pub fn redacted_clone(&self) -> Self {
+        Self {
+            headers: self.headers.iter()
+                .map(|header| NamedHeader::new(redact_header(header), header.name.clone()))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether both requests carry the same information, regardless of the order of the headers
+    ///
+    /// The Request-Line, the body and the headers must be equal, but headers only need to appear
+    /// in the same order when their order is meaningful: Via, Route and Record-Route headers must
+    /// follow each other in the same order in both requests. The derived `PartialEq` compares the
+    /// headers in order.
+    pub fn semantically_eq(&self, other: &Request) -> bool {
+        if self.request_line != other.request_line || self.body != other.body {
+            return false;
+        }
+
+        let ordered = |request: &Request, name: &str| request.headers.iter()
+            .filter(|header| header.name() == name)
+            .cloned()
+            .collect::<Vec<_>>();
+        if ORDERED_HEADERS.iter().any(|name| ordered(self, name) != ordered(other, name)) {
+            return false;
+        }
+
+        let is_unordered = |header: &&NamedHeader| !ORDERED_HEADERS.contains(&header.name());
+        let mut unmatched = other.headers.iter().filter(is_unordered).collect::<Vec<_>>();
+        for header in self.headers.iter().filter(is_unordered) {
+            match unmatched.iter().position(|candidate| *candidate == header) {
+                Some(i) => { unmatched.swap_remove(i); },
+                None => return false,
+            }
+        }
+
+        unmatched.is_empty()
+    }
+}
+
+/// Headers whose relative order matters when comparing requests
+const ORDERED_HEADERS: &[&str] = &["Via", "Route", "Record-Route"];
+
+const REDACTED: &str = "<redacted>";
+
+fn redact_credentials(credentials: &Credentials) -> Credentials {
+    match credentials {
+        Credentials::DigestResponse(params) => Credentials::DigestResponse(
+            params.iter().map(|param| match param {
+                DigestResponseParam::Username(_) =>
+                    DigestResponseParam::Username(REDACTED.to_string()),
+                DigestResponseParam::Nonce(_) =>
+                    DigestResponseParam::Nonce(REDACTED.to_string()),
+                DigestResponseParam::Response(_) =>
+                    DigestResponseParam::Response(REDACTED.to_string()),
+                DigestResponseParam::CNonce(_) =>
+                    DigestResponseParam::CNonce(REDACTED.to_string()),
+                DigestResponseParam::Opaque(_) =>
+                    DigestResponseParam::Opaque(REDACTED.to_string()),
+                DigestResponseParam::Extension(name, _) =>
+                    DigestResponseParam::Extension(name.clone(), REDACTED.to_string()),
+                other => other.clone(),
+            }).collect()
+        ),
+        Credentials::OtherResponse(scheme, params) => Credentials::OtherResponse(
+            scheme.clone(),
+            params.iter().map(|(name, _)| (name.clone(), REDACTED.to_string())).collect()
+        ),
+    }
+}
+
+fn redact_header(header: &Header) -> Header {
+    match header {
+        Header::Authorization(credentials) =>
+            Header::Authorization(redact_credentials(credentials)),
+        Header::ProxyAuthorization(credentials) =>
+            Header::ProxyAuthorization(redact_credentials(credentials)),
+        Header::PAssertedIdentity(_) =>
+            Header::Extension(header.name().to_string(), REDACTED.to_string()),
+        Header::Extension(name, _) if name.eq_ignore_ascii_case("P-Asserted-Identity") =>
+            Header::Extension(name.clone(), REDACTED.to_string()),
+        other => other.clone(),
+    }
 }
 
 impl<'a> Request {
@@ -238,22 +1066,1111 @@ impl<'a> Request {
     ///
     /// **Note**: The error type of this method will probably change in the future.
     pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &[u8]>> {
-        match rfc3261::request(input) {
+        match rfc3261::whole(input, rfc3261::request, |req| rfc3261::content_length(req.headers.iter().map(|header| &header.header))) {
             Ok((_, req)) => Ok(req),
-            Err(nom::Err::Failure(err)) => Err(err),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
             Err(_) => Err(Error::new(ErrorKind::UnknownError)),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Attempts to parse a byte-slice representation of a SIP request, according to `options`
+    ///
+    /// See `ParseOptions` for the behaviours that can be changed. `parse` is equivalent to this
+    /// method with the default options.
+    pub fn parse_with_options(input: &'a [u8], options: ParseOptions) -> Result<Self, Error<'a, &'a [u8]>> {
+        match rfc3261::whole(input, |input| rfc3261::request_with_options(input, options), |req| rfc3261::content_length(req.headers.iter().map(|header| &header.header))) {
+            Ok((_, req)) => Ok(req),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
 
-    #[test]
-    fn request_parse_can_read_whole_message() {
-        let bytes = include_bytes!("../assets/invite.sip");
-        let req = Request::parse(bytes);
-        assert_eq!(req.is_err(), false);
+    /// Parses only the Request-Line and headers of a SIP request
+    ///
+    /// Parsing stops at the empty line separating the headers from the body, which is never read
+    /// or copied. Unlike `parse`, the presence of mandatory headers is not checked.
+    pub fn parse_headers(input: &'a [u8]) -> Result<(RequestLine, Vec<Header>), Error<'a, &'a [u8]>> {
+        match rfc3261::request_headers(input) {
+            Ok((_, parsed)) => Ok(parsed),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
+
+    /// Parses as much of a SIP request as possible, collecting errors instead of failing
+    ///
+    /// Unlike `parse`, a malformed header doesn't cause the whole request to be rejected: it is
+    /// returned as a `HeaderError` alongside the headers that could be parsed. Mandatory headers
+    /// are not checked either. This is meant for tooling (debuggers, tracers), applications
+    /// handling SIP traffic should use `parse`.
+    pub fn parse_best_effort(input: &[u8]) -> PartialRequest {
+        rfc3261::request_best_effort(input)
+    }
+}
+
+/// Representation of a SIP Request whose body is borrowed from the parsed buffer
+///
+/// `Request::parse` copies the body of the request. Applications that forward bodies without
+/// inspecting them (for example large SDP or multipart bodies) can use `RequestRef::parse` to
+/// avoid that copy. The headers are parsed exactly as they are for a `Request`.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RequestRef<'a> {
+    /// the parsed Request-Line
+    pub request_line: RequestLine,
+
+    /// the call ID of the request
+    pub call_id: String,
+
+    /// the command sequence of the request
+    pub cseq: CSeq,
+
+    /// the remote user making the request
+    pub from: header::From,
+
+    /// the max forwards (ttl) of the request
+    pub max_forwards: i32,
+
+    /// local user the request is for
+    pub to: header::To,
+
+    /// the Vias of the topmost Via header, the last upstream UAs this request has passed through
+    pub via: Vec<header::Via>,
+
+    /// mandatory and optional headers extracted from the request, along with the names they
+    /// were received under
+    pub headers: Vec<NamedHeader>,
+
+    /// the optional body of the request, as a slice of the parsed buffer. This is completely
+    /// unparsed and unvalidated.
+    pub body: Option<&'a [u8]>,
+}
+
+impl<'a> RequestRef<'a> {
+    pub fn new(request_line: RequestLine, headers: Vec<Header>, body: Option<&'a [u8]>) -> Result<Self, InvalidRequestError> {
+        let r = Request::new(request_line, headers, None)?;
+
+        Ok(Self {
+            request_line: r.request_line,
+            call_id: r.call_id,
+            cseq: r.cseq,
+            from: r.from,
+            max_forwards: r.max_forwards,
+            to: r.to,
+            via: r.via,
+            headers: r.headers,
+            body,
+        })
+    }
+
+    /// Attempts to parse a byte-slice representation of a SIP request, without copying its body
+    ///
+    /// **Note**: The error type of this method will probably change in the future.
+    pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &[u8]>> {
+        match rfc3261::whole(input, rfc3261::request_ref, |req| rfc3261::content_length(req.headers.iter().map(|header| &header.header))) {
+            Ok((_, req)) => Ok(req),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.request_line.method
+    }
+
+    /// Converts the request into a `Request`, copying its body
+    pub fn into_owned(self) -> Request {
+        Request {
+            request_line: self.request_line,
+            call_id: self.call_id,
+            cseq: self.cseq,
+            from: self.from,
+            max_forwards: self.max_forwards,
+            to: self.to,
+            via: self.via,
+            headers: self.headers,
+            downgraded_headers: vec![],
+            unparsed: vec![],
+            body: self.body.map(|body| body.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_parse_can_read_whole_message() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = Request::parse(bytes);
+        assert_eq!(req.is_err(), false);
+    }
+
+    #[test]
+    fn request_parse_headers_stops_before_the_body() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = Request::parse(bytes).unwrap();
+        let (request_line, headers) = Request::parse_headers(bytes).unwrap();
+        assert_eq!(request_line, req.request_line);
+        assert_eq!(headers.len(), req.headers.len());
+
+        let bytes = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+Call-ID: a84b4c76e66710\r\n\
+Content-Length: 13\r\n\
+\r\n\
+\x00\xff not SIP";
+        let (request_line, headers) = Request::parse_headers(bytes).unwrap();
+        assert_eq!(request_line.method, Method::Options);
+        assert_eq!(headers, vec![
+            Header::CallID("a84b4c76e66710".to_string()),
+            Header::ContentLength(13),
+        ]);
+
+        assert!(Request::parse_headers(b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\nCall-ID: x\r\n").is_err());
+    }
+
+    #[test]
+    fn invite_parse_validates_the_request() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let invite = Invite::parse(bytes).unwrap();
+        assert_eq!(invite.method(), &Method::Invite);
+        assert_eq!(invite.cseq, CSeq { sequence: 1, method: Method::Invite });
+
+        let bytes = b"BYE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>;tag=8321234356\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 2 BYE\r\n\
+\r\n";
+        assert_eq!(Invite::parse(bytes), Err(ParseInviteError::Invalid(InvalidInviteError::NotAnInvite)));
+        assert!(matches!(Invite::parse(b"INVITE garbage\r\n"), Err(ParseInviteError::Parse(_))));
+    }
+
+    #[cfg(feature = "sdp")]
+    #[test]
+    fn request_parse_sdp_reads_the_offer() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let sdp = Request::parse(bytes).unwrap().parse_sdp().unwrap();
+
+        assert_eq!(sdp.origin.username, "alice");
+        assert_eq!(sdp.media[0].port, 49172);
+        assert_eq!(sdp.media[0].attributes[0].name, "rtpmap");
+    }
+
+    #[cfg(feature = "sdp")]
+    #[test]
+    fn request_parse_sdp_checks_the_content_type() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let mut req = Request::parse(bytes).unwrap();
+        req.headers.retain(|header| !matches!(header.header, Header::ContentType(_)));
+
+        assert_eq!(req.parse_sdp().unwrap_err().kind, ErrorKind::UnexpectedContentType);
+    }
+
+    #[test]
+    fn request_transaction_key_uses_the_branch() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.transaction_key(), TransactionKey::Rfc3261 {
+            branch: "z9hG4bK74b43".to_string(),
+            sent_by: Host::Domain("client.atlanta.example.com".to_string()),
+            port: Some(5060),
+            method: Method::Invite,
+        });
+        assert_eq!(req.transaction_key_2543(), None);
+    }
+
+    #[test]
+    fn request_transaction_key_uses_the_topmost_via_header() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP ss1.example.com:5060;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com:5060;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 69\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+        let expected = TransactionKey::Rfc3261 {
+            branch: "z9hG4bK77ef4c2312983.1".to_string(),
+            sent_by: Host::Domain("ss1.example.com".to_string()),
+            port: Some(5060),
+            method: Method::Invite,
+        };
+
+        assert_eq!(req.transaction_key(), expected);
+        assert_eq!(req.vias().count(), 2);
+
+        let headers = req.headers.iter().map(|h| h.header.clone()).collect();
+        let unchecked = Request::from_parts_unchecked(req.request_line.clone(), headers, None);
+        assert_eq!(unchecked.transaction_key(), Some(expected));
+    }
+
+    #[test]
+    fn request_transaction_key_2543_without_magic_cookie() {
+        let invite = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+\r\n";
+        let req = Request::parse(invite).unwrap();
+        let key = req.transaction_key_2543().unwrap();
+
+        assert_eq!(req.transaction_key(), key);
+        match &key {
+            TransactionKey::Rfc2543 { from_tag, cseq, .. } => {
+                assert_eq!(from_tag.as_deref(), Some("9fxced76sl"));
+                assert_eq!(cseq, &CSeq { sequence: 1, method: Method::Invite });
+            },
+            other => panic!("unexpected key: {:?}", other),
+        }
+
+        let cancel = String::from_utf8_lossy(invite)
+            .replacen("INVITE", "CANCEL", 2);
+        let cancel = Request::parse(cancel.as_bytes()).unwrap();
+        assert_ne!(cancel.transaction_key_2543(), Some(key));
+    }
+
+    #[test]
+    fn request_transaction_key_2543_matches_an_ack_to_its_invite() {
+        let invite = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+\r\n";
+        // the ACK of a 486 response, carrying the To tag of the response
+        let ack = b"ACK sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>;tag=8321234356\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 ACK\r\n\
+\r\n";
+        let invite = Request::parse(invite).unwrap();
+        let ack = Request::parse(ack).unwrap();
+
+        assert!(invite.transaction_key_2543().is_some());
+        assert_eq!(ack.transaction_key_2543(), invite.transaction_key_2543());
+        assert_eq!(ack.transaction_key(), invite.transaction_key());
+    }
+
+    #[test]
+    fn request_update_carries_the_connected_identity() {
+        let bytes = b"UPDATE sip:alice@client.atlanta.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP biloxi.example.com:5060;branch=z9hG4bK74b44\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:bob@biloxi.example.com>;tag=8321234356\r\n\
+To: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 UPDATE\r\n\
+Supported: 100rel, from-change\r\n\
+P-Asserted-Identity: \"Carol\" <sip:carol@chicago.example.com>\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.method(), &Method::Extension("UPDATE".to_string()));
+        assert!(req.supports_from_change());
+
+        let identities = req.headers.iter().find_map(|header| match &header.header {
+            Header::PAssertedIdentity(identities) => Some(identities),
+            _ => None,
+        }).unwrap();
+        assert_eq!(identities[0].name.as_deref(), Some("Carol"));
+        assert_eq!(identities[0].addr.to_string(), "sip:carol@chicago.example.com");
+    }
+
+    #[test]
+    fn request_supports_from_change_requires_the_option_tag() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        assert!(!Request::parse(bytes).unwrap().supports_from_change());
+    }
+
+    #[test]
+    fn request_typed_accessors_read_optional_headers() {
+        let bytes = b"REGISTER sip:registrar.biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.example.com:5060;branch=z9hG4bKnashds7\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Bob <sip:bob@biloxi.example.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 REGISTER\r\n\
+Supported: path, outbound\r\n\
+Supported: gruu\r\n\
+Contact: *\r\n\
+Expires: 0\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.contacts(), Some(&header::ContactValue::Any));
+        assert_eq!(req.supported(), vec!["path", "outbound", "gruu"]);
+        assert_eq!(req.expires(), Some(0));
+        assert_eq!(req.content_type(), None);
+
+        let req = Request::parse(include_bytes!("../assets/invite.sip")).unwrap();
+        assert_eq!(req.expires(), None);
+        assert_eq!(req.content_type().map(|media| &media.r#type), Some(&header::MediaType::Application));
+    }
+
+    #[test]
+    fn request_new_only_accepts_a_lone_star_contact_in_register() {
+        let request = |method: &str, contacts: &str| format!("{} sip:registrar.biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.example.com:5060;branch=z9hG4bKnashds7\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Bob <sip:bob@biloxi.example.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 {}\r\n\
+{}\
+Content-Length: 0\r\n\
+\r\n", method, method, contacts);
+
+        assert!(Request::parse(request("REGISTER", "Contact: *\r\n").as_bytes()).is_ok());
+        assert!(Request::parse(request("REGISTER", "Contact: <sip:bob@192.0.2.4>\r\n").as_bytes()).is_ok());
+        assert_eq!(
+            Request::parse(request("OPTIONS", "Contact: *\r\n").as_bytes()).map_err(|err| err.kind),
+            Err(ErrorKind::InvalidRequest(InvalidRequestError::StarContactOutsideRegister)),
+        );
+        assert_eq!(
+            Request::parse(request("REGISTER", "Contact: *\r\nContact: <sip:bob@192.0.2.4>\r\n").as_bytes()).map_err(|err| err.kind),
+            Err(ErrorKind::InvalidRequest(InvalidRequestError::StarContactNotAlone)),
+        );
+    }
+
+    #[test]
+    fn request_new_checks_cseq_method() {
+        let request = |method: &str, cseq_method: &str| format!("{} sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 {}\r\n\
+Content-Length: 0\r\n\
+\r\n", method, cseq_method);
+
+        assert!(Request::parse(request("BYE", "BYE").as_bytes()).is_ok());
+        assert!(Request::parse(request("ACK", "ACK").as_bytes()).is_ok());
+        assert!(Request::parse(request("CANCEL", "CANCEL").as_bytes()).is_ok());
+        for (method, cseq_method) in &[("BYE", "INVITE"), ("ACK", "INVITE"), ("CANCEL", "INVITE")] {
+            assert_eq!(
+                Request::parse(request(method, cseq_method).as_bytes()).map_err(|err| err.kind),
+                Err(ErrorKind::InvalidRequest(InvalidRequestError::CSeqMethodMismatch)),
+            );
+        }
+    }
+
+    #[test]
+    fn request_with_validation_only_requires_the_selected_headers() {
+        let bytes = b"ACK sip:bob@192.0.2.4 SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds9\r\n\
+To: Bob <sip:bob@biloxi.example.com>;tag=a6c85cf\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 ACK\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let full = Request::parse_with_options(bytes, ParseOptions::default());
+        assert_eq!(full.map_err(|err| err.kind), Err(ErrorKind::InvalidRequest(InvalidRequestError::MissingMaxForwardsHeader)));
+
+        let (_, (request_line, headers)) = rfc3261::request_headers(bytes).unwrap();
+        let validation = RequestValidation { max_forwards: false, ..RequestValidation::default() };
+        assert_eq!(
+            Request::new(request_line.clone(), headers.clone(), None),
+            Err(InvalidRequestError::MissingMaxForwardsHeader),
+        );
+
+        let req = Request::with_validation(request_line.clone(), headers.clone(), None, validation).unwrap();
+        assert_eq!(req.max_forwards, None);
+        assert_eq!(req.call_id.as_deref(), Some("a84b4c76e66710"));
+        assert_eq!(req.clone().into_request(), Err(InvalidRequestError::MissingMaxForwardsHeader));
+
+        let validation = RequestValidation { via: false, ..validation };
+        let headers: Vec<_> = headers.into_iter().filter(|header| !header.is_named("Via")).collect();
+        assert!(Request::with_validation(request_line, headers, None, validation).is_ok());
+    }
+
+    #[test]
+    fn request_from_parts_unchecked_accepts_partial_requests() {
+        let (_, (request_line, _)) = rfc3261::request_headers(b"BYE sip:bob@192.0.2.4 SIP/2.0\r\n\r\n").unwrap();
+        let req = Request::from_parts_unchecked(request_line, vec![], None);
+
+        assert_eq!(req.call_id, None);
+        assert_eq!(req.cseq, None);
+        assert!(req.via.is_empty());
+        assert!(req.headers.is_empty());
+    }
+
+    #[test]
+    fn unchecked_request_transaction_keys_need_the_headers_they_are_made_of() {
+        let unchecked = |bytes: &[u8]| {
+            let (_, (request_line, headers)) = rfc3261::request_headers(bytes).unwrap();
+            Request::from_parts_unchecked(request_line, headers, None)
+        };
+
+        let req = unchecked(b"BYE sip:bob@192.0.2.4 SIP/2.0\r\n\r\n");
+        assert_eq!(req.transaction_key(), None);
+        assert_eq!(req.transaction_key_2543(), None);
+
+        let req = unchecked(b"BYE sip:bob@192.0.2.4 SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+\r\n");
+        assert_eq!(req.transaction_key(), Some(TransactionKey::Rfc3261 {
+            branch: "z9hG4bKnashds8".to_string(),
+            sent_by: Host::Domain("pc33.atlanta.example.com".to_string()),
+            port: None,
+            method: Method::Bye,
+        }));
+        assert_eq!(req.transaction_key_2543(), None);
+
+        // an RFC2543 key needs the From, Call-ID and CSeq headers as well
+        let req = unchecked(b"BYE sip:bob@192.0.2.4 SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=nashds8\r\n\
+\r\n");
+        assert_eq!(req.transaction_key(), None);
+        assert_eq!(req.transaction_key_2543(), None);
+    }
+
+    #[test]
+    fn request_parse_with_lenient_headers_downgrades_malformed_headers() {
+        let bytes = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Expires: 60abc\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+        assert!(Request::parse(bytes).is_err());
+        assert!(Request::parse_with_options(bytes, ParseOptions::default()).is_err());
+
+        let req = Request::parse_with_options(bytes, ParseOptions { lenient_headers: true, ..ParseOptions::default() }).unwrap();
+        assert_eq!(req.headers[6], Header::Extension("Expires".to_string(), "60abc".to_string()));
+        assert_eq!(req.downgraded_headers, vec!["Expires".to_string()]);
+        assert!(Request::parse(&req.to_bytes().unwrap()).is_err());
+    }
+
+    #[test]
+    fn request_parse_with_skip_malformed_headers_keeps_the_raw_lines() {
+        let bytes = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Expires: 60abc\r\n\
+not a header\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let options = ParseOptions { skip_malformed_headers: true, ..ParseOptions::default() };
+
+        let req = Request::parse_with_options(bytes, options).unwrap();
+        assert_eq!(req.unparsed, vec![b"Expires: 60abc".to_vec(), b"not a header".to_vec()]);
+        assert_eq!(req.headers.last(), Some(&Header::ContentLength(0).into()));
+
+        let req = Request::parse_with_options(bytes, ParseOptions { lenient_headers: true, ..options }).unwrap();
+        assert_eq!(req.downgraded_headers, vec!["Expires".to_string()]);
+        assert_eq!(req.unparsed, vec![b"not a header".to_vec()]);
+    }
+
+    #[test]
+    fn request_iterators_flatten_headers_in_order() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP p2.example.com;branch=z9hG4bK2, SIP/2.0/UDP p1.example.com;branch=z9hG4bK1\r\n\
+Max-Forwards: 70\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com;branch=z9hG4bK0\r\n\
+Route: <sip:r1.example.com;lr>\r\n\
+Record-Route: <sip:rr2.example.com;lr>, <sip:rr1.example.com;lr>\r\n\
+Route: <sip:r2.example.com;lr>, <sip:r3.example.com;lr>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+Contact: <sip:alice@192.0.2.1>, <sip:alice@192.0.2.2>\r\n\
+Contact: <sip:alice@192.0.2.3>\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        let vias: Vec<_> = req.vias().map(|via| via.branch().unwrap()).collect();
+        assert_eq!(vias, vec!["z9hG4bK2", "z9hG4bK1", "z9hG4bK0"]);
+
+        let routes: Vec<_> = req.routes().map(|route| route.addr.to_string()).collect();
+        assert_eq!(routes, vec!["sip:r1.example.com;lr", "sip:r2.example.com;lr", "sip:r3.example.com;lr"]);
+
+        let record_routes: Vec<_> = req.record_routes().map(|route| route.addr.to_string()).collect();
+        assert_eq!(record_routes, vec!["sip:rr2.example.com;lr", "sip:rr1.example.com;lr"]);
+
+        let contacts: Vec<_> = req.all_contacts().map(|contact| contact.addr.to_string()).collect();
+        assert_eq!(contacts, vec!["sip:alice@192.0.2.1", "sip:alice@192.0.2.2", "sip:alice@192.0.2.3"]);
+    }
+
+    #[test]
+    fn request_has_via_from_scans_every_via() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP p2.example.com;branch=z9hG4bK2, SIP/2.0/UDP p1.example.com:5070;branch=z9hG4bK1\r\n\
+Max-Forwards: 70\r\n\
+Via: SIP/2.0/UDP [2001:db8::1];branch=z9hG4bK0\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert!(req.has_via_from("p2.example.com", "z9hG4bK2"));
+        assert!(req.has_via_from("P1.Example.com:5070", "z9hG4bK1"));
+        assert!(req.has_via_from("[2001:db8::1]", "z9hG4bK0"));
+        assert!(!req.has_via_from("p1.example.com", "z9hG4bK1"));
+        assert!(!req.has_via_from("p2.example.com", "z9hG4bK1"));
+        assert!(!req.has_via_from("p2.example.com", "Z9HG4BK2"));
+
+        // ports default to the one of the transport, and hosts are compared as hosts
+        assert!(req.has_via_from("p2.example.com.:5060", "z9hG4bK2"));
+        assert!(!req.has_via_from("p2.example.com:5061", "z9hG4bK2"));
+        assert!(req.has_via_from("[2001:DB8:0::1]:5060", "z9hG4bK0"));
+        assert!(!req.has_via_from("not a host", "z9hG4bK2"));
+    }
+
+    #[test]
+    fn request_requires_precondition_reads_the_require_header() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+Require: 100rel, precondition\r\n\
+Contact: <sip:alice@client.atlanta.example.com;transport=tcp>\r\n\
+\r\n";
+        assert!(Request::parse(bytes).unwrap().requires_precondition());
+
+        let bytes = include_bytes!("../assets/invite.sip");
+        assert!(!Request::parse(bytes).unwrap().requires_precondition());
+    }
+
+    #[test]
+    fn request_parse_options_without_body() {
+        let bytes = b"OPTIONS sip:proxy.atlanta.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:proxy.atlanta.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 OPTIONS\r\n\
+Accept: application/sdp\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.method(), &Method::Options);
+        assert_eq!(req.body, None);
+        assert!(req.is_keepalive_options());
+    }
+
+    #[test]
+    fn request_body_part_by_cid_resolves_a_cid_reference() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+Geolocation: <cid:target123@atlanta.example.com>\r\n\
+Content-Type: multipart/mixed;boundary=boundary1\r\n\
+\r\n\
+--boundary1\r\n\
+Content-Type: application/sdp\r\n\
+\r\n\
+v=0\r\n\
+--boundary1\r\n\
+Content-Type: application/pidf+xml\r\n\
+Content-ID: <target123@atlanta.example.com>\r\n\
+\r\n\
+<presence/>\r\n\
+--boundary1--\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.body_parts().map(|parts| parts.len()), Ok(2));
+
+        let part = req.body_part_by_cid("cid:target123@atlanta.example.com").unwrap().unwrap();
+        assert_eq!(part.content_id(), Some("target123@atlanta.example.com"));
+        assert_eq!(part.body, b"<presence/>");
+        assert_eq!(req.body_part_by_cid("cid:unknown@atlanta.example.com"), Ok(None));
+    }
+
+    #[test]
+    fn request_body_parts_reports_malformed_multipart_bodies() {
+        let request = |content_type: &str, body: &str| format!("MESSAGE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 MESSAGE\r\n\
+Content-Type: {}\r\n\
+\r\n\
+{}", content_type, body);
+
+        let req = Request::parse(request("text/plain", "hello").as_bytes()).unwrap();
+        assert_eq!(req.body_parts(), Ok(vec![]));
+
+        let req = Request::parse(request("multipart/mixed", "--boundary1\r\n\r\nhello\r\n--boundary1--").as_bytes()).unwrap();
+        assert_eq!(req.body_parts().map_err(|err| err.kind), Err(ErrorKind::InvalidMultipartBody));
+
+        let req = Request::parse(request("multipart/mixed;boundary=boundary1", "hello").as_bytes()).unwrap();
+        assert!(req.body_parts().is_err());
+        assert!(req.body_part_by_cid("cid:target123@atlanta.example.com").is_err());
+    }
+
+    #[test]
+    fn request_create_response_copies_the_dialog_headers() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.example.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 69\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Timestamp: 54\r\n\
+Contact: <sip:alice@pc33.atlanta.example.com>\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+        let trying = req.create_response(StatusCode::Trying, "Trying").unwrap();
+
+        let vias: Vec<_> = trying.headers.iter().filter(|header| matches!(header, Header::Via(_))).collect();
+        assert_eq!(vias, req.headers.iter().map(|header| &header.header).filter(|header| matches!(header, Header::Via(_))).collect::<Vec<_>>());
+        assert!(trying.headers.contains(&Header::CSeq(req.cseq.clone())));
+        assert!(trying.headers.contains(&Header::CallID(req.call_id.clone())));
+        assert!(trying.headers.iter().any(|header| matches!(header, Header::Timestamp(_))));
+        assert!(!trying.headers.iter().any(|header| matches!(header, Header::MaxForwards(_) | Header::Contact(_))));
+
+        let reparsed = Response::parse(&trying.content).unwrap();
+        assert_eq!(reparsed.status_code, StatusCode::Trying);
+        assert!(reparsed.headers.contains(&Header::CSeq(req.cseq.clone())));
+
+        let busy = req.create_response(StatusCode::BusyHere, "Busy Here").unwrap();
+        assert!(!busy.headers.iter().any(|header| matches!(header, Header::Timestamp(_))));
+        assert_eq!(busy.status_code.as_u16(), 486);
+    }
+
+    #[test]
+    fn request_is_keepalive_options_requires_a_server_uri() {
+        let bytes = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 OPTIONS\r\n\
+\r\n";
+
+        assert!(!Request::parse(bytes).unwrap().is_keepalive_options());
+    }
+
+    #[test]
+    fn request_ref_borrows_the_body() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = RequestRef::parse(bytes).unwrap();
+
+        let body = req.body.unwrap();
+        assert!(bytes.as_ptr_range().contains(&body.as_ptr()));
+        assert!(body.starts_with(b"v=0\r\n"));
+        assert_eq!(req.into_owned(), Request::parse(bytes).unwrap());
+    }
+
+    #[test]
+    fn request_ref_parse_rejects_a_truncated_body() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let truncated = &bytes[..bytes.len() - 10];
+
+        assert_eq!(RequestRef::parse(truncated).map_err(|err| err.kind), Err(ErrorKind::Incomplete(Some(10))));
+
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\nCSeq: abc INVITE\r\n\r\n";
+        assert!(RequestRef::parse(bytes).unwrap_err().offset.is_some());
+    }
+
+    #[test]
+    fn request_parse_best_effort_collects_malformed_headers() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+CSeq: abc INVITE\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+Content-Length: 4\r\n\
+\r\n\
+body";
+        let (request_line, headers, body) = Request::parse_best_effort(bytes);
+
+        assert_eq!(request_line.unwrap().method, Method::Invite);
+        assert_eq!(headers.len(), 5);
+        assert!(matches!(headers[0], Ok(Header::Via(_))));
+        assert_eq!(headers[1], Ok(Header::MaxForwards(70)));
+        assert_eq!(headers[2], Err(HeaderError { raw: b"CSeq: abc INVITE".to_vec() }));
+        assert_eq!(headers[3], Ok(Header::CallID("3848276298220188511@atlanta.example.com".to_string())));
+        assert_eq!(headers[4], Ok(Header::ContentLength(4)));
+        assert_eq!(body, Some(b"body".to_vec()));
+
+        assert!(Request::parse(bytes).is_err());
+    }
+
+    #[test]
+    fn request_parse_best_effort_skips_malformed_request_line() {
+        let bytes = b"INVITE not a uri\r\nMax-Forwards: 70\r\n\r\n";
+        let (request_line, headers, body) = Request::parse_best_effort(bytes);
+
+        assert_eq!(request_line, None);
+        assert_eq!(headers, vec![Ok(Header::MaxForwards(70))]);
+        assert_eq!(body, None);
+    }
+
+    #[test]
+    fn request_to_bytes_round_trips() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = Request::parse(bytes).unwrap();
+        let serialized = req.to_bytes().unwrap();
+        let reparsed = Request::parse(&serialized).unwrap();
+
+        assert_eq!(reparsed.request_line, req.request_line);
+        assert_eq!(reparsed.via, req.via);
+        assert_eq!(reparsed.body, req.body);
+        assert_eq!(reparsed.to_bytes().unwrap(), serialized);
+    }
+
+    #[test]
+    fn request_to_bytes_recomputes_content_length() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let mut req = Request::parse(bytes).unwrap();
+        req.body = Some(b"v=0\r\n".to_vec());
+
+        let serialized = req.to_bytes().unwrap();
+        let reparsed = Request::parse(&serialized).unwrap();
+
+        assert!(reparsed.headers.contains(&Header::ContentLength(5).into()));
+        assert!(serialized.ends_with(b"Content-Length: 5\r\n\r\nv=0\r\n"));
+    }
+
+    #[test]
+    fn request_to_bytes_preserves_received_header_names() {
+        let bytes = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+v: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+MAX-FORWARDS: 70\r\n\
+f: \"Alice\" <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+to: \"Bob\" <sip:bob@biloxi.example.com>\r\n\
+call-id: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 OPTIONS\r\n\
+X-Custom-HEADER: foo\r\n\
+l: 0\r\n\
+\r\n";
+        let mut req = Request::parse(bytes).unwrap();
+        let names: Vec<_> = req.headers.iter().map(|header| header.name.as_deref().unwrap()).collect();
+        assert_eq!(names, vec![
+            "v", "MAX-FORWARDS", "f", "to", "call-id", "CSeq", "X-Custom-HEADER", "l",
+        ]);
+        assert_eq!(req.to_bytes().unwrap(), bytes.to_vec());
+
+        // a header replaced by a different one is written under its own name
+        req.headers[3].header = Header::Subject(Some("Hello".to_string()));
+        assert!(core::str::from_utf8(&req.to_bytes().unwrap()).unwrap().contains("\r\nSubject: Hello\r\n"));
+
+        // added headers are written under their canonical name, the others keep theirs
+        req.headers.insert(1, Header::MaxForwards(69).into());
+        assert!(req.to_bytes().unwrap().starts_with(b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+v: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 69\r\n\
+MAX-FORWARDS: 70\r\n"));
+    }
+
+    #[test]
+    fn request_equality_ignores_the_received_header_names() {
+        let compact = b"OPTIONS sip:bob@biloxi.example.com SIP/2.0\r\n\
+v: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+MAX-FORWARDS: 70\r\n\
+f: <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+t: <sip:bob@biloxi.example.com>\r\n\
+i: 3848276298220188511@atlanta.example.com\r\n\
+CSEQ: 1 OPTIONS\r\n\
+l: 0\r\n\
+\r\n";
+        let req = Request::parse(compact).unwrap();
+        let mut canonical = req.clone();
+        for header in canonical.headers.iter_mut() {
+            header.name = None;
+        }
+        let reparsed = Request::parse(&canonical.to_bytes().unwrap()).unwrap();
+
+        assert_ne!(reparsed.to_bytes().unwrap(), compact.to_vec());
+        assert_eq!(reparsed, req);
+    }
+
+    #[test]
+    fn request_to_bytes_rejects_line_breaks() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let mut req = Request::parse(bytes).unwrap();
+        for header in req.headers.iter_mut() {
+            if let Header::From(from) = &mut header.header {
+                from.name = Some("Alice\r\nContact: <sip:mallory@evil.example.com>".to_string());
+            }
+        }
+        assert_eq!(req.to_bytes(), Err(EncodeError::HeaderInjection));
+
+        let mut req = Request::parse(bytes).unwrap();
+        req.headers.push(Header::Extension("X-Note".to_string(), "a\r\nVia: x".to_string()).into());
+        assert_eq!(req.to_bytes(), Err(EncodeError::HeaderInjection));
+
+        let req = Request::parse(bytes).unwrap();
+        assert_eq!(req.create_response(StatusCode::BusyHere, "Busy\r\nVia: x").unwrap_err(), EncodeError::HeaderInjection);
+    }
+
+    #[test]
+    fn request_uri_scheme_is_taken_from_the_request_line() {
+        let schemes = [
+            ("sip:bob@biloxi.example.com", "sip"),
+            ("sips:bob@biloxi.example.com", "sips"),
+            ("tel:+14155551212", "tel"),
+            ("urn:service:sos", "urn"),
+        ];
+
+        for (uri, scheme) in schemes.iter() {
+            let bytes = format!("OPTIONS {} SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 OPTIONS\r\n\
+\r\n", uri);
+            let req = Request::parse(bytes.as_bytes()).unwrap();
+
+            assert_eq!(req.request_uri_scheme(), *scheme);
+        }
+    }
+
+    #[test]
+    fn request_header_finds_headers_by_name() {
+        let bytes = b"OPTIONS sip:carol@chicago.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP proxy.atlanta.example.com;branch=z9hG4bK2d4790.1\r\n\
+v: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKhjhs8ass877\r\n\
+Max-Forwards: 70\r\n\
+To: <sip:carol@chicago.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+X-Custom: value\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert_eq!(req.header("v"), Some(&req.headers[0].header));
+        assert_eq!(req.header("VIA"), Some(&req.headers[0].header));
+        assert_eq!(req.headers_named("via").count(), 2);
+        assert_eq!(req.header("call-id").map(|header| header.name()), Some("Call-ID"));
+        assert_eq!(req.header("i").map(|header| header.name()), Some("Call-ID"));
+        assert_eq!(req.header("x-custom").map(|header| header.name()), Some("X-Custom"));
+        assert_eq!(req.header("Supported"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn request_serde_round_trips_through_json() {
+        let bytes = include_bytes!("../assets/invite.sip");
+        let req = Request::parse(bytes).unwrap();
+
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["call_id"], "3848276298220188511@atlanta.example.com");
+        assert_eq!(json["request_line"]["method"], "Invite");
+
+        assert_eq!(serde_json::from_value::<Request>(json).unwrap(), req);
+    }
+
+    #[test]
+    fn request_semantically_eq_ignores_the_order_of_unordered_headers() {
+        let bytes = b"OPTIONS sip:carol@chicago.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKhjhs8ass877\r\n\
+Max-Forwards: 70\r\n\
+To: <sip:carol@chicago.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Accept: application/sdp\r\n\
+\r\n";
+        let reordered = b"OPTIONS sip:carol@chicago.example.com SIP/2.0\r\n\
+Accept: application/sdp\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Call-ID: a84b4c76e66710\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+To: <sip:carol@chicago.example.com>\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKhjhs8ass877\r\n\
+Max-Forwards: 70\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+        let reordered = Request::parse(reordered).unwrap();
+
+        assert_ne!(req, reordered);
+        assert!(req.semantically_eq(&reordered));
+        assert!(reordered.semantically_eq(&req));
+    }
+
+    #[test]
+    fn request_semantically_eq_respects_the_order_of_via_headers() {
+        let bytes = b"OPTIONS sip:carol@chicago.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP proxy.atlanta.example.com;branch=z9hG4bK2d4790.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKhjhs8ass877\r\n\
+Max-Forwards: 70\r\n\
+To: <sip:carol@chicago.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+\r\n";
+        let reordered = b"OPTIONS sip:carol@chicago.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKhjhs8ass877\r\n\
+Via: SIP/2.0/UDP proxy.atlanta.example.com;branch=z9hG4bK2d4790.1\r\n\
+Max-Forwards: 70\r\n\
+To: <sip:carol@chicago.example.com>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+
+        assert!(req.semantically_eq(&req.clone()));
+        assert!(!req.semantically_eq(&Request::parse(reordered).unwrap()));
+    }
+
+    /// Builds a request to `uri`, carrying the given Route headers
+    fn routed_request(uri: &str, routes: &[&str]) -> Request {
+        let mut bytes = format!("INVITE {} SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n", uri);
+        for route in routes {
+            bytes.push_str(&format!("Route: {}\r\n", route));
+        }
+        bytes.push_str("\r\n");
+
+        Request::parse(bytes.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn request_next_hop_is_the_loose_route() {
+        let req = routed_request("sip:bob@biloxi.example.com", &[
+            "<sip:ss1.atlanta.example.com;lr>",
+            "<sip:ss2.biloxi.example.com;lr>",
+        ]);
+
+        assert_eq!(req.next_hop().unwrap().to_string(), "sip:ss1.atlanta.example.com;lr");
+    }
+
+    #[test]
+    fn request_next_hop_is_the_request_uri_for_strict_routes() {
+        let req = routed_request("sip:ss1.atlanta.example.com", &[
+            "<sip:ss2.biloxi.example.com>",
+            "<sip:bob@biloxi.example.com>",
+        ]);
+
+        assert_eq!(req.next_hop().unwrap().to_string(), "sip:ss1.atlanta.example.com");
+    }
+
+    #[test]
+    fn request_next_hop_is_the_request_uri_without_routes() {
+        let req = routed_request("sip:bob@biloxi.example.com", &[]);
+        assert_eq!(req.next_hop().unwrap().to_string(), "sip:bob@biloxi.example.com");
+
+        let req = routed_request("tel:+14085264000", &[]);
+        assert_eq!(req.next_hop(), Err(NextHopError::InvalidRequestUri));
+    }
+
+    #[test]
+    fn request_dedup_adjacent_routes_collapses_duplicates() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+Route: <sip:ss1.atlanta.example.com;lr>, <sip:ss1.atlanta.example.com;lr>\r\n\
+Route: <sip:ss1.atlanta.example.com;lr>, <sip:ss2.biloxi.example.com;lr>\r\n\
+Route: <sip:ss1.atlanta.example.com;lr>\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 1 INVITE\r\n\
+\r\n";
+        let mut req = Request::parse(bytes).unwrap();
+        req.dedup_adjacent_routes();
+
+        let routes: Vec<String> = req.headers.iter()
+            .filter_map(|h| match &h.header {
+                Header::Route(routes) => Some(routes),
+                _ => None,
+            })
+            .flatten()
+            .map(|route| route.addr.to_string())
+            .collect();
+
+        assert_eq!(routes, vec![
+            "sip:ss1.atlanta.example.com;lr",
+            "sip:ss2.biloxi.example.com;lr",
+            "sip:ss1.atlanta.example.com;lr",
+        ]);
+        assert_eq!(req.headers.iter().filter(|h| matches!(h.header, Header::Route(_))).count(), 3);
+    }
+
+    #[test]
+    fn request_redacted_clone_hides_credentials() {
+        let bytes = b"INVITE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP client.atlanta.example.com:5060;branch=z9hG4bK74b43\r\n\
+Max-Forwards: 70\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag=9fxced76sl\r\n\
+To: Bob <sip:bob@biloxi.example.com>\r\n\
+Call-ID: 3848276298220188511@atlanta.example.com\r\n\
+CSeq: 2 INVITE\r\n\
+Authorization: Digest username=\"alice\", realm=\"atlanta.example.com\", nonce=\"dc3a5ab2\", uri=\"sip:bob@biloxi.example.com\", response=\"ccdca50cb091d587421457305d097458\", algorithm=MD5\r\n\
+P-Asserted-Identity: <sip:alice@atlanta.example.com>\r\n\
+\r\n";
+        let req = Request::parse(bytes).unwrap();
+        let redacted = req.redacted_clone();
+
+        let credentials = redacted.headers.iter().find_map(|h| match &h.header {
+            Header::Authorization(Credentials::DigestResponse(params)) => Some(params),
+            _ => None,
+        }).unwrap();
+        assert!(credentials.contains(&DigestResponseParam::Username("<redacted>".to_string())));
+        assert!(credentials.contains(&DigestResponseParam::Response("<redacted>".to_string())));
+        assert!(credentials.contains(&DigestResponseParam::Realm("atlanta.example.com".to_string())));
+
+        assert!(redacted.headers.contains(
+            &Header::Extension("P-Asserted-Identity".to_string(), "<redacted>".to_string()).into()
+        ));
+
+        assert_eq!(redacted.via, req.via);
+        assert!(redacted.headers.iter().any(|h| match &h.header {
+            Header::Via(via) => via == &req.via,
+            _ => false,
+        }));
     }
 }
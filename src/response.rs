@@ -1,26 +1,209 @@
+use crate::prelude::*;
+
+use crate::encode::{ self, EncodeError, };
+use crate::ParseOptions;
+use crate::header::{ Accept, Encoding, Header, Language, RetryAfter, };
+use crate::sip::{ Method, StatusCode, Version, };
 use crate::parser::rfc3261;
 use crate::parser::{ Error, ErrorKind, };
 
 /// Representation of a SIP Response
 ///
-/// **Note**: Responses are currently not well-supported. Patches welcome!
+/// The Status-Line and the headers are parsed, the body is kept as is. Unlike requests, no
+/// header is mandatory: a response missing Via or CSeq still parses.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Response {
+    /// the version of the SIP protocol used in the Status-Line
+    pub version: Version,
+
+    /// the status code of the Response
+    pub status_code: StatusCode,
+
+    /// the reason phrase accompanying the status code. It is meant for humans, and should not be
+    /// used to make decisions.
+    pub reason_phrase: String,
+
     /// unparsed content of the Response
+    ///
+    /// **Note**: This field is kept for backwards compatibility and will be removed in the
+    /// future, prefer the structured fields.
     pub content: Vec<u8>,
+
+    /// headers extracted from the Response
+    pub headers: Vec<Header>,
+
+    /// the raw lines of the headers that were skipped because they couldn't be parsed. Always
+    /// empty unless the response was parsed with `ParseOptions::skip_malformed_headers`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unparsed: Vec<Vec<u8>>,
+
+    /// the names of the headers kept as extension headers because their value was malformed.
+    /// Always empty unless the response was parsed with `ParseOptions::lenient_headers`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub downgraded_headers: Vec<String>,
+
+    /// the optional body of the response. This is completely unparsed and unvalidated.
+    pub body: Option<Vec<u8>>,
+}
+
+/// Capabilities advertised by a peer, typically in the response to an OPTIONS request
+///
+/// Values from repeated headers are concatenated in the order they appear. An empty list means
+/// the corresponding header was absent or empty.
+#[derive(PartialEq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// the methods listed in the Allow headers
+    pub methods: Vec<Method>,
+
+    /// the option-tags listed in the Supported headers
+    pub supported: Vec<String>,
+
+    /// the media ranges listed in the Accept headers
+    pub accept: Vec<Accept>,
+
+    /// the content-codings listed in the Accept-Encoding headers
+    pub accept_encoding: Vec<Encoding>,
+
+    /// the language ranges listed in the Accept-Language headers
+    pub accept_language: Vec<Language>,
+}
+
+impl Response {
+    /// Returns the first header called `name`
+    ///
+    /// Names are compared case-insensitively, and compact forms are accepted (`v` finds the Via
+    /// header). Extension headers are found whatever the casing they were received with.
+    pub fn header(&self, name: &str) -> Option<&Header> {
+        self.headers.iter().find(|header| header.is_named(name))
+    }
+
+    /// Returns every header called `name`, in order
+    ///
+    /// Names are compared the same way as by `Response::header`.
+    pub fn headers_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Header> {
+        self.headers.iter().filter(move |header| header.is_named(name))
+    }
+
+    /// Collects the capabilities advertised in the Allow, Supported, Accept, Accept-Encoding and
+    /// Accept-Language headers
+    pub fn capabilities(&self) -> Capabilities {
+        let mut capabilities = Capabilities::default();
+
+        for header in &self.headers {
+            match header {
+                Header::Allow(methods) => capabilities.methods.extend(methods.iter().cloned()),
+                Header::Supported(tags) => capabilities.supported.extend(tags.iter().cloned()),
+                Header::Accept(accepts) => capabilities.accept.extend(accepts.iter().cloned()),
+                Header::AcceptEncoding(encodings) => capabilities.accept_encoding.extend(encodings.iter().cloned()),
+                Header::AcceptLanguage(languages) => capabilities.accept_language.extend(languages.iter().cloned()),
+                _ => {},
+            }
+        }
+
+        capabilities
+    }
+
+    /// Returns the value of the Min-Expires header, if present
+    ///
+    /// A registrar answers a registration with too short an expiration interval with a `423
+    /// Interval Too Brief` response carrying this header. The client is expected to retry with an
+    /// Expires value greater than or equal to the returned value.
+    pub fn min_expires(&self) -> Option<u32> {
+        self.headers.iter().find_map(|header| match header {
+            Header::MinExpires(me) => Some(*me),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the Retry-After header, if present
+    ///
+    /// Its meaning depends on the status code, which callers have to take into account: in a `503
+    /// Service Unavailable` response, it tells how long the service is expected to be unavailable,
+    /// while in a `404 Not Found`, `413 Request Entity Too Large`, `480 Temporarily Unavailable`,
+    /// `486 Busy Here`, `600 Busy Everywhere` or `603 Decline` response, it tells when the callee
+    /// may be available again. A `3xx` response may carry it as well.
+    pub fn retry_after(&self) -> Option<&RetryAfter> {
+        self.headers.iter().find_map(|header| match header {
+            Header::RetryAfter(retry_after) => Some(retry_after),
+            _ => None,
+        })
+    }
+
+    /// Serializes the response to its wire format
+    ///
+    /// The Content-Length header is computed from the body, whatever value it had when the
+    /// response was parsed.
+    ///
+    /// Fails if a value contains a line break, see `encode::EncodeError::HeaderInjection`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let status_line = format!("{} {} {}", self.version, self.status_code.as_u16(), self.reason_phrase);
+
+        let headers = self.headers.iter().map(|header| (header.name(), header));
+
+        encode::message(&status_line, headers, self.body.as_deref())
+    }
 }
 
 impl<'a> Response {
     /// Attempts to parse a byte-slice representation of a SIP response
     ///
-    /// **Note**: Responses are currently not parsed in detail.
+    /// **Note**: The error type of this method will probably change in the future.
     pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &[u8]>> {
-        match rfc3261::response(input) {
-            Ok((_, req)) => Ok(req),
-            Err(nom::Err::Failure(err)) => Err(err),
+        match rfc3261::whole(input, rfc3261::response, |resp| rfc3261::content_length(resp.headers.iter())) {
+            Ok((_, resp)) => Ok(resp),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
             Err(_) => Err(Error::new(ErrorKind::UnknownError)),
         }
     }
+
+    /// Attempts to parse a byte-slice representation of a SIP response, according to `options`
+    ///
+    /// See `ParseOptions` for the behaviours that can be changed. `parse` is equivalent to this
+    /// method with the default options.
+    pub fn parse_with_options(input: &'a [u8], options: ParseOptions) -> Result<Self, Error<'a, &'a [u8]>> {
+        match rfc3261::whole(input, |input| rfc3261::response_with_options(input, options), |resp| rfc3261::content_length(resp.headers.iter())) {
+            Ok((_, resp)) => Ok(resp),
+            Err(nom::Err::Failure(err)) => Err(err.locate(input)),
+            Err(nom::Err::Incomplete(needed)) => Err(needed.into()),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
+
+    /// Attempts to parse a byte-slice representation of a SIP response, skipping malformed
+    /// headers
+    ///
+    /// Unlike `parse`, a header that can't be parsed doesn't cause the whole response to be
+    /// rejected: its raw line is kept in `unparsed`, and the other headers are still available.
+    /// This lets the Via headers be read off responses sent by peers that produce one odd header.
+    /// It is equivalent to `parse_with_options` with `ParseOptions::skip_malformed_headers`.
+    pub fn parse_lenient(input: &'a [u8]) -> Result<Self, Error<'a, &'a [u8]>> {
+        Self::parse_with_options(input, ParseOptions { skip_malformed_headers: true, ..ParseOptions::default() })
+    }
+}
+
+/// The response of `assets/200ok.sip`, along with the 274 bytes of SDP it announces
+///
+/// The asset is the example of RFC3261 section 11.2, which leaves its body out.
+#[cfg(test)]
+pub(crate) fn ok_200_with_body() -> Vec<u8> {
+    let body = b"v=0\r\n\
+o=carol 2890876487 2890876487 IN IP4 192.0.2.4\r\n\
+s=-\r\n\
+c=IN IP4 192.0.2.4\r\n\
+t=0 0\r\n\
+m=audio 0 RTP/AVP 0 1 3 99\r\n\
+a=rtpmap:0 PCMU/8000\r\n\
+a=rtpmap:1 1016/8000\r\n\
+a=rtpmap:3 GSM/8000\r\n\
+a=rtpmap:99 iLBC/8000\r\n\
+m=video 0 RTP/AVP 31 34\r\n\
+a=rtpmap:31 H261/90000\r\n\
+a=rtpmap:34 H263/90000\r\n";
+
+    [&include_bytes!("../assets/200ok.sip")[..], &body[..]].concat()
 }
 
 #[cfg(test)]
@@ -29,7 +212,216 @@ mod tests {
 
     #[test]
     fn response_parse_can_read_whole_message() {
-        let bytes = include_bytes!("../assets/200ok.sip");
+        let bytes = &ok_200_with_body()[..];
         assert_eq!(Response::parse(bytes).is_err(), false);
     }
+
+    #[test]
+    fn response_parse_reports_the_missing_body_of_the_asset() {
+        let bytes = include_bytes!("../assets/200ok.sip");
+        assert_eq!(Response::parse(bytes).map_err(|err| err.kind), Err(ErrorKind::Incomplete(Some(274))));
+    }
+
+    #[test]
+    fn response_parse_reads_status_line() {
+        let bytes = &ok_200_with_body()[..];
+        let response = Response::parse(bytes).unwrap();
+
+        assert_eq!(response.version, Version::Two);
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(response.reason_phrase, "OK");
+    }
+
+    #[test]
+    fn response_parse_keeps_spaces_in_reason_phrase() {
+        let bytes = b"SIP/2.0 486 Busy Here\r\n\r\n";
+        let response = Response::parse(bytes).unwrap();
+
+        assert_eq!(response.status_code, StatusCode::BusyHere);
+        assert_eq!(response.reason_phrase, "Busy Here");
+    }
+
+    #[test]
+    fn response_parse_reads_headers_and_body() {
+        let bytes = b"SIP/2.0 200 OK\r\n\
+Via: SIP/2.0/UDP server10.biloxi.com;branch=z9hG4bKnashds8\r\n\
+CSeq: 314159 INVITE\r\n\
+Content-Length: 4\r\n\
+\r\n\
+body";
+        let response = Response::parse(bytes).unwrap();
+
+        assert_eq!(response.headers.len(), 3);
+        assert!(response.headers.contains(&Header::CSeq(crate::sip::CSeq {
+            sequence: 314159,
+            method: crate::sip::Method::Invite,
+        })));
+        assert_eq!(response.body, Some(b"body".to_vec()));
+    }
+
+    #[test]
+    fn response_parse_rejects_out_of_range_status_code() {
+        let bytes = b"SIP/2.0 700 Unknown\r\n\r\n";
+        match Response::parse(bytes) {
+            Err(err) => assert_eq!(err.kind, ErrorKind::InvalidStatusCode(b"700")),
+            Ok(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn response_min_expires_reads_423_header() {
+        let bytes = b"SIP/2.0 423 Interval Too Brief\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7\r\n\
+To: Bob <sip:bob@biloxi.com>;tag=2493k59kd\r\n\
+From: Bob <sip:bob@biloxi.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 REGISTER\r\n\
+Min-Expires: 3600\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let response = Response::parse(bytes).unwrap();
+        assert_eq!(response.min_expires(), Some(3600));
+    }
+
+    #[test]
+    fn response_retry_after_reads_503_header() {
+        let bytes = b"SIP/2.0 503 Service Unavailable\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7\r\n\
+CSeq: 1826 REGISTER\r\n\
+Retry-After: 120\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let response = Response::parse(bytes).unwrap();
+
+        assert_eq!(response.status_code, StatusCode::ServiceUnavailable);
+        assert_eq!(response.retry_after().map(|retry_after| retry_after.duration), Some(120));
+    }
+
+    #[test]
+    fn response_retry_after_reads_486_header() {
+        let bytes = b"SIP/2.0 486 Busy Here\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+CSeq: 314159 INVITE\r\n\
+Retry-After: 3600 (in a meeting);duration=1800\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let response = Response::parse(bytes).unwrap();
+        let retry_after = response.retry_after().unwrap();
+
+        assert_eq!(response.status_code, StatusCode::BusyHere);
+        assert_eq!(retry_after.duration, 3600);
+        assert_eq!(retry_after.comment, Some("in a meeting".to_string()));
+        assert_eq!(retry_after.params.len(), 1);
+    }
+
+    #[test]
+    fn response_to_bytes_round_trips() {
+        let bytes = &ok_200_with_body()[..];
+        let response = Response::parse(bytes).unwrap();
+        let serialized = response.to_bytes().unwrap();
+        let reparsed = Response::parse(&serialized).unwrap();
+
+        assert_eq!(reparsed.status_code, response.status_code);
+        assert_eq!(reparsed.reason_phrase, response.reason_phrase);
+        assert_eq!(reparsed.body, response.body);
+        assert_eq!(reparsed.to_bytes().unwrap(), serialized);
+    }
+
+    #[test]
+    fn response_capabilities_collects_options_headers() {
+        let bytes = b"SIP/2.0 200 OK\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Allow: INVITE, ACK, CANCEL\r\n\
+Allow: OPTIONS, BYE\r\n\
+Accept: application/sdp\r\n\
+Accept-Encoding: gzip\r\n\
+Accept-Language: en\r\n\
+Supported: replace, timer\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let capabilities = Response::parse(bytes).unwrap().capabilities();
+
+        assert_eq!(capabilities.methods, vec![
+            Method::Invite,
+            Method::Ack,
+            Method::Cancel,
+            Method::Options,
+            Method::Bye,
+        ]);
+        assert_eq!(capabilities.supported, vec!["replace".to_string(), "timer".to_string()]);
+        assert_eq!(capabilities.accept.len(), 1);
+        assert_eq!(capabilities.accept_encoding.len(), 1);
+        assert_eq!(capabilities.accept_language.len(), 1);
+    }
+
+    #[test]
+    fn response_capabilities_are_empty_when_absent() {
+        let bytes = b"SIP/2.0 200 OK\r\n\r\n";
+        assert_eq!(Response::parse(bytes).unwrap().capabilities(), Capabilities::default());
+    }
+
+    #[test]
+    fn response_parse_lenient_skips_malformed_headers() {
+        let bytes = b"SIP/2.0 180 Ringing\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+CSeq: abc INVITE\r\n\
+Call-ID: a84b4c76e66710\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+        assert!(Response::parse(bytes).is_err());
+
+        let response = Response::parse_lenient(bytes).unwrap();
+        assert_eq!(response.status_code, StatusCode::Ringing);
+        assert_eq!(response.unparsed, vec![b"CSeq: abc INVITE".to_vec()]);
+        assert_eq!(response.headers.len(), 3);
+        assert!(matches!(response.headers[0], Header::Via(_)));
+        assert_eq!(response.content, bytes.to_vec());
+    }
+
+    #[test]
+    fn response_parse_lenient_matches_parse_for_valid_responses() {
+        let bytes = &ok_200_with_body()[..];
+        assert_eq!(Response::parse_lenient(bytes).unwrap(), Response::parse(bytes).unwrap());
+    }
+
+    #[test]
+    fn response_parse_with_lenient_headers_downgrades_malformed_headers() {
+        let bytes = b"SIP/2.0 200 OK\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+Min-Expires: 60abc\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let options = ParseOptions { lenient_headers: true, ..ParseOptions::default() };
+
+        assert!(Response::parse(bytes).is_err());
+
+        let response = Response::parse_with_options(bytes, options).unwrap();
+        assert_eq!(response.headers[1], Header::Extension("Min-Expires".to_string(), "60abc".to_string()));
+        assert_eq!(response.downgraded_headers, vec!["Min-Expires".to_string()]);
+        assert_eq!(response.min_expires(), None);
+    }
+
+    #[test]
+    fn response_header_lookup_ignores_case_of_extension_headers() {
+        let bytes = b"SIP/2.0 200 OK\r\n\
+v: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+p-preferred-service: urn:urn-7:3gpp-service.ims.icsi.mmtel\r\n\
+P-PREFERRED-SERVICE: urn:urn-7:3gpp-application.ims.iari.rcse.im\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let response = Response::parse(bytes).unwrap();
+
+        assert_eq!(response.header("Via"), Some(&response.headers[0]));
+        assert_eq!(response.header("P-Preferred-Service"), Some(&response.headers[1]));
+        assert_eq!(response.headers_named("p-Preferred-service").count(), 2);
+        assert_eq!(response.header("P-Asserted-Identity"), None);
+    }
+
+    #[test]
+    fn response_min_expires_is_none_when_absent() {
+        let bytes = &ok_200_with_body()[..];
+        assert_eq!(Response::parse(bytes).unwrap().min_expires(), None);
+    }
 }
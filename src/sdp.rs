@@ -0,0 +1,121 @@
+use crate::prelude::*;
+use crate::parser::{ rfc4566, Error, ErrorKind };
+
+/// Representation of an SDP origin (`o=`) line
+#[derive(PartialEq, Debug, Clone)]
+pub struct Origin {
+    /// the login of the user on the originating host, `-` if not supported
+    pub username: String,
+
+    /// a numeric string identifying the session
+    pub session_id: String,
+
+    /// the version of the session description, increased with each modification
+    pub session_version: String,
+
+    /// the type of network, `IN` for the Internet
+    pub network_type: String,
+
+    /// the type of the address, `IP4` or `IP6`
+    pub address_type: String,
+
+    /// the address of the machine from which the session was created
+    pub address: String,
+}
+
+/// Representation of an SDP connection data (`c=`) line
+#[derive(PartialEq, Debug, Clone)]
+pub struct Connection {
+    /// the type of network, `IN` for the Internet
+    pub network_type: String,
+
+    /// the type of the address, `IP4` or `IP6`
+    pub address_type: String,
+
+    /// the connection address, which may carry a TTL and a number of addresses for multicast
+    pub address: String,
+}
+
+/// Representation of an SDP timing (`t=`) line
+#[derive(PartialEq, Debug, Clone)]
+pub struct Timing {
+    /// the start time of the session (NTP), 0 if unbounded
+    pub start: u64,
+
+    /// the stop time of the session (NTP), 0 if unbounded
+    pub stop: u64,
+}
+
+/// Representation of an SDP attribute (`a=`) line
+#[derive(PartialEq, Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// Representation of an SDP media description, starting with a media (`m=`) line
+#[derive(PartialEq, Debug, Clone)]
+pub struct MediaDescription {
+    /// the type of media, e.g. `audio` or `video`
+    pub media: String,
+
+    /// the transport port the media is sent to
+    pub port: u16,
+
+    /// the number of consecutive ports used, if more than one
+    pub port_count: Option<u16>,
+
+    /// the transport protocol, e.g. `RTP/AVP`
+    pub protocol: String,
+
+    /// the media formats, e.g. RTP payload types
+    pub formats: Vec<String>,
+
+    /// the connection data specific to this media, if any
+    pub connection: Option<Connection>,
+
+    /// the attributes specific to this media
+    pub attributes: Vec<Attribute>,
+}
+
+/// Representation of an SDP session description (RFC4566)
+///
+/// Only the lines needed to set up a session are kept: version, origin, session name, connection
+/// data, timing, attributes and media descriptions. Other lines are skipped.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SessionDescription {
+    /// the version of the protocol, always 0
+    pub version: u32,
+
+    pub origin: Origin,
+
+    /// the name of the session, `-` if it has none
+    pub session_name: String,
+
+    /// the session-level connection data, if any
+    pub connection: Option<Connection>,
+
+    /// the times at which the session is active
+    pub timing: Vec<Timing>,
+
+    /// the session-level attributes
+    pub attributes: Vec<Attribute>,
+
+    /// the media descriptions
+    pub media: Vec<MediaDescription>,
+}
+
+impl<'a> SessionDescription {
+    /// Attempts to parse a byte-slice representation of a session description
+    ///
+    /// Lines can be terminated by either CRLF or LF.
+    ///
+    /// **Note**: The error type of this method will probably change in the future.
+    pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &'a [u8]>> {
+        match rfc4566::session_description(input) {
+            Ok((_, sdp)) => Ok(sdp),
+            Err(nom::Err::Failure(err)) | Err(nom::Err::Error(err)) => Err(err.locate(input)),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
+}
@@ -1,12 +1,30 @@
-use slog;
+use crate::prelude::*;
+use core::fmt;
+use crate::net::{ Ipv4Addr, Ipv6Addr, };
+
+use crate::{
+    header::{ URIParam, URIHeader, },
+    parser::{ rfc2806, rfc3261, Error, ErrorKind, },
+};
 
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Version {
     Two,
     Other(i32, i32),
 }
 
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Two => f.write_str("SIP/2.0"),
+            Self::Other(major, minor) => write!(f, "SIP/{}.{}", major, minor),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Transport {
     UDP,
     TCP,
@@ -16,12 +34,667 @@ pub enum Transport {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum User {
     Phone,
     IP,
     Other(String),
 }
 
+impl Transport {
+    /// Returns the port used when none is given (RFC3261 section 19.1.2): 5061 for TLS, 5060
+    /// otherwise
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::TLS => 5061,
+            _ => 5060,
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UDP => "udp",
+            Self::TCP => "tcp",
+            Self::SCTP => "sctp",
+            Self::TLS => "tls",
+            Self::Extension(transport) => transport,
+        })
+    }
+}
+
+impl fmt::Display for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Phone => "phone",
+            Self::IP => "ip",
+            Self::Other(user) => user,
+        })
+    }
+}
+
+/// Representation of a host, as found in URIs and in the Via and Warning headers
+///
+/// Domains are compared as described in RFC3261 section 19.1.4: case-insensitively, and the
+/// trailing dot of a fully qualified domain name (`host.example.com.`) is ignored. The domain is
+/// kept as it was received, so that it is encoded unchanged.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Host {
+    /// a fully qualified domain name, or anything that couldn't be read as an IP address
+    Domain(String),
+
+    /// an IPv4 address
+    IpV4(Ipv4Addr),
+
+    /// an IPv6 address. On the wire, it is enclosed in square brackets.
+    IpV6(Ipv6Addr),
+}
+
+fn without_root(domain: &str) -> &str {
+    domain.strip_suffix('.').unwrap_or(domain)
+}
+
+impl Host {
+    /// Returns the domain without the trailing dot of a fully qualified domain name
+    ///
+    /// Returns `None` for IP addresses.
+    pub fn normalized_domain(&self) -> Option<&str> {
+        match self {
+            Self::Domain(domain) => Some(without_root(domain)),
+            _ => None,
+        }
+    }
+
+    /// Returns the host in a canonical form: domains are lowercased, and their trailing dot is
+    /// removed
+    ///
+    /// Canonical hosts compare equal exactly when the original hosts do, which makes them suitable
+    /// as keys, for example in a location service. The original host should be kept for encoding.
+    pub fn normalized(&self) -> Self {
+        match self {
+            Self::Domain(domain) => Self::Domain(without_root(domain).to_ascii_lowercase()),
+            other => other.clone(),
+        }
+    }
+}
+
+impl PartialEq for Host {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Domain(a), Self::Domain(b)) => {
+                without_root(a).eq_ignore_ascii_case(without_root(b))
+            },
+            (Self::IpV4(a), Self::IpV4(b)) => a == b,
+            (Self::IpV6(a), Self::IpV6(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Host {}
+
+impl core::hash::Hash for Host {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Domain(domain) => without_root(domain)
+                .bytes()
+                .for_each(|c| c.to_ascii_lowercase().hash(state)),
+            Self::IpV4(addr) => addr.hash(state),
+            Self::IpV6(addr) => addr.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Domain(domain) => f.write_str(domain),
+            Self::IpV4(addr) => addr.fmt(f),
+            Self::IpV6(addr) => write!(f, "[{}]", addr),
+        }
+    }
+}
+
+/// Representation of a SIP or SIPS URI
+///
+/// SIP URIs identify communication resources, and are found in Request-Lines as well as in
+/// headers such as From, To or Contact. Use `SipUri::parse` (or `str::parse`) to read a
+/// standalone URI, for example one coming from a configuration file.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SipUri {
+    /// whether the URI uses the `sips` scheme
+    pub secure: bool,
+
+    /// the user part of the URI, if present. Escaped characters (e.g. `%40`) are decoded.
+    pub user: Option<String>,
+
+    /// the password of the user, if present, with escaped characters decoded. Its use is not
+    /// recommended.
+    pub password: Option<String>,
+
+    /// the host providing the SIP resource
+    pub host: Host,
+
+    /// the port on which the host can be reached
+    pub port: Option<u16>,
+
+    /// the URI parameters
+    pub params: Vec<URIParam>,
+
+    /// the headers to be included in a request constructed from the URI
+    pub headers: Vec<URIHeader>,
+}
+
+/// Writes `value`, escaping every character that isn't `allowed` unescaped (e.g.
+/// `rfc3261::is_param_char` for URI parameters)
+pub(crate) fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str, allowed: fn(u8) -> bool) -> fmt::Result {
+    for c in value.bytes() {
+        if allowed(c) {
+            write!(f, "{}", c as char)?;
+        } else {
+            write!(f, "%{:02X}", c)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for SipUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.secure { "sips:" } else { "sip:" })?;
+
+        if let Some(user) = &self.user {
+            write_escaped(f, user, rfc3261::is_user_char)?;
+            if let Some(password) = &self.password {
+                f.write_str(":")?;
+                write_escaped(f, password, rfc3261::is_password_char)?;
+            }
+            f.write_str("@")?;
+        }
+
+        self.host.fmt(f)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+
+        for param in &self.params {
+            write!(f, ";{}", param)?;
+        }
+
+        for (i, header) in self.headers.iter().enumerate() {
+            f.write_str(if i == 0 { "?" } else { "&" })?;
+            write_escaped(f, &header.name, rfc3261::is_header_char)?;
+            f.write_str("=")?;
+            write_escaped(f, &header.value, rfc3261::is_header_char)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Representation of the URI found in address-bearing headers (From, To, Contact, etc.)
+///
+/// SIP and SIPS URIs are parsed in detail. Any other URI (`tel`, `mailto`, `http`, etc.) is kept
+/// as-is.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Uri {
+    /// a SIP or SIPS URI
+    Sip(SipUri),
+
+    /// any other absolute URI, unparsed
+    Absolute(String),
+}
+
+impl Uri {
+    /// Returns the URI parameters, which only SIP and SIPS URIs can have
+    pub fn params(&self) -> &[URIParam] {
+        match self {
+            Self::Sip(uri) => &uri.params,
+            Self::Absolute(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sip(uri) => uri.fmt(f),
+            Self::Absolute(uri) => f.write_str(uri),
+        }
+    }
+}
+
+/// Error returned when a string could not be parsed as a SIP URI
+#[derive(PartialEq, Debug, Copy, Clone, thiserror::Error)]
+#[error("invalid SIP URI")]
+pub struct InvalidSipUriError;
+
+impl<'a> SipUri {
+    /// Attempts to parse a byte-slice representation of a SIP or SIPS URI
+    ///
+    /// The whole input must be consumed by the URI.
+    ///
+    /// **Note**: The error type of this method will probably change in the future.
+    pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &'a [u8]>> {
+        match rfc3261::sip_uri(input) {
+            Ok((_, uri)) => Ok(uri),
+            Err(nom::Err::Failure(err)) => Err(err),
+            Err(nom::Err::Error(err)) => Err(err),
+            Err(_) => Err(Error::new(ErrorKind::UnknownError)),
+        }
+    }
+}
+
+impl SipUri {
+    /// Whether both URIs designate the same address of record
+    ///
+    /// As done by registrars (RFC3261 section 10.3), URI parameters and headers are ignored. The
+    /// scheme, user and port must be the same, and hosts are compared case-insensitively.
+    pub fn aor_eq(&self, other: &SipUri) -> bool {
+        self.secure == other.secure
+            && self.user == other.user
+            && self.host == other.host
+            && self.port == other.port
+    }
+
+    /// Whether both URIs are equivalent, following the comparison rules of RFC3261 section 19.1.4
+    ///
+    /// The scheme, user, password, host and port must be the same, hosts being compared
+    /// case-insensitively. Parameters present in both URIs must have the same value (compared
+    /// case-insensitively), regardless of their order. The `transport`, `user`, `ttl`, `method`
+    /// and `maddr` parameters must be present in both URIs or in neither, even when set to their
+    /// default value, other parameters present in only one URI are ignored. Headers must be the
+    /// same in both URIs, in any order.
+    pub fn matches(&self, other: &SipUri) -> bool {
+        const NEVER_IGNORED: [&str; 5] = ["transport", "user", "ttl", "method", "maddr"];
+
+        let find = |params: &[URIParam], name: &str| params.iter()
+            .find(|param| param.name().eq_ignore_ascii_case(name))
+            .map(|param| param.to_string());
+
+        let params_match = |params: &[URIParam], others: &[URIParam]| params.iter().all(|param| {
+            match find(others, param.name()) {
+                Some(other) => other.eq_ignore_ascii_case(&param.to_string()),
+                None => !NEVER_IGNORED.iter().any(|name| param.name().eq_ignore_ascii_case(name)),
+            }
+        });
+
+        let headers_match = |headers: &[URIHeader], others: &[URIHeader]| headers.iter().all(|header| {
+            others.iter().any(|other| {
+                other.name.eq_ignore_ascii_case(&header.name) && other.value == header.value
+            })
+        });
+
+        self.aor_eq(other)
+            && self.password == other.password
+            && params_match(&self.params, &other.params)
+            && params_match(&other.params, &self.params)
+            && headers_match(&self.headers, &other.headers)
+            && headers_match(&other.headers, &self.headers)
+    }
+}
+
+impl core::str::FromStr for SipUri {
+    type Err = InvalidSipUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s.as_bytes()).map_err(|_| InvalidSipUriError)
+    }
+}
+
+/// Representation of a `tel` URI, as defined in RFC2806
+///
+/// The telephone-subscriber part is kept as written, visual separators and parameters included.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TelUri {
+    /// the telephone-subscriber, i.e. everything following `tel:`
+    pub number: String,
+}
+
+impl<'a> TelUri {
+    /// Attempts to parse a byte-slice representation of a `tel` URI
+    ///
+    /// The whole input must be consumed by the URI.
+    pub fn parse(input: &'a [u8]) -> Result<Self, Error<'a, &'a [u8]>> {
+        let number = match rfc2806::telephone_url(input) {
+            Ok((_, number)) => number,
+            Err(nom::Err::Failure(err)) => return Err(err),
+            Err(nom::Err::Error(err)) => return Err(err),
+            Err(_) => return Err(Error::new(ErrorKind::UnknownError)),
+        };
+
+        let number = core::str::from_utf8(number)?.to_string();
+
+        Ok(Self { number })
+    }
+
+    /// Whether the number is a global (E.164) number, i.e. starts with `+`
+    pub fn is_global(&self) -> bool {
+        self.number.starts_with('+')
+    }
+
+    /// Returns the number in E.164 form: `+` followed by digits only
+    ///
+    /// Visual separators (`-.()`) and parameters are dropped. Local numbers can't be expressed in
+    /// E.164 without knowing their context, so `None` is returned for those.
+    pub fn normalized_e164(&self) -> Option<String> {
+        if !self.is_global() {
+            return None;
+        }
+
+        let base = self.number.split(';').next().unwrap_or_default();
+        let digits = base.chars().filter(|c| c.is_ascii_digit());
+
+        Some(core::iter::once('+').chain(digits).collect())
+    }
+}
+
+impl fmt::Display for TelUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tel:{}", self.number)
+    }
+}
+
+/// Representation of a SIP response status code
+///
+/// The status codes defined in RFC3261 have their own variant. Any other code in the 100-699 range
+/// is stored as `Other` by `from_u16`. Status codes are compared by their numeric value, so an
+/// `Other` holding a code that has its own variant (e.g. `Other(200)`) is equal to that variant.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusCode {
+    /// 100 Trying
+    Trying,
+
+    /// 180 Ringing
+    Ringing,
+
+    /// 181 Call Is Being Forwarded
+    CallIsBeingForwarded,
+
+    /// 182 Queued
+    Queued,
+
+    /// 183 Session Progress
+    SessionProgress,
+
+    /// 200 OK
+    OK,
+
+    /// 300 Multiple Choices
+    MultipleChoices,
+
+    /// 301 Moved Permanently
+    MovedPermanently,
+
+    /// 302 Moved Temporarily
+    MovedTemporarily,
+
+    /// 305 Use Proxy
+    UseProxy,
+
+    /// 380 Alternative Service
+    AlternativeService,
+
+    /// 400 Bad Request
+    BadRequest,
+
+    /// 401 Unauthorized
+    Unauthorized,
+
+    /// 402 Payment Required
+    PaymentRequired,
+
+    /// 403 Forbidden
+    Forbidden,
+
+    /// 404 Not Found
+    NotFound,
+
+    /// 405 Method Not Allowed
+    MethodNotAllowed,
+
+    /// 406 Not Acceptable
+    NotAcceptable,
+
+    /// 407 Proxy Authentication Required
+    ProxyAuthenticationRequired,
+
+    /// 408 Request Timeout
+    RequestTimeout,
+
+    /// 410 Gone
+    Gone,
+
+    /// 413 Request Entity Too Large
+    RequestEntityTooLarge,
+
+    /// 414 Request-URI Too Long
+    RequestURITooLong,
+
+    /// 415 Unsupported Media Type
+    UnsupportedMediaType,
+
+    /// 416 Unsupported URI Scheme
+    UnsupportedURIScheme,
+
+    /// 420 Bad Extension
+    BadExtension,
+
+    /// 421 Extension Required
+    ExtensionRequired,
+
+    /// 423 Interval Too Brief
+    IntervalTooBrief,
+
+    /// 480 Temporarily Unavailable
+    TemporarilyUnavailable,
+
+    /// 481 Call/Transaction Does Not Exist
+    CallTransactionDoesNotExist,
+
+    /// 482 Loop Detected
+    LoopDetected,
+
+    /// 483 Too Many Hops
+    TooManyHops,
+
+    /// 484 Address Incomplete
+    AddressIncomplete,
+
+    /// 485 Ambiguous
+    Ambiguous,
+
+    /// 486 Busy Here
+    BusyHere,
+
+    /// 487 Request Terminated
+    RequestTerminated,
+
+    /// 488 Not Acceptable Here
+    NotAcceptableHere,
+
+    /// 491 Request Pending
+    RequestPending,
+
+    /// 493 Undecipherable
+    Undecipherable,
+
+    /// 500 Server Internal Error
+    ServerInternalError,
+
+    /// 501 Not Implemented
+    NotImplemented,
+
+    /// 502 Bad Gateway
+    BadGateway,
+
+    /// 503 Service Unavailable
+    ServiceUnavailable,
+
+    /// 504 Server Time-out
+    ServerTimeout,
+
+    /// 505 Version Not Supported
+    VersionNotSupported,
+
+    /// 513 Message Too Large
+    MessageTooLarge,
+
+    /// 600 Busy Everywhere
+    BusyEverywhere,
+
+    /// 603 Decline
+    Decline,
+
+    /// 604 Does Not Exist Anywhere
+    DoesNotExistAnywhere,
+
+    /// 606 Not Acceptable
+    NotAcceptableAnywhere,
+
+    /// any other status code, for example defined by an extension to the SIP protocol
+    Other(u16),
+}
+
+/// The class of a status code, as indicated by its first digit
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusClass {
+    /// 1xx: request received, continuing to process the request
+    Provisional,
+
+    /// 2xx: the action was successfully received, understood, and accepted
+    Success,
+
+    /// 3xx: further action needs to be taken in order to complete the request
+    Redirection,
+
+    /// 4xx: the request contains bad syntax or cannot be fulfilled at this server
+    ClientError,
+
+    /// 5xx: the server failed to fulfill an apparently valid request
+    ServerError,
+
+    /// 6xx: the request cannot be fulfilled at any server
+    GlobalFailure,
+}
+
+const KNOWN_STATUS_CODES: &[(u16, StatusCode)] = &[
+    (100, StatusCode::Trying),
+    (180, StatusCode::Ringing),
+    (181, StatusCode::CallIsBeingForwarded),
+    (182, StatusCode::Queued),
+    (183, StatusCode::SessionProgress),
+    (200, StatusCode::OK),
+    (300, StatusCode::MultipleChoices),
+    (301, StatusCode::MovedPermanently),
+    (302, StatusCode::MovedTemporarily),
+    (305, StatusCode::UseProxy),
+    (380, StatusCode::AlternativeService),
+    (400, StatusCode::BadRequest),
+    (401, StatusCode::Unauthorized),
+    (402, StatusCode::PaymentRequired),
+    (403, StatusCode::Forbidden),
+    (404, StatusCode::NotFound),
+    (405, StatusCode::MethodNotAllowed),
+    (406, StatusCode::NotAcceptable),
+    (407, StatusCode::ProxyAuthenticationRequired),
+    (408, StatusCode::RequestTimeout),
+    (410, StatusCode::Gone),
+    (413, StatusCode::RequestEntityTooLarge),
+    (414, StatusCode::RequestURITooLong),
+    (415, StatusCode::UnsupportedMediaType),
+    (416, StatusCode::UnsupportedURIScheme),
+    (420, StatusCode::BadExtension),
+    (421, StatusCode::ExtensionRequired),
+    (423, StatusCode::IntervalTooBrief),
+    (480, StatusCode::TemporarilyUnavailable),
+    (481, StatusCode::CallTransactionDoesNotExist),
+    (482, StatusCode::LoopDetected),
+    (483, StatusCode::TooManyHops),
+    (484, StatusCode::AddressIncomplete),
+    (485, StatusCode::Ambiguous),
+    (486, StatusCode::BusyHere),
+    (487, StatusCode::RequestTerminated),
+    (488, StatusCode::NotAcceptableHere),
+    (491, StatusCode::RequestPending),
+    (493, StatusCode::Undecipherable),
+    (500, StatusCode::ServerInternalError),
+    (501, StatusCode::NotImplemented),
+    (502, StatusCode::BadGateway),
+    (503, StatusCode::ServiceUnavailable),
+    (504, StatusCode::ServerTimeout),
+    (505, StatusCode::VersionNotSupported),
+    (513, StatusCode::MessageTooLarge),
+    (600, StatusCode::BusyEverywhere),
+    (603, StatusCode::Decline),
+    (604, StatusCode::DoesNotExistAnywhere),
+    (606, StatusCode::NotAcceptableAnywhere),
+];
+
+impl StatusCode {
+    /// Returns the status code matching `code`, or `None` if it is outside the 100-699 range
+    pub fn from_u16(code: u16) -> Option<Self> {
+        if !(100..=699).contains(&code) {
+            return None;
+        }
+
+        let status = KNOWN_STATUS_CODES.iter()
+            .find(|(known, _)| *known == code)
+            .map(|(_, status)| *status)
+            .unwrap_or(Self::Other(code));
+
+        Some(status)
+    }
+
+    /// Returns the numeric value of the status code
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            Self::Other(code) => *code,
+            status => KNOWN_STATUS_CODES.iter()
+                .find(|(_, known)| core::mem::discriminant(known) == core::mem::discriminant(status))
+                .map(|(code, _)| *code)
+                .unwrap(),
+        }
+    }
+
+    /// Returns the class of the status code, given by its first digit
+    ///
+    /// Returns `None` for an `Other` code outside the 100-699 range.
+    pub fn class(&self) -> Option<StatusClass> {
+        match self.as_u16() / 100 {
+            1 => Some(StatusClass::Provisional),
+            2 => Some(StatusClass::Success),
+            3 => Some(StatusClass::Redirection),
+            4 => Some(StatusClass::ClientError),
+            5 => Some(StatusClass::ServerError),
+            6 => Some(StatusClass::GlobalFailure),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for StatusCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_u16() == other.as_u16()
+    }
+}
+
+impl Eq for StatusCode {}
+
+impl core::hash::Hash for StatusCode {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_u16().hash(state);
+    }
+}
+
 /// Representation of a SIP method
 ///
 /// A SIP method informs on the request type (when it is part of a Request-Line), or what a
@@ -30,6 +703,7 @@ pub enum User {
 ///
 /// **Note**: `INFO` is not supported, but can be if there is a use for it. Patches welcome!
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Method {
     /// used to setup sessions
     Invite,
@@ -53,18 +727,429 @@ pub enum Method {
     Extension(String)
 }
 
-impl slog::Value for Method {
-    fn serialize(&self, _rec: &slog::Record, key: slog::Key, serializer: &mut dyn slog::Serializer) -> slog::Result {
-        let method = match self {
+impl core::str::FromStr for Method {
+    type Err = core::convert::Infallible;
+
+    /// Parses a method name, case-sensitively. Unknown names become `Method::Extension`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "INVITE" => Self::Invite,
+            "ACK" => Self::Ack,
+            "OPTIONS" => Self::Options,
+            "BYE" => Self::Bye,
+            "CANCEL" => Self::Cancel,
+            "REGISTER" => Self::Register,
+            other => Self::Extension(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
             Self::Invite => "INVITE",
-            Self::Ack => "Ack",
+            Self::Ack => "ACK",
             Self::Options => "OPTIONS",
             Self::Bye => "BYE",
             Self::Cancel => "CANCEL",
             Self::Register => "REGISTER",
-            Self::Extension(s) => &s,
-        };
+            Self::Extension(method) => method,
+        })
+    }
+}
+
+#[cfg(feature = "slog")]
+impl slog::Value for Method {
+    fn serialize(&self, _rec: &slog::Record, key: slog::Key, serializer: &mut dyn slog::Serializer) -> slog::Result {
+        serializer.emit_arguments(key, &format_args!("{}", self))
+    }
+}
+
+/// Representation of the value of a CSeq header
+///
+/// The sequence number orders requests within a dialog. CSeq values only compare as equal when
+/// both their sequence number and their method match, and are otherwise ordered by sequence
+/// number. Consistently with equality, values with the same sequence number but different methods
+/// (e.g. an ACK or a CANCEL and the INVITE it relates to) are not ordered: every comparison
+/// between them is false. Compare their `sequence` fields to relate such requests.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CSeq {
+    /// the sequence number, lower than 2**31
+    pub sequence: u32,
+
+    /// the method of the request
+    pub method: Method,
+}
+
+impl core::cmp::PartialOrd for CSeq {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        match self.sequence.cmp(&other.sequence) {
+            // consistent with PartialEq: same number but different methods are not comparable
+            core::cmp::Ordering::Equal if self.method != other.method => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl fmt::Display for CSeq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.sequence, self.method)
+    }
+}
+
+/// Returns `len` random bytes written as lowercase hexadecimal digits
+#[cfg(feature = "generate")]
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).expect("no source of randomness is available");
+
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Generates a random tag for a From or To header
+///
+/// RFC3261 (section 19.3) requires tags to be cryptographically random, with at least 32 bits of
+/// randomness. The generated tag holds 64 bits, written as hexadecimal digits so that it is a
+/// valid token.
+///
+/// # Panics
+///
+/// Panics if the operating system can't provide random bytes.
+#[cfg(feature = "generate")]
+pub fn gen_tag() -> String {
+    random_hex(8)
+}
+
+/// Generates a random Call-ID of the form `localid@host`
+///
+/// The local part holds 128 bits of randomness, written as hexadecimal digits. `host` is usually
+/// the host name or IP address of the UA, and must only contain `word` characters (RFC3261
+/// section 25.1) for the Call-ID to be parsed back.
+///
+/// # Panics
+///
+/// Panics if the operating system can't provide random bytes.
+#[cfg(feature = "generate")]
+pub fn gen_call_id(host: &str) -> String {
+    format!("{}@{}", random_hex(16), host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cseq_is_ordered_by_sequence_number() {
+        let invite = CSeq { sequence: 1, method: Method::Invite };
+        let bye = CSeq { sequence: 2, method: Method::Bye };
+        let ack = CSeq { sequence: 1, method: Method::Ack };
+
+        assert!(invite < bye);
+        assert!(bye > ack);
+        assert_eq!(invite.partial_cmp(&invite.clone()), Some(core::cmp::Ordering::Equal));
+        assert!(invite <= invite.clone() && invite >= invite.clone());
+    }
+
+    #[test]
+    fn cseq_with_the_same_number_but_another_method_is_not_ordered() {
+        let invite = CSeq { sequence: 1, method: Method::Invite };
+        let ack = CSeq { sequence: 1, method: Method::Ack };
+
+        assert_eq!(invite.partial_cmp(&ack), None);
+        assert_eq!([invite < ack, invite > ack, invite <= ack, invite >= ack], [false; 4]);
+        assert_ne!(invite, ack);
+        assert_eq!(invite.sequence, ack.sequence);
+    }
+
+    #[test]
+    fn tel_uri_normalized_e164_strips_visual_separators() {
+        let uri = TelUri::parse(b"tel:+1-415-555-1212").unwrap();
+
+        assert_eq!(uri.number, "+1-415-555-1212");
+        assert_eq!(uri.normalized_e164(), Some("+14155551212".to_string()));
+    }
+
+    #[test]
+    fn tel_uri_normalized_e164_ignores_params_and_local_numbers() {
+        let uri = TelUri::parse(b"tel:+1(415)555.1212;tsp=example.com").unwrap();
+        assert_eq!(uri.normalized_e164(), Some("+14155551212".to_string()));
+
+        let uri = TelUri::parse(b"tel:7042;phone-context=example.com").unwrap();
+        assert!(!uri.is_global());
+        assert_eq!(uri.normalized_e164(), None);
+    }
+
+    #[test]
+    fn sip_uri_parse_reads_user_host_and_params() {
+        let uri = SipUri::parse(b"sip:alice@atlanta.example.com;transport=tcp").unwrap();
+
+        assert!(!uri.secure);
+        assert_eq!(uri.user, Some("alice".to_string()));
+        assert_eq!(uri.password, None);
+        assert_eq!(uri.host, Host::Domain("atlanta.example.com".to_string()));
+        assert_eq!(uri.port, None);
+        assert_eq!(uri.params, vec![URIParam::Transport(Transport::TCP)]);
+        assert_eq!(uri.headers, vec![]);
+    }
+
+    #[test]
+    fn sip_uri_parse_reads_sips_port_and_headers() {
+        let uri = SipUri::parse(b"sips:bob:secret@biloxi.example.com:5061?subject=project").unwrap();
+
+        assert!(uri.secure);
+        assert_eq!(uri.user, Some("bob".to_string()));
+        assert_eq!(uri.password, Some("secret".to_string()));
+        assert_eq!(uri.host, Host::Domain("biloxi.example.com".to_string()));
+        assert_eq!(uri.port, Some(5061));
+        assert_eq!(uri.headers, vec![URIHeader {
+            name: "subject".to_string(),
+            value: "project".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn sip_uri_parse_rejects_trailing_data() {
+        assert!(SipUri::parse(b"sip:alice@atlanta.example.com>").is_err());
+        assert!(SipUri::parse(b"mailto:alice@atlanta.example.com").is_err());
+    }
+
+    #[test]
+    fn sip_uri_parse_reads_ip_hosts() {
+        let uri = SipUri::parse(b"sip:alice@192.0.2.4:5060").unwrap();
+        assert_eq!(uri.host, Host::IpV4(Ipv4Addr::new(192, 0, 2, 4)));
+
+        let uri = SipUri::parse(b"sip:alice@[2001:db8::10]").unwrap();
+        assert_eq!(uri.host, Host::IpV6("2001:db8::10".parse().unwrap()));
+        assert_eq!(uri.to_string(), "sip:alice@[2001:db8::10]");
+    }
+
+    #[test]
+    fn host_domains_compare_equal_with_or_without_the_trailing_dot() {
+        let dotted = SipUri::parse(b"sip:alice@atlanta.example.com.").unwrap();
+        let undotted = SipUri::parse(b"sip:alice@atlanta.example.com").unwrap();
+
+        assert_eq!(dotted, undotted);
+        assert_eq!(dotted.host.normalized_domain(), Some("atlanta.example.com"));
+        assert_eq!(dotted.to_string(), "sip:alice@atlanta.example.com.");
+        assert_ne!(undotted.host, Host::Domain("atlanta.example.org".to_string()));
+    }
+
+    #[test]
+    fn sip_uri_aor_eq_ignores_host_case_and_params() {
+        let uri = SipUri::parse(b"sip:alice@Example.COM;transport=tcp").unwrap();
+
+        assert!(uri.aor_eq(&SipUri::parse(b"sip:alice@example.com").unwrap()));
+        assert!(!uri.aor_eq(&SipUri::parse(b"sip:Alice@example.com").unwrap()));
+        assert!(!uri.aor_eq(&SipUri::parse(b"sips:alice@example.com").unwrap()));
+        assert!(!uri.aor_eq(&SipUri::parse(b"sip:alice@example.com:5060").unwrap()));
+
+        assert_eq!(uri.host.normalized().normalized_domain(), Some("example.com"));
+        assert_eq!(uri.to_string(), "sip:alice@Example.COM;transport=tcp");
+    }
+
+    #[test]
+    fn host_hashes_consistently_with_equality() {
+        use std::collections::HashSet;
+
+        let hosts: HashSet<Host> = vec![
+            Host::Domain("Example.COM.".to_string()),
+            Host::Domain("example.com".to_string()),
+            Host::Domain("example.org".to_string()),
+            Host::IpV4("192.0.2.1".parse().unwrap()),
+        ].into_iter().collect();
+
+        assert_eq!(hosts.len(), 3);
+        assert!(hosts.contains(&Host::Domain("EXAMPLE.com".to_string())));
+    }
+
+    #[test]
+    fn sip_uri_matches_equivalent_uris() {
+        let equivalent = [
+            ("sip:%61lice@atlanta.com;transport=TCP", "sip:alice@AtLanTa.CoM;Transport=tcp"),
+            ("sip:carol@chicago.com", "sip:carol@chicago.com;newparam=5"),
+            ("sip:carol@chicago.com", "sip:carol@chicago.com;security=on"),
+            ("sip:carol@chicago.com;security=on", "sip:carol@chicago.com;newparam=5"),
+            (
+                "sip:biloxi.com;transport=tcp;method=REGISTER?to=sip:bob%40biloxi.com",
+                "sip:biloxi.com;method=REGISTER;transport=tcp?to=sip:bob%40biloxi.com",
+            ),
+            (
+                "sip:alice@atlanta.com?subject=project%20x&priority=urgent",
+                "sip:alice@atlanta.com?priority=urgent&subject=project%20x",
+            ),
+        ];
+
+        for (a, b) in equivalent.iter() {
+            let (a, b) = (SipUri::parse(a.as_bytes()).unwrap(), SipUri::parse(b.as_bytes()).unwrap());
+            assert!(a.matches(&b), "{} should match {}", a, b);
+            assert!(b.matches(&a), "{} should match {}", b, a);
+        }
+    }
+
+    #[test]
+    fn sip_uri_matches_rejects_different_uris() {
+        let different = [
+            ("SIP:ALICE@AtLanTa.CoM;Transport=udp", "sip:alice@AtLanTa.CoM;Transport=UDP"),
+            ("sip:bob@biloxi.com", "sip:bob@biloxi.com:5060"),
+            ("sip:bob@biloxi.com", "sip:bob@biloxi.com;transport=udp"),
+            ("sip:bob@biloxi.com", "sip:bob@biloxi.com:6000;transport=tcp"),
+            ("sip:carol@chicago.com", "sip:carol@chicago.com?Subject=next%20meeting"),
+            ("sip:bob@phone21.boxesbybob.com", "sip:bob@192.0.2.4"),
+            ("sip:bob@biloxi.com;user=ip", "sip:bob@biloxi.com"),
+            ("sip:bob@biloxi.com;maddr=239.255.255.1", "sip:bob@biloxi.com"),
+            ("sip:bob@biloxi.com;ttl=1", "sip:bob@biloxi.com;ttl=2"),
+            ("sip:bob:secret@biloxi.com", "sip:bob@biloxi.com"),
+            ("sips:bob@biloxi.com", "sip:bob@biloxi.com"),
+        ];
+
+        for (a, b) in different.iter() {
+            let (a, b) = (SipUri::parse(a.as_bytes()).unwrap(), SipUri::parse(b.as_bytes()).unwrap());
+            assert!(!a.matches(&b), "{} shouldn't match {}", a, b);
+            assert!(!b.matches(&a), "{} shouldn't match {}", b, a);
+        }
+    }
+
+    #[test]
+    fn sip_uri_parse_decodes_user_info() {
+        let uri = SipUri::parse(b"sip:alice%40home:se%63ret@atlanta.example.com").unwrap();
+
+        assert_eq!(uri.user, Some("alice@home".to_string()));
+        assert_eq!(uri.password, Some("secret".to_string()));
+        assert_eq!(uri.to_string(), "sip:alice%40home:secret@atlanta.example.com");
+    }
+
+    #[test]
+    fn sip_uri_parse_decodes_params_and_headers() {
+        let uri = SipUri::parse(b"sip:alice@atlanta.example.com;foo=a%3Bb?subject=lunch%20break").unwrap();
+
+        assert_eq!(uri.params, vec![URIParam::Other("foo".to_string(), Some("a;b".to_string()))]);
+        assert_eq!(uri.headers, vec![URIHeader {
+            name: "subject".to_string(),
+            value: "lunch break".to_string(),
+        }]);
+        assert_eq!(uri.to_string(), "sip:alice@atlanta.example.com;foo=a%3Bb?subject=lunch%20break");
+    }
+
+    #[test]
+    fn sip_uri_to_string_round_trips() {
+        for uri in &[
+            "sip:alice@atlanta.example.com;transport=tcp",
+            "sips:bob:secret@biloxi.example.com:5061;lr;maddr=239.255.255.1?subject=project&priority=urgent",
+            "sip:+12125551212@gateway.example.com;user=phone;method=INVITE;ttl=15;foo=bar;baz",
+        ] {
+            assert_eq!(&SipUri::parse(uri.as_bytes()).unwrap().to_string(), uri);
+        }
+    }
+
+    #[test]
+    fn sip_uri_from_str() {
+        let uri: SipUri = "sip:alice@atlanta.example.com;transport=tcp".parse().unwrap();
+        assert_eq!(uri.host, Host::Domain("atlanta.example.com".to_string()));
+
+        assert_eq!("alice@atlanta.example.com".parse::<SipUri>(), Err(InvalidSipUriError));
+    }
+
+    #[test]
+    fn status_code_maps_known_codes() {
+        assert_eq!(StatusCode::from_u16(180), Some(StatusCode::Ringing));
+        assert_eq!(StatusCode::from_u16(606), Some(StatusCode::NotAcceptableAnywhere));
+        assert_eq!(StatusCode::Ringing.as_u16(), 180);
+    }
+
+    #[test]
+    fn status_code_keeps_unknown_codes() {
+        assert_eq!(StatusCode::from_u16(199), Some(StatusCode::Other(199)));
+        assert_eq!(StatusCode::Other(199).as_u16(), 199);
+    }
+
+    #[test]
+    fn status_code_rejects_out_of_range_codes() {
+        assert_eq!(StatusCode::from_u16(99), None);
+        assert_eq!(StatusCode::from_u16(700), None);
+    }
+
+    #[test]
+    fn status_code_class() {
+        assert_eq!(StatusCode::Trying.class(), Some(StatusClass::Provisional));
+        assert_eq!(StatusCode::OK.class(), Some(StatusClass::Success));
+        assert_eq!(StatusCode::MovedTemporarily.class(), Some(StatusClass::Redirection));
+        assert_eq!(StatusCode::Other(499).class(), Some(StatusClass::ClientError));
+        assert_eq!(StatusCode::ServiceUnavailable.class(), Some(StatusClass::ServerError));
+        assert_eq!(StatusCode::Decline.class(), Some(StatusClass::GlobalFailure));
+        assert_eq!(StatusCode::Other(42).class(), None);
+        assert_eq!(StatusCode::Other(700).class(), None);
+    }
+
+    #[test]
+    fn status_code_other_equals_the_variant_of_its_code() {
+        assert_eq!(StatusCode::Other(200), StatusCode::OK);
+        assert_eq!(StatusCode::Other(200).class(), Some(StatusClass::Success));
+        assert_ne!(StatusCode::Other(201), StatusCode::OK);
+        assert_eq!(StatusCode::from_u16(200), Some(StatusCode::OK));
+        assert!(matches!(StatusCode::from_u16(200), Some(StatusCode::OK)));
+    }
+
+    #[test]
+    fn method_from_str_recognizes_known_methods() {
+        assert_eq!("INVITE".parse::<Method>(), Ok(Method::Invite));
+        assert_eq!("ACK".parse::<Method>(), Ok(Method::Ack));
+        assert_eq!("REGISTER".parse::<Method>(), Ok(Method::Register));
+    }
+
+    #[test]
+    fn method_from_str_is_case_sensitive() {
+        assert_eq!("invite".parse::<Method>(), Ok(Method::Extension("invite".to_string())));
+    }
+
+    #[test]
+    fn method_from_str_falls_back_to_extension() {
+        assert_eq!("SUBSCRIBE".parse::<Method>(), Ok(Method::Extension("SUBSCRIBE".to_string())));
+    }
+
+    #[cfg(feature = "generate")]
+    #[test]
+    fn generated_identifiers_are_parsed_back() {
+        let tag = gen_tag();
+        let call_id = gen_call_id("atlanta.example.com");
+        assert_ne!(tag, gen_tag());
+        assert_eq!(tag.len(), 16);
+
+        let bytes = format!("BYE sip:bob@biloxi.example.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.example.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.example.com>;tag={}\r\n\
+From: Alice <sip:alice@atlanta.example.com>;tag={}\r\n\
+Call-ID: {}\r\n\
+CSeq: 231 BYE\r\n\
+Content-Length: 0\r\n\
+\r\n", gen_tag(), tag, call_id);
+        let req = crate::Request::parse(bytes.as_bytes()).unwrap();
+
+        assert_eq!(req.from.tag(), Some(tag.as_str()));
+        assert_eq!(req.call_id, call_id);
+    }
+
+    #[cfg(feature = "slog")]
+    #[test]
+    fn method_is_logged_in_its_wire_format() {
+        struct Capture(String);
+
+        impl slog::Serializer for Capture {
+            fn emit_arguments(&mut self, _key: slog::Key, val: &fmt::Arguments) -> slog::Result {
+                self.0 = val.to_string();
+                Ok(())
+            }
+        }
+
+        let mut capture = Capture(String::new());
+        slog::Value::serialize(
+            &Method::Ack,
+            &slog::record!(slog::Level::Info, "", &format_args!(""), slog::b!()),
+            "method",
+            &mut capture,
+        ).unwrap();
 
-        serializer.emit_str(key, method)
+        assert_eq!(capture.0, "ACK");
     }
 }